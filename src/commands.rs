@@ -0,0 +1,377 @@
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use validator::Validate;
+
+use crate::configs::{
+    Argon2Hash, Command, ConfigCommand, Database, DatabaseScheme, MigrateCommand, MultiHash, Password, PasswordChecker,
+    SchemaCommand, SchemaManager, Settings, TokenCommand, UserCommand,
+};
+use crate::entities::ADMIN_ROLE;
+use crate::errors::{ApiError, ConfigError, DatabaseError, ValidationError};
+use crate::payload::{AuditContextDto, AuditRecordDto, UserCreateDto, UserDto, UserIdentity, UserUpdateDto};
+use crate::repository::{AuditRepository, UserRepository};
+use crate::services::{AuditService, AuthService, TokenService, UserService};
+
+/// Actor recorded in the audit log for changes made from the command line.
+const CLI_ACTOR: &str = "cli";
+
+struct UserCommandContext {
+    auth_service: AuthService,
+    user_service: UserService,
+    audit_service: AuditService,
+}
+
+impl UserCommandContext {
+    async fn new(settings: &Arc<Settings>) -> Result<Self, ApiError> {
+        let database = Arc::new(Database::connect(settings).await?);
+        let hasher = Arc::new(MultiHash::with_primary(Argon2Hash::with_settings(settings)?)) as Arc<dyn Password>;
+        let policy = Arc::new(PasswordChecker::new(settings));
+
+        let user_repo = Arc::new(UserRepository::new(&hasher, &policy, &database));
+        let audit_repo = Arc::new(AuditRepository::new(&database));
+
+        Ok(Self {
+            auth_service: AuthService::new(&user_repo, &hasher),
+            user_service: UserService::new(&user_repo),
+            audit_service: AuditService::new(&audit_repo),
+        })
+    }
+
+    async fn audit(&self, action: &str, user: &UserDto) {
+        let context = AuditContextDto {
+            actor: Some(CLI_ACTOR.into()),
+            ..Default::default()
+        };
+        let record = AuditRecordDto {
+            action: action.into(),
+            success: true,
+            target: Some(UserIdentity::Id(user.id).to_string()),
+            diff: None,
+        };
+
+        self.audit_service.record(context, record).await;
+    }
+}
+
+/// Runs a one-off command against the loaded settings and returns what it prints.
+pub async fn run(command: &Command, settings: &Arc<Settings>) -> Result<String, ApiError> {
+    match command {
+        Command::Serve => Ok(String::new()),
+        Command::Config { command } => config(command, settings),
+        Command::Migrate { command } => migrate(command, settings).await,
+        Command::Schema { command } => schema(command, settings).await,
+        Command::User { command } => user(command, settings).await,
+        Command::Token { command } => token(command, settings).await,
+    }
+}
+
+fn config(command: &ConfigCommand, settings: &Settings) -> Result<String, ApiError> {
+    let ConfigCommand::Show { effective, origin } = command;
+    let mut output = String::new();
+
+    for (key, value) in settings.entries(*effective)? {
+        if *origin {
+            output.push_str(&format!("{key} = {value}  # {}\n", settings.origin(&key)));
+        } else {
+            output.push_str(&format!("{key} = {value}\n"));
+        }
+    }
+
+    Ok(output)
+}
+
+async fn migrate(command: &MigrateCommand, settings: &Arc<Settings>) -> Result<String, ApiError> {
+    let Some(migration_path) = &settings.database.migration_path else {
+        Err(ConfigError::InvalidValueError {
+            path: "$.database.migration_path".into(),
+            reason: "No migration directory is configured".into(),
+        })?
+    };
+
+    let database = Database::connect(settings).await?;
+
+    let output = match command {
+        MigrateCommand::Up => {
+            let versions = database.migrate().await?;
+
+            if versions.is_empty() {
+                "Already up to date\n".to_string()
+            } else {
+                versions.iter().map(|version| format!("Applied {version}\n")).collect()
+            }
+        }
+        MigrateCommand::Down { target } => {
+            let versions = database.revert(*target).await?;
+
+            if versions.is_empty() {
+                "Nothing to revert\n".to_string()
+            } else {
+                versions.iter().map(|version| format!("Reverted {version}\n")).collect()
+            }
+        }
+        MigrateCommand::Status => database
+            .migration_status()
+            .await?
+            .into_iter()
+            .map(|migration| {
+                let state = if migration.applied { "applied" } else { "pending" };
+                format!("{} {state} {}\n", migration.version, migration.description)
+            })
+            .collect(),
+        MigrateCommand::Generate { name, allow_drop } => {
+            generate_migration(&database, migration_path, name, *allow_drop).await?
+        }
+    };
+
+    Ok(output)
+}
+
+async fn generate_migration(
+    database: &Database,
+    migration_path: &str,
+    name: &str,
+    allow_drop: bool,
+) -> Result<String, ApiError> {
+    let migrations = database.migration_status().await?;
+    let pending = migrations.iter().filter(|migration| !migration.applied).count();
+
+    if pending > 0 {
+        // The diff would repeat whatever the pending migrations already change.
+        Err(ConfigError::InvalidValueError {
+            path: "$.database.migration_path".into(),
+            reason: format!("{pending} migration(s) are pending, apply them with `migrate up` first"),
+        })?
+    }
+
+    let schema_manager = SchemaManager::default();
+    let drift = database.schema_drift(&schema_manager).await?;
+    let migration = schema_manager.migration(&drift, &database.scheme, allow_drop)?;
+
+    if migration.is_empty() {
+        return Ok("The schema matches the managed tables, nothing to generate\n".to_string());
+    }
+
+    let latest = migrations.iter().map(|migration| migration.version).max().unwrap_or_default();
+    let version = migration_version(SystemTime::now()).max(latest + 1);
+    let name: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect();
+
+    let path = Path::new(migration_path).join(format!("{version}_{name}.sql"));
+    std::fs::write(&path, migration).map_err(ConfigError::from)?;
+
+    Ok(format!("Generated {}, review it before running `migrate up`\n", path.display()))
+}
+
+/// `YYYYMMDDHHMMSS` in UTC, the version format of `sqlx migrate add`.
+fn migration_version(now: SystemTime) -> i64 {
+    let seconds = now.duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_secs() as i64).unwrap_or_default();
+    let (days, time) = (seconds.div_euclid(86_400), seconds.rem_euclid(86_400));
+
+    // Civil date from the days since 1970-01-01, after Howard Hinnant's `civil_from_days`.
+    let shifted = days + 719_468;
+    let era = shifted.div_euclid(146_097);
+    let day_of_era = shifted.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    let date = (year * 100 + month) * 100 + day;
+    let time = (time / 3_600) * 10_000 + (time % 3_600 / 60) * 100 + time % 60;
+
+    date * 1_000_000 + time
+}
+
+async fn schema(command: &SchemaCommand, settings: &Arc<Settings>) -> Result<String, ApiError> {
+    let schema_manager = SchemaManager::default();
+
+    match command {
+        SchemaCommand::Print { dialect } => {
+            let scheme = dialect
+                .clone()
+                .unwrap_or_else(|| DatabaseScheme::from_url(settings.database.url.expose()));
+
+            let statements = schema_manager.create_schema(&scheme);
+
+            Ok(statements.iter().map(|statement| format!("{}\n\n", statement.trim())).collect())
+        }
+        SchemaCommand::Check => {
+            let drift = Database::connect(settings).await?.schema_drift(&schema_manager).await?;
+
+            if drift.is_empty() {
+                Ok("The schema matches the managed tables\n".to_string())
+            } else {
+                Err(DatabaseError::SchemaDriftError(drift.to_string()))?
+            }
+        }
+    }
+}
+
+async fn user(command: &UserCommand, settings: &Arc<Settings>) -> Result<String, ApiError> {
+    let context = UserCommandContext::new(settings).await?;
+
+    let (action, user) = match command {
+        UserCommand::Create { username, email, password, admin } => {
+            let data = UserCreateDto {
+                username: username.clone(),
+                email: email.clone(),
+                password: read_password(password)?,
+            };
+            data.validate().map_err(ValidationError::from)?;
+
+            let mut user = context.auth_service.create_user(data).await?;
+
+            if *admin {
+                user = context.user_service.grant_role(UserIdentity::Id(user.id), ADMIN_ROLE).await?;
+            }
+
+            ("users.create", user)
+        }
+        UserCommand::ResetPassword { user, password } => {
+            let data = UserUpdateDto {
+                username: None,
+                email: None,
+                password: Some(read_password(password)?),
+            };
+            data.validate().map_err(ValidationError::from)?;
+
+            ("users.password_reset", context.user_service.update_user(user.clone(), data).await?)
+        }
+        UserCommand::GrantRole { user, role } => {
+            ("users.role_granted", context.user_service.grant_role(user.clone(), role).await?)
+        }
+        UserCommand::Delete { user } => ("users.delete", context.user_service.delete_user(user.clone()).await?),
+    };
+
+    context.audit(action, &user).await;
+
+    Ok(format!("{}\n", serde_json::to_string_pretty(&user).unwrap_or_default()))
+}
+
+async fn token(command: &TokenCommand, settings: &Arc<Settings>) -> Result<String, ApiError> {
+    let TokenCommand::Issue { user } = command;
+    let context = UserCommandContext::new(settings).await?;
+
+    let user = context.user_service.find_user(user.clone()).await?;
+    let token = TokenService::new(settings).generate_token(user.clone())?;
+
+    context.audit("tokens.issue", &user).await;
+
+    Ok(format!("{}\n", token.token))
+}
+
+/// The given password, or the first line of stdin so it stays out of the shell history.
+fn read_password(password: &Option<String>) -> Result<String, ConfigError> {
+    if let Some(password) = password {
+        return Ok(password.clone());
+    }
+
+    let mut line = String::new();
+    io::stdin().read_line(&mut line)?;
+
+    let password = line.trim_end_matches(['\n', '\r']);
+    if password.is_empty() {
+        Err(ConfigError::InvalidValueError {
+            path: "--password".into(),
+            reason: "No password given and none could be read from stdin".into(),
+        })
+    } else {
+        Ok(password.to_string())
+    }
+}
+
+#[cfg(test)]
+mod commands_tests {
+    use clap::Parser;
+
+    use crate::configs::Cli;
+    use super::*;
+
+    async fn run_cli(args: &[&str]) -> Result<String, ApiError> {
+        let cli = Cli::parse_from(["smarinth"].iter().chain(args));
+        let settings = Arc::new(cli.settings()?);
+
+        run(cli.command.as_ref().unwrap(), &settings).await
+    }
+
+    #[test]
+    fn test_config_show() {
+        let cli = Cli::parse_from(["smarinth", "config", "show", "--origin", "--set", "server.port=9292"]);
+        let settings = cli.settings().unwrap();
+        let Some(Command::Config { command }) = &cli.command else { panic!("Should parse the config command.") };
+
+        let output = config(command, &settings).unwrap();
+
+        assert!(output.contains("server.port = 9292  # cli:--set"));
+        assert!(!output.contains("server.host"), "Defaults should only be printed with --effective.");
+
+        let output = config(&ConfigCommand::Show { effective: true, origin: false }, &settings).unwrap();
+
+        assert!(output.contains("server.host = "));
+        assert!(output.contains("auth.secret = \"[redacted]\""));
+    }
+
+    #[test]
+    fn test_migration_version() {
+        let at = |seconds| migration_version(UNIX_EPOCH + std::time::Duration::from_secs(seconds));
+
+        assert_eq!(at(0), 19700101000000);
+        assert_eq!(at(946684799), 19991231235959);
+        assert_eq!(at(1709210096), 20240229123456);
+    }
+
+    #[tokio::test]
+    async fn test_bootstrap_admin_and_issue_token() {
+        let settings = Arc::new(Settings::new().unwrap());
+        let _database = Database::new(&settings, &SchemaManager::default()).await.unwrap();
+
+        let output = run_cli(&[
+            "user", "create", "--username", "test_cli_admin", "--email", "test_cli_admin@sieluna.com",
+            "--password", "test_cli_password", "--admin",
+        ]).await.unwrap();
+        let user: UserDto = serde_json::from_str(&output).unwrap();
+
+        assert!(user.is_admin(), "--admin should grant the admin role.");
+
+        let token = run_cli(&["token", "issue", "--user", "username:test_cli_admin"]).await.unwrap();
+        let claims = TokenService::new(&settings).retrieve_token_claims(token.trim()).unwrap();
+
+        assert_eq!(claims.claims.sub, user.id.to_string());
+
+        let output = run_cli(&["user", "grant-role", "test_cli_admin@sieluna.com", "user"]).await.unwrap();
+
+        assert!(!serde_json::from_str::<UserDto>(&output).unwrap().is_admin());
+        assert!(run_cli(&["user", "delete", &format!("id:{}", user.id)]).await.is_ok());
+        assert!(run_cli(&["token", "issue", "--user", "username:test_cli_admin"]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_create_user_validates_fields() {
+        let settings = Arc::new(Settings::new().unwrap());
+        let _database = Database::new(&settings, &SchemaManager::default()).await.unwrap();
+
+        let result = run_cli(&[
+            "user", "create", "--username", "test cli user", "--email", "not-an-email", "--password", "test_cli_password",
+        ]).await;
+
+        assert!(
+            matches!(result, Err(ApiError::ValidationError(ValidationError::InvalidFields(fields))) if fields.len() == 2),
+            "The CLI should apply the same rules as registration."
+        );
+    }
+
+    #[tokio::test]
+    async fn test_schema_print() {
+        let settings = Arc::new(Settings::new().unwrap());
+        let output = schema(&SchemaCommand::Print { dialect: Some(DatabaseScheme::POSTGRES) }, &settings).await;
+
+        assert!(output.unwrap().contains("CREATE TABLE"));
+    }
+}
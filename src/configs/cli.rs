@@ -0,0 +1,149 @@
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+
+use crate::configs::{DatabaseScheme, Settings};
+use crate::entities::{ADMIN_ROLE, USER_ROLE};
+use crate::errors::ConfigError;
+use crate::payload::UserIdentity;
+
+#[derive(Debug, Clone, Parser)]
+#[command(version, about)]
+pub struct Cli {
+    /// Extra TOML file merged over `configs/{RUN_MODE}.toml`; repeatable, later files win.
+    #[arg(long = "config", value_name = "PATH", global = true)]
+    pub config: Vec<PathBuf>,
+
+    /// Overrides one key after every file and environment variable, e.g. `--set database.url=...`.
+    #[arg(long = "set", value_name = "KEY=VALUE", global = true)]
+    pub set: Vec<String>,
+
+    /// Runs a one-off command instead of the server.
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum Command {
+    /// Starts the server, the default without a command.
+    Serve,
+    /// Inspects the loaded configuration.
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+    /// Applies or reverts the migrations in `database.migration_path`.
+    Migrate {
+        #[command(subcommand)]
+        command: MigrateCommand,
+    },
+    /// Prints the schema the server creates on a clean start.
+    Schema {
+        #[command(subcommand)]
+        command: SchemaCommand,
+    },
+    /// Manages user accounts without going through the API.
+    User {
+        #[command(subcommand)]
+        command: UserCommand,
+    },
+    /// Issues access tokens.
+    Token {
+        #[command(subcommand)]
+        command: TokenCommand,
+    },
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum ConfigCommand {
+    /// Prints the merged settings as dotted keys, with secrets redacted.
+    Show {
+        /// Prints every key, not only those overriding the defaults.
+        #[arg(long)]
+        effective: bool,
+        /// Annotates each key with the layer that supplied it.
+        #[arg(long)]
+        origin: bool,
+    },
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum MigrateCommand {
+    /// Applies every pending migration.
+    Up,
+    /// Reverts the latest migration, or every migration newer than `--target`.
+    Down {
+        #[arg(long, value_name = "VERSION")]
+        target: Option<i64>,
+    },
+    /// Lists the migrations and whether they have been applied.
+    Status,
+    /// Writes a migration bringing the live database to the managed tables, for review before `migrate up`.
+    Generate {
+        /// Describes the migration in its file name.
+        #[arg(long, default_value = "schema")]
+        name: String,
+        /// Keeps statements dropping undeclared tables and columns, which are commented out otherwise.
+        #[arg(long)]
+        allow_drop: bool,
+    },
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum SchemaCommand {
+    /// Prints the `CREATE TABLE` statements, for the configured database unless `--dialect` is given.
+    Print {
+        #[arg(long)]
+        dialect: Option<DatabaseScheme>,
+    },
+    /// Compares the managed tables with the configured database, failing on drift.
+    Check,
+}
+
+/// Users are given as `id:1`, `username:alice`, `email:alice@example.com` or a bare username or email.
+/// Passwords are read from the first line of stdin when `--password` is omitted.
+#[derive(Debug, Clone, Subcommand)]
+pub enum UserCommand {
+    /// Creates a user, optionally with the admin role.
+    Create {
+        #[arg(long)]
+        username: String,
+        #[arg(long)]
+        email: String,
+        #[arg(long)]
+        password: Option<String>,
+        #[arg(long)]
+        admin: bool,
+    },
+    /// Replaces a user's password.
+    ResetPassword {
+        user: UserIdentity,
+        #[arg(long)]
+        password: Option<String>,
+    },
+    /// Sets a user's role.
+    GrantRole {
+        user: UserIdentity,
+        #[arg(value_parser = [ADMIN_ROLE, USER_ROLE])]
+        role: String,
+    },
+    /// Deletes a user.
+    Delete {
+        user: UserIdentity,
+    },
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum TokenCommand {
+    /// Prints an access token for a user.
+    Issue {
+        #[arg(long)]
+        user: UserIdentity,
+    },
+}
+
+impl Cli {
+    pub fn settings(&self) -> Result<Settings, ConfigError> {
+        Settings::load(&self.config, &self.set)
+    }
+}
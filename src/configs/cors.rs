@@ -0,0 +1,119 @@
+use ntex::http::header::HeaderName;
+use ntex::http::Method;
+use url::Url;
+
+use crate::configs::Cors;
+use crate::errors::ConfigError;
+
+pub enum AllowedOrigin {
+    Any,
+    Exact(String),
+    /// `scheme://*.domain[:port]`, split around the `*`; matches any subdomain but not the domain itself.
+    Subdomain { prefix: String, suffix: String },
+}
+
+impl AllowedOrigin {
+    fn parse(origin: &str, path: &str) -> Result<Self, ConfigError> {
+        if origin == "*" {
+            return Ok(AllowedOrigin::Any);
+        }
+
+        let invalid = |reason: &str| ConfigError::InvalidValueError {
+            path: path.to_string(),
+            reason: format!("'{origin}' {reason}"),
+        };
+
+        let origin = origin.to_ascii_lowercase();
+        let (candidate, wildcard) = match origin.split_once("://*.") {
+            Some((scheme, domain)) => (format!("{scheme}://wildcard.{domain}"), Some((scheme, domain))),
+            None => (origin.clone(), None),
+        };
+
+        let url = Url::parse(&candidate).map_err(|_| invalid("is not a valid origin"))?;
+        if url.origin().ascii_serialization() != candidate {
+            return Err(invalid("must be scheme://host[:port] without a path or trailing slash"));
+        }
+
+        Ok(match wildcard {
+            Some((scheme, domain)) => AllowedOrigin::Subdomain {
+                prefix: format!("{scheme}://"),
+                suffix: format!(".{domain}"),
+            },
+            None => AllowedOrigin::Exact(origin),
+        })
+    }
+
+    pub fn matches(&self, origin: &str) -> bool {
+        match self {
+            AllowedOrigin::Any => true,
+            AllowedOrigin::Exact(allowed) => allowed == origin,
+            AllowedOrigin::Subdomain { prefix, suffix } => origin
+                .strip_prefix(prefix.as_str())
+                .and_then(|rest| rest.strip_suffix(suffix.as_str()))
+                .is_some_and(|subdomain| {
+                    !subdomain.is_empty()
+                        && !subdomain.starts_with('.')
+                        && !subdomain.ends_with('.')
+                        && subdomain.bytes().all(|byte| byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'.')
+                }),
+        }
+    }
+}
+
+/// `[server.cors]` with every entry parsed.
+pub struct CorsRules {
+    pub origins: Vec<AllowedOrigin>,
+    pub methods: Vec<Method>,
+    pub headers: Vec<HeaderName>,
+    pub exposed_headers: Vec<HeaderName>,
+}
+
+impl Cors {
+    pub fn parse(&self) -> Result<CorsRules, ConfigError> {
+        let origins = self
+            .allowed_origins
+            .iter()
+            .enumerate()
+            .map(|(index, origin)| AllowedOrigin::parse(origin, &format!("$.server.cors.allowed_origins[{index}]")))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if self.allow_credentials && origins.iter().any(|origin| matches!(origin, AllowedOrigin::Any)) {
+            return Err(ConfigError::InvalidValueError {
+                path: "$.server.cors.allowed_origins".into(),
+                reason: "Credentials cannot be allowed for every origin, list the trusted origins instead".into(),
+            });
+        }
+
+        let methods = self
+            .allowed_methods
+            .iter()
+            .enumerate()
+            .map(|(index, method)| {
+                Method::from_bytes(method.to_ascii_uppercase().as_bytes()).map_err(|_| ConfigError::InvalidValueError {
+                    path: format!("$.server.cors.allowed_methods[{index}]"),
+                    reason: format!("'{method}' is not an HTTP method"),
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let header_names = |field: &str, names: &[String]| {
+            names
+                .iter()
+                .enumerate()
+                .map(|(index, name)| {
+                    HeaderName::from_bytes(name.as_bytes()).map_err(|_| ConfigError::InvalidValueError {
+                        path: format!("$.server.cors.{field}[{index}]"),
+                        reason: format!("'{name}' is not a header name"),
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()
+        };
+
+        Ok(CorsRules {
+            origins,
+            methods,
+            headers: header_names("allowed_headers", &self.allowed_headers)?,
+            exposed_headers: header_names("exposed_headers", &self.exposed_headers)?,
+        })
+    }
+}
@@ -3,12 +3,12 @@ use std::str::FromStr;
 use std::sync::Arc;
 
 use sqlx::any::{Any, AnyConnectOptions, AnyPoolOptions};
-use sqlx::migrate::{MigrateDatabase, Migrator};
+use sqlx::migrate::{Migrate, MigrateDatabase, Migrator};
 use sqlx::{AnyPool, ConnectOptions, Connection};
 
 use crate::errors::DatabaseError;
-use super::schema::SchemaManager;
-use super::settings::Settings;
+use super::schema::{LiveColumn, LiveIndex, LiveTable, SchemaDrift, SchemaManager};
+use super::settings::{SchemaMode, Settings};
 
 #[macro_export]
 macro_rules! sql {
@@ -42,29 +42,94 @@ macro_rules! sql {
     }};
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, clap::ValueEnum)]
+#[value(rename_all = "lower")]
 pub enum DatabaseScheme {
     POSTGRES,
     SQLITE,
     MYSQL,
 }
 
+impl DatabaseScheme {
+    /// URL schemes the sqlx drivers accept, aliases included.
+    pub const URL_SCHEMES: [(&'static str, DatabaseScheme); 5] = [
+        ("sqlite", DatabaseScheme::SQLITE),
+        ("postgres", DatabaseScheme::POSTGRES),
+        ("postgresql", DatabaseScheme::POSTGRES),
+        ("mysql", DatabaseScheme::MYSQL),
+        ("mariadb", DatabaseScheme::MYSQL),
+    ];
+
+    pub fn parse(url: &str) -> Option<Self> {
+        let (scheme, _) = url.split_once(':')?;
+
+        Self::URL_SCHEMES.iter().find(|(name, _)| *name == scheme).map(|(_, dialect)| dialect.clone())
+    }
+
+    pub fn from_url(url: &str) -> Self {
+        Self::parse(url).unwrap_or(DatabaseScheme::SQLITE)
+    }
+}
+
+/// A configured migration and whether it has been applied.
+#[derive(Debug, Clone)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    pub applied: bool,
+}
+
 #[derive(Debug, Clone)]
 pub struct Database {
     pub scheme: DatabaseScheme,
     pub pool: AnyPool,
+    migrator: Option<Arc<Migrator>>,
 }
 
 impl Database {
     pub async fn new(settings: &Arc<Settings>, schema_manager: &SchemaManager) -> Result<Self, DatabaseError> {
-        let db_url = settings.database.url.clone();
-        let db_options = AnyConnectOptions::from_str(&db_url)?;
-        let db_scheme = match db_options.database_url.scheme() {
-            "postgres" => DatabaseScheme::POSTGRES,
-            "mysql" => DatabaseScheme::MYSQL,
-            _ => DatabaseScheme::SQLITE,
+        let database = Self::connect(settings).await?;
+
+        let statements = match settings.database.schema_mode {
+            SchemaMode::None => Vec::new(),
+            SchemaMode::CreateIfMissing => schema_manager.create_schema(&database.scheme),
+            SchemaMode::Recreate => {
+                sqlx::query("DROP TABLE IF EXISTS _sqlx_migrations")
+                    .execute(&database.pool)
+                    .await?;
+
+                tracing::warn!("perform a clean boot: clean and recreate schema");
+
+                [schema_manager.dispose_schema(), schema_manager.create_schema(&database.scheme)].concat()
+            }
         };
 
+        for statement in statements.iter() {
+            sqlx::query(&statement)
+                .execute(&database.pool)
+                .await?;
+        }
+
+        if database.migrator.is_some() {
+            database.migrate().await?;
+
+            tracing::info!("database migration success");
+        }
+
+        let drift = database.schema_drift(schema_manager).await?;
+        if !drift.is_empty() {
+            tracing::warn!(%drift, "database schema differs from the managed tables");
+        }
+
+        Ok(database)
+    }
+
+    /// Connects, creating the database if needed, without touching the schema or running migrations.
+    pub async fn connect(settings: &Arc<Settings>) -> Result<Self, DatabaseError> {
+        let db_url = settings.database.url.expose().to_string();
+        let db_options = AnyConnectOptions::from_str(&db_url)?;
+        let db_scheme = DatabaseScheme::from_url(&db_url);
+
         sqlx::any::install_default_drivers();
 
         match db_options.connect().await {
@@ -74,36 +139,187 @@ impl Database {
 
         let pool = AnyPoolOptions::new().connect_with(db_options).await?;
 
-        if settings.database.clean_start {
-            let dispose_statements = schema_manager.dispose_schema();
-            let create_statements = schema_manager.create_schema(&db_scheme);
-            let statements = [&dispose_statements[..], &create_statements[..]].concat();
+        let mut migrator = None;
+
+        if let Some(migration_path) = settings.database.migration_path.clone() {
+            migrator = Some(Arc::new(Migrator::new(Path::new(&migration_path)).await?));
+        }
+
+        Ok(Self {
+            scheme: db_scheme,
+            pool,
+            migrator,
+        })
+    }
+
+    /// Applies every pending migration, returning their versions.
+    pub async fn migrate(&self) -> Result<Vec<i64>, DatabaseError> {
+        let Some(migrator) = &self.migrator else {
+            return Ok(Vec::new());
+        };
+
+        let pending: Vec<_> = self.migration_status().await?
+            .into_iter()
+            .filter(|migration| !migration.applied)
+            .map(|migration| migration.version)
+            .collect();
+
+        let mut pool_connection = self.pool.acquire().await?;
+        migrator.run(&mut pool_connection).await?;
+
+        Ok(pending)
+    }
+
+    /// Reverts applied migrations newer than `target`, by default only the latest one, returning their versions.
+    pub async fn revert(&self, target: Option<i64>) -> Result<Vec<i64>, DatabaseError> {
+        let Some(migrator) = &self.migrator else {
+            return Ok(Vec::new());
+        };
+
+        let mut applied: Vec<_> = self.migration_status().await?
+            .into_iter()
+            .filter(|migration| migration.applied)
+            .map(|migration| migration.version)
+            .collect();
+        let target = target.unwrap_or_else(|| applied.iter().rev().nth(1).copied().unwrap_or(0));
+        applied.retain(|version| *version > target);
 
-            sqlx::query("DROP TABLE IF EXISTS _sqlx_migrations")
-                .execute(&pool)
+        let mut pool_connection = self.pool.acquire().await?;
+        migrator.undo(&mut pool_connection, target).await?;
+
+        Ok(applied.into_iter().rev().collect())
+    }
+
+    pub async fn migration_status(&self) -> Result<Vec<MigrationStatus>, DatabaseError> {
+        let Some(migrator) = &self.migrator else {
+            return Ok(Vec::new());
+        };
+
+        let mut pool_connection = self.pool.acquire().await?;
+        pool_connection.ensure_migrations_table().await?;
+        let applied: Vec<i64> = pool_connection.list_applied_migrations().await?
+            .into_iter()
+            .map(|migration| migration.version)
+            .collect();
+        // A migration that failed halfway is recorded but not applied.
+        let dirty = pool_connection.dirty_version().await?;
+
+        Ok(migrator
+            .iter()
+            .filter(|migration| !migration.migration_type.is_down_migration())
+            .map(|migration| MigrationStatus {
+                version: migration.version,
+                description: migration.description.to_string(),
+                applied: applied.contains(&migration.version) && dirty != Some(migration.version),
+            })
+            .collect())
+    }
+
+    pub async fn table_names(&self) -> Result<Vec<String>, DatabaseError> {
+        let statement = match self.scheme {
+            DatabaseScheme::POSTGRES => {
+                "SELECT CAST(table_name AS TEXT) FROM information_schema.tables WHERE table_schema = current_schema()"
+            }
+            DatabaseScheme::MYSQL => {
+                "SELECT CAST(table_name AS CHAR) FROM information_schema.tables WHERE table_schema = DATABASE()"
+            }
+            DatabaseScheme::SQLITE => "SELECT name FROM sqlite_master WHERE type = 'table'",
+        };
+
+        Ok(sqlx::query_scalar(statement).fetch_all(&self.pool).await?)
+    }
+
+    /// Columns and indexes of every table; indexes backing constraints are left out.
+    pub async fn live_schema(&self) -> Result<Vec<LiveTable>, DatabaseError> {
+        let (columns_statement, indexes_statement) = match self.scheme {
+            DatabaseScheme::POSTGRES => (
+                "SELECT CAST(a.attname AS TEXT), CAST(format_type(a.atttypid, a.atttypmod) AS TEXT), \
+                 CAST(CASE WHEN a.attnotnull THEN 1 ELSE 0 END AS BIGINT) \
+                 FROM pg_attribute a \
+                 WHERE a.attrelid = CAST($1 AS regclass) AND a.attnum > 0 AND NOT a.attisdropped \
+                 ORDER BY a.attnum",
+                "SELECT CAST(i.relname AS TEXT), CAST(CASE WHEN x.indisunique THEN 1 ELSE 0 END AS BIGINT), \
+                 CAST(a.attname AS TEXT) \
+                 FROM pg_index x \
+                 JOIN pg_class i ON i.oid = x.indexrelid \
+                 CROSS JOIN LATERAL unnest(x.indkey) WITH ORDINALITY AS k(attnum, position) \
+                 JOIN pg_attribute a ON a.attrelid = x.indrelid AND a.attnum = k.attnum \
+                 WHERE x.indrelid = CAST($1 AS regclass) \
+                 AND NOT EXISTS (SELECT 1 FROM pg_constraint c WHERE c.conindid = x.indexrelid) \
+                 ORDER BY i.relname, k.position",
+            ),
+            DatabaseScheme::MYSQL => (
+                "SELECT CAST(column_name AS CHAR), CAST(column_type AS CHAR), CAST(is_nullable = 'NO' AS SIGNED) \
+                 FROM information_schema.columns \
+                 WHERE table_schema = DATABASE() AND table_name = $1 \
+                 ORDER BY ordinal_position",
+                "SELECT CAST(s.index_name AS CHAR), CAST(s.non_unique = 0 AS SIGNED), CAST(s.column_name AS CHAR) \
+                 FROM information_schema.statistics s \
+                 LEFT JOIN information_schema.table_constraints c ON c.table_schema = s.table_schema \
+                 AND c.table_name = s.table_name AND c.constraint_name = s.index_name \
+                 WHERE s.table_schema = DATABASE() AND s.table_name = $1 AND c.constraint_name IS NULL \
+                 ORDER BY s.index_name, s.seq_in_index",
+            ),
+            DatabaseScheme::SQLITE => (
+                "SELECT name, type, \"notnull\" FROM pragma_table_info($1) ORDER BY cid",
+                "SELECT il.name, il.\"unique\", ii.name \
+                 FROM pragma_index_list($1) il, pragma_index_info(il.name) ii \
+                 WHERE il.origin = 'c' \
+                 ORDER BY il.name, ii.seqno",
+            ),
+        };
+        let columns_statement = sql!(self.scheme, columns_statement);
+        let indexes_statement = sql!(self.scheme, indexes_statement);
+
+        let mut tables = Vec::new();
+
+        for name in self.table_names().await? {
+            let columns = sqlx::query_as::<_, (String, String, i64)>(&columns_statement)
+                .bind(&name)
+                .fetch_all(&self.pool)
+                .await?
+                .into_iter()
+                .map(|(name, data_type, not_null)| LiveColumn { name, data_type, nullable: not_null == 0 })
+                .collect();
+
+            let index_columns = sqlx::query_as::<_, (String, i64, String)>(&indexes_statement)
+                .bind(&name)
+                .fetch_all(&self.pool)
                 .await?;
 
-            for statement in statements.iter() {
-                sqlx::query(&statement)
-                    .execute(&pool)
-                    .await?;
+            let mut indexes: Vec<LiveIndex> = Vec::new();
+            for (index, unique, column) in index_columns {
+                match indexes.last_mut() {
+                    Some(last) if last.name == index => last.columns.push(column),
+                    _ => indexes.push(LiveIndex { name: index, columns: vec![column], unique: unique != 0 }),
+                }
             }
 
-            tracing::warn!("perform a clean boot: clean and recreate schema");
+            tables.push(LiveTable { name, columns, indexes });
         }
 
-        if let Some(migration_path) = settings.database.migration_path.clone() {
-            let mut pool_connection = pool.acquire().await?;
-            let migrator = Migrator::new(Path::new(&migration_path)).await?;
-            migrator.run(&mut pool_connection).await?;
+        Ok(tables)
+    }
 
-            tracing::info!("database migration success");
-        }
+    pub async fn schema_drift(&self, schema_manager: &SchemaManager) -> Result<SchemaDrift, DatabaseError> {
+        Ok(schema_manager.drift(&self.live_schema().await?, &self.scheme))
+    }
 
-        Ok(Self {
-            scheme: db_scheme,
-            pool,
-        })
+    pub async fn ping(&self) -> Result<(), DatabaseError> {
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
+
+        Ok(())
+    }
+
+    /// Versions of the configured migrations that have not been applied successfully.
+    pub async fn pending_migrations(&self) -> Result<Vec<i64>, DatabaseError> {
+        Ok(self
+            .migration_status()
+            .await?
+            .into_iter()
+            .filter(|migration| !migration.applied)
+            .map(|migration| migration.version)
+            .collect())
     }
 }
 
@@ -111,6 +327,13 @@ impl Database {
 mod database_tests {
     use super::*;
 
+    #[test]
+    fn test_scheme_aliases() {
+        assert!(matches!(DatabaseScheme::from_url("postgresql://db/smarinth"), DatabaseScheme::POSTGRES));
+        assert!(matches!(DatabaseScheme::from_url("mariadb://db/smarinth"), DatabaseScheme::MYSQL));
+        assert!(DatabaseScheme::parse("oracle://db/smarinth").is_none());
+    }
+
     #[test]
     fn test_replace_under_not_postgres() {
         let query = "SELECT * FROM users WHERE id = $1";
@@ -146,4 +369,57 @@ mod database_tests {
 
         assert_eq!(actual, expected);
     }
+
+    #[tokio::test]
+    async fn test_report_and_create_missing_tables() {
+        let overrides = [
+            "database.url=sqlite:file:smarinth_drift?mode=memory&cache=shared".to_string(),
+            "database.schema_mode=none".to_string(),
+        ];
+        let settings = Settings::load::<&str>(&[], &overrides).unwrap();
+        let schema_manager = SchemaManager::default();
+
+        let database = Database::new(&Arc::new(settings.clone()), &schema_manager).await.unwrap();
+        let drift = database.schema_drift(&schema_manager).await.unwrap();
+
+        assert!(drift.missing_tables.contains(&"users"), "Mode none should leave the database alone.");
+
+        let mut settings = settings;
+        settings.database.schema_mode = SchemaMode::CreateIfMissing;
+        let database = Database::new(&Arc::new(settings), &schema_manager).await.unwrap();
+
+        assert!(database.schema_drift(&schema_manager).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_apply_generated_sqlite_rebuild() {
+        let overrides = [
+            "database.url=sqlite:file:smarinth_generate?mode=memory&cache=shared".to_string(),
+            "database.schema_mode=none".to_string(),
+        ];
+        let settings = Settings::load::<&str>(&[], &overrides).unwrap();
+        let schema_manager = SchemaManager::default();
+        let database = Database::new(&Arc::new(settings), &schema_manager).await.unwrap();
+
+        sqlx::raw_sql(
+            "CREATE TABLE oauth_clients (id INTEGER PRIMARY KEY AUTOINCREMENT, client_id VARCHAR(64) NOT NULL UNIQUE, \
+             name VARCHAR(255) NOT NULL, redirect_uris VARCHAR(2048) NOT NULL, scopes VARCHAR(2048) NOT NULL, \
+             grant_types VARCHAR(255) NOT NULL, legacy TEXT); \
+             INSERT INTO oauth_clients (client_id, name, redirect_uris, scopes, grant_types) \
+             VALUES ('test_rebuild_client', 'Rebuild', '', '', 'client_credentials');",
+        ).execute(&database.pool).await.unwrap();
+
+        let drift = database.schema_drift(&schema_manager).await.unwrap();
+        let migration = schema_manager.migration(&drift, &database.scheme, true).unwrap();
+
+        sqlx::raw_sql(&migration).execute(&database.pool).await.unwrap();
+
+        let client_id: String = sqlx::query_scalar("SELECT client_id FROM oauth_clients")
+            .fetch_one(&database.pool)
+            .await
+            .unwrap();
+
+        assert_eq!(client_id, "test_rebuild_client", "Rebuilding should keep the existing rows.");
+        assert!(database.schema_drift(&schema_manager).await.unwrap().is_empty());
+    }
 }
@@ -1,9 +1,23 @@
+mod cli;
+mod cors;
 mod database;
+mod origin;
 mod password;
+mod policy;
+mod reload;
 mod schema;
+mod secret;
 mod settings;
+mod telemetry;
 
-pub use database::{Database, DatabaseScheme};
-pub use password::{Argon2Hash, Password};
-pub use schema::SchemaManager;
-pub use settings::Settings;
+pub use cli::{Cli, Command, ConfigCommand, MigrateCommand, SchemaCommand, TokenCommand, UserCommand};
+pub use cors::{AllowedOrigin, CorsRules};
+pub use database::{Database, DatabaseScheme, MigrationStatus};
+pub use origin::ConfigOrigin;
+pub use password::{Argon2Hash, MultiHash, Password};
+pub use policy::PasswordChecker;
+pub use reload::{LogFilter, Reloadable, SettingsReloader};
+pub use schema::{SchemaDrift, SchemaManager};
+pub use secret::Secret;
+pub use settings::{Cors, LogFormat, OidcProvider, RateLimit, RateLimitKey, RateLimitPolicy, RateLimitStore, SchemaMode, Settings, ENV_PREFIX};
+pub use telemetry::TraceExporter;
@@ -0,0 +1,38 @@
+use std::fmt;
+use std::path::PathBuf;
+
+use serde::{Serialize, Serializer};
+
+/// The layer that supplied a setting, lowest precedence first.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ConfigOrigin {
+    /// The embedded `default.toml` or a serde default.
+    #[default]
+    Default,
+    /// `configs/{RUN_MODE}.toml`.
+    RunMode(PathBuf),
+    /// A file passed with `--config`.
+    File(PathBuf),
+    /// A `SMARINTH__*` environment variable.
+    Env(String),
+    /// A `--set` flag.
+    Cli,
+}
+
+impl fmt::Display for ConfigOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigOrigin::Default => write!(f, "default"),
+            ConfigOrigin::RunMode(path) => write!(f, "run_mode:{}", path.display()),
+            ConfigOrigin::File(path) => write!(f, "file:{}", path.display()),
+            ConfigOrigin::Env(name) => write!(f, "env:{name}"),
+            ConfigOrigin::Cli => write!(f, "cli:--set"),
+        }
+    }
+}
+
+impl Serialize for ConfigOrigin {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
@@ -1,7 +1,16 @@
-use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
+use std::str::FromStr;
+
+use argon2::{Algorithm, Argon2, Params, PasswordHash, PasswordHasher, PasswordVerifier, Version};
 use argon2::password_hash::{rand_core, SaltString};
+use base64::engine::general_purpose::{STANDARD, STANDARD_NO_PAD};
+use base64::Engine;
+use pbkdf2::{pbkdf2_hmac, Pbkdf2};
+use scrypt::Scrypt;
+use sha1::Sha1;
+use sha2::{Sha256, Sha512};
 
-use crate::errors::AuthError;
+use crate::errors::{AuthError, ConfigError};
+use super::settings::Settings;
 
 pub trait Password: Send + Sync {
     fn new() -> Self where Self: Sized;
@@ -9,19 +18,76 @@ pub trait Password: Send + Sync {
     fn hash(&self, password: &str) -> Result<String, AuthError>;
 
     fn verify(&self, password: &str, password_hash: &str) -> Result<bool, AuthError>;
+
+    /// Whether a stored hash was produced with anything other than the current parameters.
+    fn needs_rehash(&self, _password_hash: &str) -> bool {
+        false
+    }
+
+    /// Whether a stored hash is in a format this hasher is able to verify.
+    fn supports(&self, password_hash: &str) -> bool {
+        PasswordHash::new(password_hash).is_ok()
+    }
 }
 
 #[derive(Debug, Clone)]
-pub struct Argon2Hash(Argon2<'static>);
+pub struct Argon2Hash {
+    algorithm: Algorithm,
+    version: Version,
+    params: Params,
+    pepper: Option<Vec<u8>>,
+}
+
+impl Argon2Hash {
+    pub fn with_settings(settings: &Settings) -> Result<Self, ConfigError> {
+        let config = &settings.auth.argon2;
+
+        let algorithm = Algorithm::new(&config.algorithm).map_err(|e| ConfigError::InvalidValueError {
+            path: "$.auth.argon2.algorithm".into(),
+            reason: e.to_string(),
+        })?;
+        let version = Version::try_from(config.version).map_err(|e| ConfigError::InvalidValueError {
+            path: "$.auth.argon2.version".into(),
+            reason: e.to_string(),
+        })?;
+        let params = Params::new(config.memory_cost, config.time_cost, config.parallelism, None)
+            .map_err(|e| ConfigError::InvalidValueError {
+                path: "$.auth.argon2".into(),
+                reason: e.to_string(),
+            })?;
+        let pepper = config.pepper.as_ref().map(|pepper| pepper.expose().as_bytes().to_vec());
+
+        let hasher = Self { algorithm, version, params, pepper };
+        hasher.context().map_err(|e| ConfigError::InvalidValueError {
+            path: "$.auth.argon2.pepper".into(),
+            reason: e.to_string(),
+        })?;
+
+        Ok(hasher)
+    }
+
+    fn context(&self) -> Result<Argon2<'_>, AuthError> {
+        match &self.pepper {
+            Some(pepper) => Argon2::new_with_secret(pepper, self.algorithm, self.version, self.params.clone())
+                .map_err(|e| AuthError::PasswordHashError(e.to_string())),
+            None => Ok(Argon2::new(self.algorithm, self.version, self.params.clone())),
+        }
+    }
+}
 
 impl Password for Argon2Hash {
     fn new() -> Self {
-        Self(Argon2::default())
+        Self {
+            algorithm: Algorithm::default(),
+            version: Version::default(),
+            params: Params::default(),
+            pepper: None,
+        }
     }
 
     fn hash(&self, password: &str) -> Result<String, AuthError> {
         let hash_salt = SaltString::generate(&mut rand_core::OsRng);
-        let hash = self.0.hash_password(password.as_ref(), &hash_salt)
+        let hash = self.context()?.hash_password(password.as_ref(), &hash_salt)
             .map_err(|e| AuthError::PasswordHashError(e.to_string()))?;
 
         Ok(hash.to_string())
@@ -30,10 +96,191 @@ impl Password for Argon2Hash {
     fn verify(&self, password: &str, password_hash: &str) -> Result<bool, AuthError> {
         let parsed_hash = PasswordHash::new(password_hash)
             .map_err(|e| AuthError::PasswordHashError(e.to_string()))?;
-        let result = self.0.verify_password(password.as_ref(), &parsed_hash);
+        let result = self.context()?.verify_password(password.as_ref(), &parsed_hash);
 
         Ok(result.is_ok())
     }
+
+    fn needs_rehash(&self, password_hash: &str) -> bool {
+        let Ok(parsed_hash) = PasswordHash::new(password_hash) else {
+            return true;
+        };
+
+        let algorithm = Algorithm::try_from(parsed_hash.algorithm).ok();
+        let version = parsed_hash.version.and_then(|version| Version::try_from(version).ok());
+        let params = Params::try_from(&parsed_hash).ok();
+
+        algorithm != Some(self.algorithm)
+            || version != Some(self.version)
+            || params.map_or(true, |params| {
+                params.m_cost() != self.params.m_cost()
+                    || params.t_cost() != self.params.t_cost()
+                    || params.p_cost() != self.params.p_cost()
+            })
+    }
+
+    fn supports(&self, password_hash: &str) -> bool {
+        PasswordHash::new(password_hash)
+            .is_ok_and(|parsed_hash| Algorithm::try_from(parsed_hash.algorithm).is_ok())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HashFormat {
+    Argon2,
+    Bcrypt,
+    Pbkdf2,
+    /// Passlib's `$pbkdf2-sha256$rounds$salt$hash`, with salt and hash in its dotted base64 alphabet.
+    Passlib,
+    /// Django's `pbkdf2_sha256$rounds$salt$hash`, with a plain text salt.
+    Django,
+    Scrypt,
+    Unknown,
+}
+
+impl HashFormat {
+    fn detect(password_hash: &str) -> Self {
+        let mut parts = password_hash.split('$');
+        let prefix = parts.next().unwrap_or_default();
+        let ident = parts.next().unwrap_or_default();
+        let passlib_rounds = parts.next().is_some_and(|rounds| rounds.parse::<u32>().is_ok());
+
+        match (prefix, ident) {
+            ("pbkdf2_sha1" | "pbkdf2_sha256", _) => HashFormat::Django,
+            ("", "argon2i" | "argon2d" | "argon2id") => HashFormat::Argon2,
+            ("", "2a" | "2b" | "2x" | "2y") => HashFormat::Bcrypt,
+            ("", "pbkdf2" | "pbkdf2-sha256" | "pbkdf2-sha512") if passlib_rounds => HashFormat::Passlib,
+            ("", "pbkdf2" | "pbkdf2-sha256" | "pbkdf2-sha512") => HashFormat::Pbkdf2,
+            ("", "scrypt") => HashFormat::Scrypt,
+            _ => HashFormat::Unknown,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Pbkdf2Digest {
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+/// A PBKDF2 hash stored outside the PHC format, as written by Passlib and Django.
+struct LegacyPbkdf2 {
+    digest: Pbkdf2Digest,
+    rounds: u32,
+    salt: Vec<u8>,
+    hash: Vec<u8>,
+}
+
+impl LegacyPbkdf2 {
+    fn parse(password_hash: &str) -> Option<Self> {
+        let ab64 = |value: &str| STANDARD_NO_PAD.decode(value.replace('.', "+")).ok();
+
+        let (digest, rounds, salt, hash) = match HashFormat::detect(password_hash) {
+            HashFormat::Passlib => {
+                let [_, ident, rounds, salt, hash] = password_hash.split('$').collect::<Vec<_>>()[..] else {
+                    return None;
+                };
+                let digest = match ident {
+                    "pbkdf2" => Pbkdf2Digest::Sha1,
+                    "pbkdf2-sha256" => Pbkdf2Digest::Sha256,
+                    _ => Pbkdf2Digest::Sha512,
+                };
+
+                (digest, rounds, ab64(salt)?, ab64(hash)?)
+            }
+            HashFormat::Django => {
+                let [ident, rounds, salt, hash] = password_hash.split('$').collect::<Vec<_>>()[..] else {
+                    return None;
+                };
+                let digest = match ident {
+                    "pbkdf2_sha1" => Pbkdf2Digest::Sha1,
+                    _ => Pbkdf2Digest::Sha256,
+                };
+
+                (digest, rounds, salt.as_bytes().to_vec(), STANDARD.decode(hash).ok()?)
+            }
+            _ => return None,
+        };
+
+        let rounds = rounds.parse().ok().filter(|rounds| *rounds > 0)?;
+        (!salt.is_empty() && !hash.is_empty()).then_some(Self { digest, rounds, salt, hash })
+    }
+
+    fn verify(&self, password: &str) -> bool {
+        let mut derived = vec![0u8; self.hash.len()];
+
+        match self.digest {
+            Pbkdf2Digest::Sha1 => pbkdf2_hmac::<Sha1>(password.as_bytes(), &self.salt, self.rounds, &mut derived),
+            Pbkdf2Digest::Sha256 => pbkdf2_hmac::<Sha256>(password.as_bytes(), &self.salt, self.rounds, &mut derived),
+            Pbkdf2Digest::Sha512 => pbkdf2_hmac::<Sha512>(password.as_bytes(), &self.salt, self.rounds, &mut derived),
+        }
+
+        derived.iter().zip(&self.hash).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+    }
+}
+
+/// Verifies hashes imported from older systems next to Argon2 ones, while always hashing with Argon2.
+///
+/// Anything that is not an Argon2 hash with the current parameters reports `needs_rehash`,
+/// so legacy hashes are upgraded the first time their owner logs in.
+#[derive(Debug, Clone)]
+pub struct MultiHash {
+    primary: Argon2Hash,
+}
+
+impl MultiHash {
+    pub fn with_primary(primary: Argon2Hash) -> Self {
+        Self { primary }
+    }
+
+    fn verify_phc<V: PasswordVerifier>(verifier: V, password: &str, password_hash: &str) -> Result<bool, AuthError> {
+        let parsed_hash = PasswordHash::new(password_hash)
+            .map_err(|e| AuthError::PasswordHashError(e.to_string()))?;
+
+        Ok(verifier.verify_password(password.as_ref(), &parsed_hash).is_ok())
+    }
+}
+
+impl Password for MultiHash {
+    fn new() -> Self {
+        Self::with_primary(Argon2Hash::new())
+    }
+
+    fn hash(&self, password: &str) -> Result<String, AuthError> {
+        self.primary.hash(password)
+    }
+
+    fn verify(&self, password: &str, password_hash: &str) -> Result<bool, AuthError> {
+        match HashFormat::detect(password_hash) {
+            HashFormat::Argon2 => self.primary.verify(password, password_hash),
+            HashFormat::Bcrypt => bcrypt::verify(password, password_hash)
+                .map_err(|e| AuthError::PasswordHashError(e.to_string())),
+            HashFormat::Pbkdf2 => Self::verify_phc(Pbkdf2, password, password_hash),
+            HashFormat::Passlib | HashFormat::Django => LegacyPbkdf2::parse(password_hash)
+                .map(|legacy| legacy.verify(password))
+                .ok_or(AuthError::UnsupportedPasswordHash),
+            HashFormat::Scrypt => Self::verify_phc(Scrypt, password, password_hash),
+            HashFormat::Unknown => Err(AuthError::UnsupportedPasswordHash),
+        }
+    }
+
+    fn needs_rehash(&self, password_hash: &str) -> bool {
+        match HashFormat::detect(password_hash) {
+            HashFormat::Argon2 => self.primary.needs_rehash(password_hash),
+            _ => true,
+        }
+    }
+
+    fn supports(&self, password_hash: &str) -> bool {
+        match HashFormat::detect(password_hash) {
+            HashFormat::Argon2 => self.primary.supports(password_hash),
+            HashFormat::Bcrypt => bcrypt::HashParts::from_str(password_hash).is_ok(),
+            HashFormat::Pbkdf2 | HashFormat::Scrypt => PasswordHash::new(password_hash).is_ok(),
+            HashFormat::Passlib | HashFormat::Django => LegacyPbkdf2::parse(password_hash).is_some(),
+            HashFormat::Unknown => false,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -48,4 +295,89 @@ mod password_tests {
 
         assert!(hasher.verify(password, &password_hash).unwrap());
     }
+
+    #[test]
+    fn test_detect_outdated_parameters() {
+        let weak = Argon2Hash {
+            params: Params::new(8, 1, 1, None).unwrap(),
+            ..Argon2Hash::new()
+        };
+        let strong = Argon2Hash::new();
+        let password = "test_password";
+        let password_hash = weak.hash(password).unwrap();
+
+        assert!(strong.verify(password, &password_hash).unwrap());
+        assert!(strong.needs_rehash(&password_hash));
+        assert!(!strong.needs_rehash(&strong.hash(password).unwrap()));
+    }
+
+    #[test]
+    fn test_pepper_is_required_to_verify() {
+        let peppered = Argon2Hash {
+            pepper: Some(b"test_pepper".to_vec()),
+            ..Argon2Hash::new()
+        };
+        let password = "test_password";
+        let password_hash = peppered.hash(password).unwrap();
+
+        assert!(peppered.verify(password, &password_hash).unwrap());
+        assert!(!Argon2Hash::new().verify(password, &password_hash).unwrap());
+    }
+
+    #[test]
+    fn test_verify_legacy_hashes() {
+        let hasher = MultiHash::new();
+        let password = "test_password";
+        let salt = SaltString::generate(&mut rand_core::OsRng);
+
+        let bcrypt_hash = bcrypt::hash(password, 4).unwrap();
+        let pbkdf2_hash = Pbkdf2
+            .hash_password_customized(
+                password.as_bytes(),
+                Some(pbkdf2::Algorithm::Pbkdf2Sha256.ident()),
+                None,
+                pbkdf2::Params { rounds: 1000, output_length: 32 },
+                &salt,
+            )
+            .unwrap()
+            .to_string();
+        let scrypt_hash = Scrypt
+            .hash_password_customized(password.as_bytes(), None, None, scrypt::Params::new(4, 8, 1, 32).unwrap(), &salt)
+            .unwrap()
+            .to_string();
+
+        for legacy_hash in [bcrypt_hash, pbkdf2_hash, scrypt_hash] {
+            assert!(hasher.supports(&legacy_hash), "Should recognise {legacy_hash}.");
+            assert!(hasher.verify(password, &legacy_hash).unwrap(), "Should verify {legacy_hash}.");
+            assert!(!hasher.verify("wrong_password", &legacy_hash).unwrap(), "Should reject {legacy_hash}.");
+            assert!(hasher.needs_rehash(&legacy_hash), "Should upgrade {legacy_hash}.");
+        }
+
+        assert!(!hasher.needs_rehash(&hasher.hash(password).unwrap()));
+        assert!(!hasher.supports("$md5$legacy"));
+    }
+
+    #[test]
+    fn test_verify_passlib_and_django_pbkdf2() {
+        let hasher = MultiHash::new();
+        let password = "test_password";
+
+        let legacy_hashes = [
+            "$pbkdf2$131000$XzwqnYHkewbJ0vGoPmtcRw$9aPt4mala/Ov2z1qYmRrA8ucet8",
+            "$pbkdf2-sha256$29000$XzwqnYHkewbJ0vGoPmtcRw$lephFHFUQ48Bn/TXu3.8Lb9VpDp00iSf.PQdao2hBTs",
+            "$pbkdf2-sha512$25000$XzwqnYHkewbJ0vGoPmtcRw$PZY57e.JZhXuT7bOf4WkRpP0dE6CQMQW6l7CWtykSt7p/CHfEAM3.MiCSWstt.rISAOlw5adYwRB4/E5HmadeA",
+            "pbkdf2_sha256$600000$Zp3Kq8wXvT2mR9sLbN4yHc$zTyfbdcEa72kwzNVX7Iy0RBMAMw/ikuW//rFkGvzJVI=",
+            "pbkdf2_sha1$600000$Zp3Kq8wXvT2mR9sLbN4yHc$1ehPWXLLcSvQCMm6+DVoP3jvOcQ=",
+        ];
+
+        for legacy_hash in legacy_hashes {
+            assert!(hasher.supports(legacy_hash), "Should recognise {legacy_hash}.");
+            assert!(hasher.verify(password, legacy_hash).unwrap(), "Should verify {legacy_hash}.");
+            assert!(!hasher.verify("wrong_password", legacy_hash).unwrap(), "Should reject {legacy_hash}.");
+            assert!(hasher.needs_rehash(legacy_hash), "Should upgrade {legacy_hash}.");
+        }
+
+        assert!(!hasher.supports("$pbkdf2-sha256$0$XzwqnYHkewbJ0vGoPmtcRw$lephFHFUQ48Bn"));
+        assert!(!hasher.supports("pbkdf2_sha256$600000$salt"));
+    }
 }
@@ -0,0 +1,235 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+use std::sync::Arc;
+
+use sha1::{Digest, Sha1};
+
+use crate::errors::{AuthError, PasswordViolation};
+use super::settings::{PasswordPolicy, Settings};
+
+#[derive(Clone)]
+pub struct PasswordChecker {
+    policy: PasswordPolicy,
+    /// SHA-1 digests from `breached_list`, read once so that checks never touch the disk.
+    breached: Arc<HashSet<[u8; 20]>>,
+}
+
+impl fmt::Debug for PasswordChecker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PasswordChecker")
+            .field("policy", &self.policy)
+            .field("breached", &self.breached.len())
+            .finish()
+    }
+}
+
+impl PasswordChecker {
+    pub fn new(settings: &Arc<Settings>) -> Self {
+        Self::from_policy(settings.auth.password_policy.clone())
+    }
+
+    pub fn from_policy(policy: PasswordPolicy) -> Self {
+        let breached = match &policy.breached_list {
+            Some(list) => Self::load_breached(Path::new(list)).unwrap_or_else(|err| {
+                tracing::warn!("unable to read breached password list {list}: {err}");
+                HashSet::new()
+            }),
+            None => HashSet::new(),
+        };
+
+        Self { policy, breached: Arc::new(breached) }
+    }
+
+    pub fn check(&self, password: &str, username: &str, email: &str) -> Result<(), AuthError> {
+        let violations = self.violations(password, username, email);
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(AuthError::PasswordPolicyViolation(violations))
+        }
+    }
+
+    pub fn violations(&self, password: &str, username: &str, email: &str) -> Vec<PasswordViolation> {
+        let policy = &self.policy;
+        let mut violations = Vec::new();
+
+        let length = password.chars().count();
+
+        if length < policy.min_length {
+            violations.push(PasswordViolation::TooShort { min: policy.min_length });
+        }
+        if length > policy.max_length {
+            violations.push(PasswordViolation::TooLong { max: policy.max_length });
+        }
+        if policy.require_lowercase && !password.chars().any(char::is_lowercase) {
+            violations.push(PasswordViolation::MissingLowercase);
+        }
+        if policy.require_uppercase && !password.chars().any(char::is_uppercase) {
+            violations.push(PasswordViolation::MissingUppercase);
+        }
+        if policy.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+            violations.push(PasswordViolation::MissingDigit);
+        }
+        if policy.require_symbol && !password.chars().any(|c| !c.is_alphanumeric() && !c.is_whitespace()) {
+            violations.push(PasswordViolation::MissingSymbol);
+        }
+
+        if policy.reject_identity {
+            let lowered = password.to_lowercase();
+            let email_local = email.split('@').next().unwrap_or_default();
+
+            if !username.is_empty() && lowered.contains(&username.to_lowercase()) {
+                violations.push(PasswordViolation::ContainsUsername);
+            }
+            if !email_local.is_empty() && lowered.contains(&email_local.to_lowercase()) {
+                violations.push(PasswordViolation::ContainsEmail);
+            }
+        }
+
+        if self.breached.contains(Sha1::digest(password.as_bytes()).as_slice()) {
+            violations.push(PasswordViolation::Breached);
+        }
+
+        violations
+    }
+
+    /// Reads a local copy of a k-anonymity range list into memory.
+    ///
+    /// The list is either a directory holding one `<PREFIX>` (or `<PREFIX>.txt`) file per
+    /// five character hash prefix with `SUFFIX:COUNT` lines, or a single file of `HASH:COUNT` lines.
+    fn load_breached(list: &Path) -> io::Result<HashSet<[u8; 20]>> {
+        let mut hashes = HashSet::new();
+
+        if list.is_dir() {
+            for entry in fs::read_dir(list)? {
+                let path = entry?.path();
+                let Some(prefix) = path.file_stem().and_then(|stem| stem.to_str()).filter(|stem| stem.len() == 5) else {
+                    continue;
+                };
+
+                Self::read_hashes(&path, prefix, &mut hashes)?;
+            }
+        } else {
+            Self::read_hashes(list, "", &mut hashes)?;
+        }
+
+        Ok(hashes)
+    }
+
+    fn read_hashes(path: &Path, prefix: &str, hashes: &mut HashSet<[u8; 20]>) -> io::Result<()> {
+        for line in BufReader::new(File::open(path)?).lines() {
+            let line = line?;
+            let entry = line.split(':').next().unwrap_or_default().trim();
+
+            if let Some(hash) = Self::parse_hash(&format!("{prefix}{entry}")) {
+                hashes.insert(hash);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn parse_hash(hex: &str) -> Option<[u8; 20]> {
+        if hex.len() != 40 || !hex.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+            return None;
+        }
+
+        let mut hash = [0u8; 20];
+        for (index, byte) in hash.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[index * 2..index * 2 + 2], 16).ok()?;
+        }
+
+        Some(hash)
+    }
+}
+
+#[cfg(test)]
+mod policy_tests {
+    use std::{env, fs};
+
+    use super::*;
+
+    fn policy() -> PasswordPolicy {
+        PasswordPolicy {
+            min_length: 8,
+            max_length: 16,
+            require_lowercase: true,
+            require_uppercase: true,
+            require_digit: true,
+            require_symbol: true,
+            reject_identity: true,
+            breached_list: None,
+        }
+    }
+
+    #[test]
+    fn test_accept_compliant_password() {
+        let checker = PasswordChecker::from_policy(policy());
+
+        assert!(checker.check("Str0ng#Pass", "alice", "alice@sieluna.com").is_ok());
+    }
+
+    #[test]
+    fn test_report_every_violation() {
+        let checker = PasswordChecker::from_policy(policy());
+        let violations = checker.violations("alice", "alice", "alice@sieluna.com");
+
+        assert_eq!(
+            violations,
+            vec![
+                PasswordViolation::TooShort { min: 8 },
+                PasswordViolation::MissingUppercase,
+                PasswordViolation::MissingDigit,
+                PasswordViolation::MissingSymbol,
+                PasswordViolation::ContainsUsername,
+                PasswordViolation::ContainsEmail,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reject_empty_password() {
+        let checker = PasswordChecker::from_policy(PasswordPolicy { min_length: 1, ..policy() });
+
+        assert!(checker.violations("", "bob", "bob@sieluna.com").contains(&PasswordViolation::TooShort { min: 1 }));
+    }
+
+    #[test]
+    fn test_reject_breached_password() {
+        let list = env::temp_dir().join("smarinth_policy_tests_breached.txt");
+        let hash: String = Sha1::digest(b"Str0ng#Pass").iter().map(|byte| format!("{byte:02X}")).collect();
+        fs::write(&list, format!("0000000000000000000000000000000000000000:1\n{hash}:3\n")).unwrap();
+
+        let checker = PasswordChecker::from_policy(PasswordPolicy {
+            breached_list: Some(list.to_string_lossy().to_string()),
+            ..policy()
+        });
+
+        assert_eq!(checker.violations("Str0ng#Pass", "alice", "alice@sieluna.com"), vec![PasswordViolation::Breached]);
+
+        fs::remove_file(list).unwrap();
+    }
+
+    #[test]
+    fn test_reject_breached_password_from_range_directory() {
+        let list = env::temp_dir().join("smarinth_policy_tests_ranges");
+        let hash: String = Sha1::digest(b"Str0ng#Pass").iter().map(|byte| format!("{byte:02X}")).collect();
+        let (prefix, suffix) = hash.split_at(5);
+        fs::create_dir_all(&list).unwrap();
+        fs::write(list.join(format!("{prefix}.txt")), format!("{}:2\n", suffix.to_lowercase())).unwrap();
+
+        let checker = PasswordChecker::from_policy(PasswordPolicy {
+            breached_list: Some(list.to_string_lossy().to_string()),
+            ..policy()
+        });
+
+        assert_eq!(checker.violations("Str0ng#Pass", "alice", "alice@sieluna.com"), vec![PasswordViolation::Breached]);
+        assert!(checker.violations("An0ther#Pass", "alice", "alice@sieluna.com").is_empty());
+
+        fs::remove_dir_all(list).unwrap();
+    }
+}
@@ -0,0 +1,250 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use tracing_subscriber::{reload, EnvFilter, Registry};
+
+use crate::configs::{Cli, Settings};
+use crate::errors::ConfigError;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// A component that can apply changed settings without a restart.
+pub trait Reloadable: Send + Sync {
+    /// Checks the settings without applying them, so a reload either reaches every target or none.
+    fn validate(&self, _settings: &Settings) -> Result<(), ConfigError> {
+        Ok(())
+    }
+
+    fn reload(&self, settings: &Settings) -> Result<(), ConfigError>;
+}
+
+/// The global log filter, swapped in place when `logger.level` changes. `RUST_LOG` still wins when set.
+pub struct LogFilter {
+    handle: reload::Handle<EnvFilter, Registry>,
+}
+
+impl LogFilter {
+    pub fn layer(settings: &Settings) -> (reload::Layer<EnvFilter, Registry>, Self) {
+        let (layer, handle) = reload::Layer::new(Self::filter(settings));
+
+        (layer, Self { handle })
+    }
+
+    fn filter(settings: &Settings) -> EnvFilter {
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| {
+            let app_name = env!("CARGO_PKG_NAME").replace('-', "_");
+            let level = settings.logger.level.as_str();
+
+            format!("{app_name}={level}").into()
+        })
+    }
+}
+
+impl Reloadable for LogFilter {
+    fn reload(&self, settings: &Settings) -> Result<(), ConfigError> {
+        self.handle.reload(Self::filter(settings)).map_err(|e| ConfigError::InvalidValueError {
+            path: "$.logger.level".into(),
+            reason: e.to_string(),
+        })
+    }
+}
+
+#[cfg(unix)]
+type Hangup = Option<tokio::signal::unix::Signal>;
+#[cfg(not(unix))]
+type Hangup = ();
+
+/// Reloads the configuration when a layer file changes or on SIGHUP, pushing it to every target.
+pub struct SettingsReloader {
+    cli: Cli,
+    current: Mutex<Settings>,
+    targets: Vec<Arc<dyn Reloadable>>,
+}
+
+impl SettingsReloader {
+    pub fn new(cli: Cli, settings: &Settings, targets: Vec<Arc<dyn Reloadable>>) -> Self {
+        Self {
+            cli,
+            current: Mutex::new(settings.clone()),
+            targets,
+        }
+    }
+
+    /// Loads every layer again and applies it; returns the changed paths that only take effect after a restart.
+    /// Nothing is applied unless every target accepts the new settings.
+    pub fn reload(&self) -> Result<Vec<&'static str>, ConfigError> {
+        let settings = self.cli.settings()?;
+
+        let errors: Vec<ConfigError> = self.targets.iter().filter_map(|target| target.validate(&settings).err()).collect();
+        if !errors.is_empty() {
+            return Err(ConfigError::ValidationError(errors));
+        }
+
+        let mut current = self.current.lock().unwrap_or_else(|e| e.into_inner());
+        let restart_required = Self::restart_required(&current, &settings);
+
+        for target in &self.targets {
+            target.reload(&settings)?;
+        }
+        *current = settings;
+
+        Ok(restart_required)
+    }
+
+    pub async fn watch(self: Arc<Self>) {
+        let paths = Settings::layer_paths(&self.cli.config).unwrap_or_default();
+        let mut modified = Self::modified(&paths);
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        let mut hangup = Self::hangup();
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    let current = Self::modified(&paths);
+                    if current == modified {
+                        continue;
+                    }
+                    modified = current;
+                    tracing::info!("configuration files changed, reloading");
+                }
+                _ = Self::hangup_received(&mut hangup) => tracing::info!("SIGHUP received, reloading configuration"),
+            }
+
+            match self.reload() {
+                Ok(restart_required) => {
+                    for path in restart_required {
+                        tracing::warn!(path, "setting changed but only applies after a restart");
+                    }
+                    tracing::info!("configuration reloaded");
+                }
+                Err(e) => tracing::error!(error = %e, "configuration reload failed, keeping the previous settings"),
+            }
+        }
+    }
+
+    fn restart_required(old: &Settings, new: &Settings) -> Vec<&'static str> {
+        let changes = [
+            ("$.server.host", old.server.host != new.server.host),
+            ("$.server.port", old.server.port != new.server.port),
+            ("$.logger.format", old.logger.format != new.logger.format),
+            ("$.docs", old.docs != new.docs),
+            ("$.metrics", old.metrics != new.metrics),
+            ("$.telemetry", old.telemetry != new.telemetry),
+            ("$.rate_limit.store", old.rate_limit.store != new.rate_limit.store),
+            ("$.rate_limit.fail_open", old.rate_limit.fail_open != new.rate_limit.fail_open),
+            ("$.database", old.database != new.database),
+            ("$.control", old.control != new.control),
+            ("$.auth.secret", old.auth.secret != new.auth.secret),
+            ("$.auth.argon2", old.auth.argon2 != new.auth.argon2),
+            ("$.auth.password_policy", old.auth.password_policy != new.auth.password_policy),
+            ("$.auth.oidc", old.auth.oidc != new.auth.oidc),
+        ];
+
+        changes.into_iter().filter_map(|(path, changed)| changed.then_some(path)).collect()
+    }
+
+    fn modified(paths: &[PathBuf]) -> Vec<Option<SystemTime>> {
+        paths
+            .iter()
+            .map(|path| fs::metadata(path).and_then(|metadata| metadata.modified()).ok())
+            .collect()
+    }
+
+    #[cfg(unix)]
+    fn hangup() -> Hangup {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup()).ok()
+    }
+
+    #[cfg(not(unix))]
+    fn hangup() -> Hangup {}
+
+    #[cfg(unix)]
+    async fn hangup_received(hangup: &mut Hangup) {
+        match hangup {
+            Some(signal) => {
+                signal.recv().await;
+            }
+            None => std::future::pending().await,
+        }
+    }
+
+    #[cfg(not(unix))]
+    async fn hangup_received(_: &mut Hangup) {
+        std::future::pending().await
+    }
+}
+
+#[cfg(test)]
+mod reload_tests {
+    use crate::payload::UserDto;
+    use crate::services::TokenService;
+    use super::*;
+
+    #[test]
+    fn test_apply_live_settings_and_report_restarts() {
+        let config_path = std::env::temp_dir().join("smarinth_test_reload.toml");
+        fs::write(&config_path, "[auth]\nexpiration = 100\n").unwrap();
+
+        let cli = Cli { config: vec![config_path.clone()], set: vec![], command: None };
+        let settings = Arc::new(cli.settings().unwrap());
+        let token_service = Arc::new(TokenService::new(&settings));
+        let reloader = SettingsReloader::new(cli, &settings, vec![token_service.clone()]);
+
+        fs::write(&config_path, "[server]\nport = 9191\n\n[auth]\nexpiration = 60\n").unwrap();
+
+        assert_eq!(reloader.reload().unwrap(), vec!["$.server.port"]);
+
+        let user = UserDto {
+            id: 1,
+            username: "test_reload_user".into(),
+            email: "test_reload_user@sieluna.com".into(),
+            role: "user".into(),
+        };
+        let token = token_service.generate_token(user).unwrap();
+
+        assert_eq!(token.exp - token.iat, 60, "Token expiration should apply without a restart.");
+
+        fs::remove_file(config_path).unwrap();
+    }
+
+    struct RejectingTarget;
+
+    impl Reloadable for RejectingTarget {
+        fn validate(&self, _settings: &Settings) -> Result<(), ConfigError> {
+            Err(ConfigError::InvalidValueError { path: "$.auth".into(), reason: "rejected".into() })
+        }
+
+        fn reload(&self, _settings: &Settings) -> Result<(), ConfigError> {
+            panic!("A rejected reload should not be applied.");
+        }
+    }
+
+    #[test]
+    fn test_reject_reload_when_a_target_fails() {
+        let config_path = std::env::temp_dir().join("smarinth_test_reload_rejected.toml");
+        fs::write(&config_path, "[auth]\nexpiration = 100\n").unwrap();
+
+        let cli = Cli { config: vec![config_path.clone()], set: vec![], command: None };
+        let settings = Arc::new(cli.settings().unwrap());
+        let token_service = Arc::new(TokenService::new(&settings));
+        let reloader = SettingsReloader::new(cli, &settings, vec![token_service.clone(), Arc::new(RejectingTarget)]);
+
+        fs::write(&config_path, "[auth]\nexpiration = 60\n").unwrap();
+
+        assert!(matches!(reloader.reload(), Err(ConfigError::ValidationError(_))));
+
+        let user = UserDto {
+            id: 1,
+            username: "test_reload_user".into(),
+            email: "test_reload_user@sieluna.com".into(),
+            role: "user".into(),
+        };
+        let token = token_service.generate_token(user).unwrap();
+
+        assert_eq!(token.exp - token.iat, 100, "No target should apply a rejected reload.");
+
+        fs::remove_file(config_path).unwrap();
+    }
+}
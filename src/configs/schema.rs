@@ -1,5 +1,133 @@
+use std::fmt;
+
 use crate::configs::DatabaseScheme;
-use crate::entities::{Table, UserTable};
+use crate::entities::{
+    AuditEventTable, AuthorizationGrantTable, Column, ExternalIdentityTable, OAuthClientTable, RateLimitBucketTable,
+    Table, TableDefinition, UserTable,
+};
+use crate::errors::DatabaseError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LiveColumn {
+    pub name: String,
+    pub data_type: String,
+    pub nullable: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LiveIndex {
+    pub name: String,
+    pub columns: Vec<String>,
+    pub unique: bool,
+}
+
+/// A table as the database reports it; indexes backing primary, unique or foreign keys are left out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LiveTable {
+    pub name: String,
+    pub columns: Vec<LiveColumn>,
+    pub indexes: Vec<LiveIndex>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TableDrift {
+    pub table: &'static str,
+    pub missing_columns: Vec<&'static str>,
+    pub unexpected_columns: Vec<String>,
+    /// Columns whose type or nullability differs.
+    pub altered_columns: Vec<&'static str>,
+    pub missing_indexes: Vec<&'static str>,
+    /// Undeclared indexes, and declared ones whose columns differ.
+    pub unexpected_indexes: Vec<String>,
+}
+
+impl TableDrift {
+    pub fn is_empty(&self) -> bool {
+        self.missing_columns.is_empty()
+            && self.unexpected_columns.is_empty()
+            && self.altered_columns.is_empty()
+            && self.missing_indexes.is_empty()
+            && self.unexpected_indexes.is_empty()
+    }
+}
+
+impl fmt::Display for TableDrift {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let changes = [
+            ("missing columns", self.missing_columns.join(", ")),
+            ("unexpected columns", self.unexpected_columns.join(", ")),
+            ("altered columns", self.altered_columns.join(", ")),
+            ("missing indexes", self.missing_indexes.join(", ")),
+            ("unexpected indexes", self.unexpected_indexes.join(", ")),
+        ];
+        let changes: Vec<_> = changes
+            .into_iter()
+            .filter(|(_, names)| !names.is_empty())
+            .map(|(label, names)| format!("{label} {names}"))
+            .collect();
+
+        write!(f, "{}: {}", self.table, changes.join(", "))
+    }
+}
+
+/// Differences between the declared tables and those in the live database.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SchemaDrift {
+    pub missing_tables: Vec<&'static str>,
+    pub unexpected_tables: Vec<String>,
+    pub tables: Vec<TableDrift>,
+}
+
+impl SchemaDrift {
+    pub fn is_empty(&self) -> bool {
+        self.missing_tables.is_empty() && self.unexpected_tables.is_empty() && self.tables.is_empty()
+    }
+}
+
+impl fmt::Display for SchemaDrift {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+
+        if !self.missing_tables.is_empty() {
+            parts.push(format!("missing tables: {}", self.missing_tables.join(", ")));
+        }
+        if !self.unexpected_tables.is_empty() {
+            parts.push(format!("unexpected tables: {}", self.unexpected_tables.join(", ")));
+        }
+        parts.extend(self.tables.iter().map(TableDrift::to_string));
+
+        write!(f, "{}", parts.join("; "))
+    }
+}
+
+/// Comments a statement out, line by line.
+fn commented(statement: &str) -> String {
+    statement.lines().map(|line| format!("-- {line}")).collect::<Vec<_>>().join("\n")
+}
+
+/// Tables kept by sqlx or SQLite itself.
+fn is_unmanaged(table: &str) -> bool {
+    table == "_sqlx_migrations" || table.starts_with("sqlite_")
+}
+
+/// Spelling-independent form of a SQL type, so `character varying(255)` matches `VARCHAR(255)`.
+fn canonical_type(data_type: &str) -> String {
+    let compact: String = data_type.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_ascii_uppercase();
+    let (base, arguments) = match compact.split_once('(') {
+        Some((base, arguments)) => (base.to_string(), format!("({arguments}")),
+        None => (compact.clone(), String::new()),
+    };
+
+    match (base.as_str(), arguments.as_str()) {
+        ("TINYINT", "(1)") | ("BOOL" | "BOOLEAN", _) => "BOOLEAN".into(),
+        ("INT" | "INTEGER" | "INT4", _) => "INT".into(),
+        ("BIGINT" | "INT8", _) => "BIGINT".into(),
+        ("CHARACTERVARYING", _) => format!("VARCHAR{arguments}"),
+        ("NUMERIC", _) => format!("DECIMAL{arguments}"),
+        ("TIMESTAMPTZ", _) => "TIMESTAMPWITHTIMEZONE".into(),
+        _ => compact,
+    }
+}
 
 pub struct SchemaManager {
     tables: Vec<Box<dyn Table>>,
@@ -41,12 +169,280 @@ impl SchemaManager {
     }
 
     pub fn create_schema(&self, scheme: &DatabaseScheme) -> Vec<String> {
-        self.tables.iter().map(|table| table.create(scheme)).collect()
+        self.tables.iter().flat_map(|table| table.create(scheme)).collect()
     }
 
     pub fn dispose_schema(&self) -> Vec<String> {
         self.tables.iter().rev().map(|table| table.dispose()).collect()
     }
+
+    /// Compares the managed tables with the live database.
+    pub fn drift(&self, live: &[LiveTable], scheme: &DatabaseScheme) -> SchemaDrift {
+        let definitions: Vec<_> = self.tables.iter().map(|table| table.definition()).collect();
+        let mut drift = SchemaDrift::default();
+
+        for definition in &definitions {
+            match live.iter().find(|table| table.name.eq_ignore_ascii_case(definition.name)) {
+                Some(live_table) => {
+                    let table_drift = Self::table_drift(definition, live_table, scheme);
+                    if !table_drift.is_empty() {
+                        drift.tables.push(table_drift);
+                    }
+                }
+                None => drift.missing_tables.push(definition.name),
+            }
+        }
+
+        drift.unexpected_tables = live.iter()
+            .map(|table| &table.name)
+            .filter(|name| !is_unmanaged(name))
+            .filter(|name| !definitions.iter().any(|definition| definition.name.eq_ignore_ascii_case(name)))
+            .cloned()
+            .collect();
+
+        drift
+    }
+
+    fn table_drift(definition: &TableDefinition, live: &LiveTable, scheme: &DatabaseScheme) -> TableDrift {
+        let mut drift = TableDrift { table: definition.name, ..Default::default() };
+
+        for column in &definition.columns {
+            match live.columns.iter().find(|live_column| live_column.name.eq_ignore_ascii_case(column.name)) {
+                Some(live_column) => {
+                    let keyed = column.column_type.is_id() || column.primary || definition.primary_key.contains(&column.name);
+                    let type_changed =
+                        canonical_type(&column.column_type.data_type(scheme)) != canonical_type(&live_column.data_type);
+
+                    if type_changed || (!keyed && column.nullable != live_column.nullable) {
+                        drift.altered_columns.push(column.name);
+                    }
+                }
+                None => drift.missing_columns.push(column.name),
+            }
+        }
+
+        drift.unexpected_columns = live.columns.iter()
+            .filter(|live_column| !definition.columns.iter().any(|column| live_column.name.eq_ignore_ascii_case(column.name)))
+            .map(|live_column| live_column.name.clone())
+            .collect();
+
+        for index in &definition.indexes {
+            match live.indexes.iter().find(|live_index| live_index.name.eq_ignore_ascii_case(index.name)) {
+                Some(live_index) => {
+                    let same_columns = live_index.columns.len() == index.columns.len()
+                        && live_index.columns.iter().zip(&index.columns).all(|(live, declared)| live.eq_ignore_ascii_case(declared));
+
+                    if !same_columns || live_index.unique != index.unique {
+                        drift.missing_indexes.push(index.name);
+                        drift.unexpected_indexes.push(live_index.name.clone());
+                    }
+                }
+                None => drift.missing_indexes.push(index.name),
+            }
+        }
+
+        drift.unexpected_indexes.extend(
+            live.indexes.iter()
+                .filter(|live_index| !definition.indexes.iter().any(|index| live_index.name.eq_ignore_ascii_case(index.name)))
+                .map(|live_index| live_index.name.clone()),
+        );
+
+        drift
+    }
+
+    /// SQL bringing the live database to the declared schema, empty when there is no drift. Unexpected tables are
+    /// dropped first, then managed tables are created or altered in dependency order. Statements dropping tables or
+    /// columns are commented out unless `allow_drop` is set, as those may belong to the user's own migrations.
+    pub fn migration(
+        &self,
+        drift: &SchemaDrift,
+        scheme: &DatabaseScheme,
+        allow_drop: bool,
+    ) -> Result<String, DatabaseError> {
+        let withheld = !allow_drop
+            && (!drift.unexpected_tables.is_empty() || drift.tables.iter().any(|table| !table.unexpected_columns.is_empty()));
+
+        let mut statements = Vec::new();
+        let mut rebuilt = false;
+
+        if withheld {
+            statements.push("-- Drops of undeclared tables and columns are commented out, see --allow-drop.".to_string());
+        }
+
+        for table in &drift.unexpected_tables {
+            let statement = format!("DROP TABLE IF EXISTS {table};");
+            statements.push(if allow_drop { statement } else { commented(&statement) });
+        }
+
+        for definition in self.tables.iter().map(|table| table.definition()) {
+            if drift.missing_tables.contains(&definition.name) {
+                statements.extend(definition.create(scheme));
+            } else if let Some(table_drift) = drift.tables.iter().find(|table| table.table == definition.name) {
+                let (table_statements, table_rebuilt) = Self::alter_table(&definition, table_drift, scheme, allow_drop)?;
+                statements.extend(table_statements);
+                rebuilt |= table_rebuilt;
+            }
+        }
+
+        if statements.is_empty() {
+            return Ok(String::new());
+        }
+
+        let body = statements.join("\n");
+
+        Ok(if rebuilt {
+            // Inside the transaction sqlx opens, `foreign_keys` cannot be turned off and dropping the old table
+            // would fire ON DELETE actions on the rows referencing it.
+            format!("-- no-transaction\nPRAGMA foreign_keys = OFF;\nBEGIN;\n{body}\nCOMMIT;\nPRAGMA foreign_keys = ON;\n")
+        } else {
+            format!("{body}\n")
+        })
+    }
+
+    /// Statements altering one table in place, or rebuilding it on SQLite; the flag tells whether it was rebuilt.
+    fn alter_table(
+        definition: &TableDefinition,
+        drift: &TableDrift,
+        scheme: &DatabaseScheme,
+        allow_drop: bool,
+    ) -> Result<(Vec<String>, bool), DatabaseError> {
+        let table = definition.name;
+
+        let mut statements: Vec<String> = drift.unexpected_indexes.iter()
+            .map(|index| match scheme {
+                DatabaseScheme::MYSQL => format!("DROP INDEX {index} ON {table};"),
+                _ => format!("DROP INDEX IF EXISTS {index};"),
+            })
+            .collect();
+
+        let added: Vec<_> = drift.missing_columns.iter().filter_map(|name| definition.column_named(name)).collect();
+
+        if let DatabaseScheme::SQLITE = scheme {
+            let limited = !drift.unexpected_columns.is_empty()
+                || !drift.altered_columns.is_empty()
+                || added.iter().any(|column| column.unique || column.primary || (!column.nullable && column.default.is_none()));
+
+            if limited {
+                let rebuild = Self::rebuild_table(definition, drift)?;
+
+                if allow_drop || drift.unexpected_columns.is_empty() {
+                    statements.extend(rebuild);
+                    return Ok((statements, true));
+                }
+
+                statements.push(format!("-- Rebuilding {table} drops {}.", drift.unexpected_columns.join(", ")));
+                statements.extend(rebuild.iter().map(|statement| commented(statement)));
+                return Ok((statements, false));
+            }
+        }
+
+        for column in added {
+            if column.nullable || column.default.is_some() || column.column_type.is_id() {
+                statements.push(format!("ALTER TABLE {table} ADD COLUMN {};", column.render(scheme)));
+            } else {
+                // Existing rows have no value yet, so the column is only made NOT NULL after a backfill.
+                let nullable = Column { nullable: true, ..column.clone() };
+
+                statements.push(format!("ALTER TABLE {table} ADD COLUMN {};", nullable.render(scheme)));
+                statements.push(format!("-- TODO: backfill {table}.{} before the next statement makes it NOT NULL.", column.name));
+                statements.push(match scheme {
+                    DatabaseScheme::POSTGRES => format!("ALTER TABLE {table} ALTER COLUMN {} SET NOT NULL;", column.name),
+                    _ => {
+                        let column = Column { unique: false, primary: false, ..column.clone() };
+                        format!("ALTER TABLE {table} MODIFY COLUMN {};", column.render(scheme))
+                    }
+                });
+            }
+        }
+
+        for column in &drift.unexpected_columns {
+            let statement = format!("ALTER TABLE {table} DROP COLUMN {column};");
+            statements.push(if allow_drop { statement } else { commented(&statement) });
+        }
+
+        for column in drift.altered_columns.iter().filter_map(|name| definition.column_named(name)) {
+            let name = column.name;
+            let data_type = column.column_type.data_type(scheme);
+
+            match scheme {
+                DatabaseScheme::POSTGRES => {
+                    let nullability = if column.nullable { "DROP" } else { "SET" };
+
+                    statements.push(format!("ALTER TABLE {table} ALTER COLUMN {name} TYPE {data_type} USING {name}::{data_type};"));
+                    statements.push(format!("ALTER TABLE {table} ALTER COLUMN {name} {nullability} NOT NULL;"));
+                }
+                // MySQL; SQLite tables were rebuilt above. Keys are left out, they already exist.
+                _ => {
+                    let column_sql = if column.column_type.is_id() {
+                        format!("{name} {data_type} NOT NULL AUTO_INCREMENT")
+                    } else {
+                        Column { unique: false, primary: false, ..column.clone() }.render(scheme)
+                    };
+
+                    statements.push(format!("ALTER TABLE {table} MODIFY COLUMN {column_sql};"));
+                }
+            }
+        }
+
+        for index in definition.indexes.iter().filter(|index| drift.missing_indexes.contains(&index.name)) {
+            statements.push(match scheme {
+                DatabaseScheme::MYSQL => {
+                    let unique = if index.unique { "UNIQUE " } else { "" };
+                    format!("CREATE {unique}INDEX {} ON {table} ({});", index.name, index.columns.join(", "))
+                }
+                _ => index.render(table),
+            });
+        }
+
+        Ok((statements, false))
+    }
+
+    /// Copies a SQLite table into a new one with the declared shape, keeping the columns both have in common. New
+    /// columns take their declared default, and columns becoming NOT NULL replace `NULL` with it; a new NOT NULL
+    /// column without a default is refused, as existing rows would have no value for it.
+    fn rebuild_table(definition: &TableDefinition, drift: &TableDrift) -> Result<Vec<String>, DatabaseError> {
+        let table = definition.name;
+
+        let unfilled: Vec<_> = definition.columns.iter()
+            .filter(|column| drift.missing_columns.contains(&column.name))
+            .filter(|column| !column.nullable && column.default.is_none() && !column.column_type.is_id())
+            .map(|column| format!("{table}.{}", column.name))
+            .collect();
+        if !unfilled.is_empty() {
+            return Err(DatabaseError::SchemaDriftError(format!(
+                "rebuilding {table} on SQLite needs a value for the existing rows in {}, declare a DEFAULT for them",
+                unfilled.join(", "),
+            )));
+        }
+
+        let (columns, values): (Vec<_>, Vec<_>) = definition.columns.iter()
+            .filter(|column| !drift.missing_columns.contains(&column.name))
+            .map(|column| match &column.default {
+                Some(default) if !column.nullable && drift.altered_columns.contains(&column.name) => {
+                    (column.name, format!("COALESCE({}, {default})", column.name))
+                }
+                _ => (column.name, column.name.to_string()),
+            })
+            .unzip();
+        let (columns, values) = (columns.join(", "), values.join(", "));
+
+        let mut create = definition.create(&DatabaseScheme::SQLITE);
+        let create_table = create.remove(0).replacen(
+            &format!("CREATE TABLE IF NOT EXISTS {table} ("),
+            &format!("CREATE TABLE {table}__rebuild ("),
+            1,
+        );
+
+        let mut statements = vec![create_table];
+        statements.extend([
+            format!("INSERT INTO {table}__rebuild ({columns}) SELECT {values} FROM {table};"),
+            format!("DROP TABLE {table};"),
+            format!("ALTER TABLE {table}__rebuild RENAME TO {table};"),
+        ]);
+        statements.extend(create);
+
+        Ok(statements)
+    }
 }
 
 impl Default for SchemaManager {
@@ -54,6 +450,11 @@ impl Default for SchemaManager {
         SchemaManager::new(
             vec![
                 Box::new(UserTable),
+                Box::new(ExternalIdentityTable),
+                Box::new(OAuthClientTable),
+                Box::new(AuthorizationGrantTable),
+                Box::new(AuditEventTable),
+                Box::new(RateLimitBucketTable),
             ]
         )
     }
@@ -61,85 +462,50 @@ impl Default for SchemaManager {
 
 #[cfg(test)]
 mod tests {
+    use crate::entities::{Column, ColumnType, ForeignKey, TableDefinition};
     use super::*;
 
     #[derive(Clone)]
     struct MockGroupTable;
     impl Table for MockGroupTable {
-        fn name(&self) -> &'static str {
-            "groups"
-        }
-
-        fn create(&self, _: &DatabaseScheme) -> String {
-            "CREATE TABLE groups;".to_string()
-        }
-
-        fn dispose(&self) -> String {
-            "DROP TABLE groups;".to_string()
-        }
-
-        fn dependencies(&self) -> Vec<&'static str> {
-            vec![]
+        fn definition(&self) -> TableDefinition {
+            TableDefinition::new("groups")
+                .column(Column::new("id", ColumnType::Id))
         }
     }
 
     #[derive(Clone)]
     struct MockUserTable;
     impl Table for MockUserTable {
-        fn name(&self) -> &'static str {
-            "users"
-        }
-
-        fn create(&self, _: &DatabaseScheme) -> String {
-            "CREATE TABLE users;".to_string()
-        }
-
-        fn dispose(&self) -> String {
-            "DROP TABLE users;".to_string()
-        }
-
-        fn dependencies(&self) -> Vec<&'static str> {
-            vec!["groups"]
+        fn definition(&self) -> TableDefinition {
+            TableDefinition::new("users")
+                .column(Column::new("id", ColumnType::Id))
+                .column(Column::new("group_id", ColumnType::Integer))
+                .foreign_key(ForeignKey::new(&["group_id"], "groups", &["id"]))
         }
     }
 
     #[derive(Clone)]
     struct MockToolTable;
     impl Table for MockToolTable {
-        fn name(&self) -> &'static str {
-            "tools"
-        }
-
-        fn create(&self, _: &DatabaseScheme) -> String {
-            "CREATE TABLE tools;".to_string()
-        }
-
-        fn dispose(&self) -> String {
-            "DROP TABLE tools;".to_string()
-        }
-
-        fn dependencies(&self) -> Vec<&'static str> {
-            vec!["groups"]
+        fn definition(&self) -> TableDefinition {
+            TableDefinition::new("tools")
+                .column(Column::new("id", ColumnType::Id))
+                .column(Column::new("group_id", ColumnType::Integer))
+                .foreign_key(ForeignKey::new(&["group_id"], "groups", &["id"]))
         }
     }
 
     #[derive(Clone)]
     struct MockUserRegionTable;
     impl Table for MockUserRegionTable {
-        fn name(&self) -> &'static str {
-            "users_tools_link"
-        }
-
-        fn create(&self, _: &DatabaseScheme) -> String {
-            "CREATE TABLE users_tools_link;".to_string()
-        }
-
-        fn dispose(&self) -> String {
-            "DROP TABLE users_tools_link;".to_string()
-        }
-
-        fn dependencies(&self) -> Vec<&'static str> {
-            vec!["users", "tools"]
+        fn definition(&self) -> TableDefinition {
+            TableDefinition::new("users_tools_link")
+                .column(Column::new("user_id", ColumnType::Integer))
+                .column(Column::new("tool_id", ColumnType::Integer))
+                .primary_key(&["user_id", "tool_id"])
+                .foreign_key(ForeignKey::new(&["user_id"], "users", &["id"]))
+                .foreign_key(ForeignKey::new(&["tool_id"], "tools", &["id"]))
         }
     }
 
@@ -155,9 +521,85 @@ mod tests {
         let manager = SchemaManager::new(tables);
         let statements = manager.create_schema(&DatabaseScheme::POSTGRES);
 
-        assert_eq!(statements[0], "CREATE TABLE groups;");
-        assert_eq!(statements[1], "CREATE TABLE users;");
-        assert_eq!(statements[2], "CREATE TABLE tools;");
-        assert_eq!(statements[3], "CREATE TABLE users_tools_link;");
+        assert!(statements[0].starts_with("CREATE TABLE IF NOT EXISTS groups ("));
+        assert!(statements[1].starts_with("CREATE TABLE IF NOT EXISTS users ("));
+        assert!(statements[2].starts_with("CREATE TABLE IF NOT EXISTS tools ("));
+        assert!(statements[3].starts_with("CREATE TABLE IF NOT EXISTS users_tools_link ("));
+        assert_eq!(manager.dispose_schema()[0], "DROP TABLE IF EXISTS users_tools_link;");
+    }
+
+    #[test]
+    fn test_generate_migration_from_drift() {
+        let column = |name: &str, data_type: &str, nullable| LiveColumn {
+            name: name.into(),
+            data_type: data_type.into(),
+            nullable,
+        };
+        let live = vec![
+            LiveTable {
+                name: "users".into(),
+                columns: vec![
+                    column("id", "integer", false),
+                    column("username", "character varying(255)", false),
+                    column("email", "character varying(100)", false),
+                    column("role", "character varying(255)", false),
+                    column("nickname", "text", true),
+                ],
+                indexes: vec![LiveIndex { name: "users_nickname".into(), columns: vec!["nickname".into()], unique: false }],
+            },
+            LiveTable { name: "legacy_sessions".into(), columns: Vec::new(), indexes: Vec::new() },
+        ];
+        let manager = SchemaManager::new(vec![Box::new(UserTable)]);
+
+        let drift = manager.drift(&live, &DatabaseScheme::POSTGRES);
+
+        assert_eq!(drift.unexpected_tables, vec!["legacy_sessions"]);
+        assert_eq!(drift.tables, vec![TableDrift {
+            table: "users",
+            missing_columns: vec!["password"],
+            unexpected_columns: vec!["nickname".into()],
+            altered_columns: vec!["email"],
+            missing_indexes: Vec::new(),
+            unexpected_indexes: vec!["users_nickname".into()],
+        }]);
+
+        let migration = manager.migration(&drift, &DatabaseScheme::POSTGRES, true).unwrap();
+
+        assert_eq!(migration, [
+            "DROP TABLE IF EXISTS legacy_sessions;",
+            "DROP INDEX IF EXISTS users_nickname;",
+            "ALTER TABLE users ADD COLUMN password VARCHAR(255);",
+            "-- TODO: backfill users.password before the next statement makes it NOT NULL.",
+            "ALTER TABLE users ALTER COLUMN password SET NOT NULL;",
+            "ALTER TABLE users DROP COLUMN nickname;",
+            "ALTER TABLE users ALTER COLUMN email TYPE VARCHAR(255) USING email::VARCHAR(255);",
+            "ALTER TABLE users ALTER COLUMN email SET NOT NULL;\n",
+        ].join("\n"));
+
+        let withheld = manager.migration(&drift, &DatabaseScheme::POSTGRES, false).unwrap();
+
+        assert!(withheld.contains("\n-- DROP TABLE IF EXISTS legacy_sessions;\n"), "Drops should need --allow-drop.");
+        assert!(withheld.contains("\n-- ALTER TABLE users DROP COLUMN nickname;\n"));
+
+        let refused = manager.migration(&manager.drift(&live, &DatabaseScheme::SQLITE), &DatabaseScheme::SQLITE, true);
+
+        assert!(
+            matches!(refused, Err(DatabaseError::SchemaDriftError(reason)) if reason.contains("users.password")),
+            "A new NOT NULL column without a default leaves the existing rows without a value.",
+        );
+
+        let mut live = live;
+        live[0].columns.retain(|column| column.name != "role");
+        live[0].columns.push(column("password", "varchar(255)", true));
+        let migration = manager.migration(&manager.drift(&live, &DatabaseScheme::SQLITE), &DatabaseScheme::SQLITE, true).unwrap();
+
+        assert!(migration.starts_with("-- no-transaction\n"), "SQLite rebuilds must run outside the migrator transaction.");
+        assert!(migration.contains("CREATE TABLE users__rebuild ("));
+        assert!(
+            migration.contains("INSERT INTO users__rebuild (id, username, email, password) SELECT id, username, email, password FROM users;"),
+            "The new role column should take its default.",
+        );
+        assert!(migration.contains("ALTER TABLE users__rebuild RENAME TO users;"));
+        assert!(manager.migration(&SchemaDrift::default(), &DatabaseScheme::SQLITE, false).unwrap().is_empty());
     }
 }
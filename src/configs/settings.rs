@@ -1,32 +1,74 @@
+use std::collections::BTreeMap;
+use std::net::{IpAddr, SocketAddr};
 use std::{env, fs, io};
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
+use jsonwebtoken::Algorithm;
 use serde::{Deserialize, Serialize};
 use toml::map::Map;
 use toml::Value;
 
+use crate::configs::{Argon2Hash, ConfigOrigin, DatabaseScheme, Secret, TraceExporter};
 use crate::errors::ConfigError;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Origins are exact (`https://app.example.com`), subdomain wildcards (`https://*.example.com`) or `*`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Cors {
+    pub allowed_origins: Vec<String>,
+    pub allowed_methods: Vec<String>,
+    pub allowed_headers: Vec<String>,
+    #[serde(default)]
+    pub exposed_headers: Vec<String>,
+    #[serde(default)]
+    pub allow_credentials: bool,
+    pub max_age: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Server {
     pub host: String,
     pub port: u16,
+    pub cors: Cors,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    Pretty,
+    Json,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Logger {
     pub level: String,
+    pub format: LogFormat,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// What startup does to the schema before running migrations.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SchemaMode {
+    /// Leaves the database alone; drift is still reported.
+    None,
+    #[default]
+    CreateIfMissing,
+    /// Drops every table, migration history included, then creates them again. Refused outside development
+    /// unless `confirm_recreate` is set.
+    Recreate,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Database {
     pub migration_path: Option<String>,
-    pub clean_start: bool,
-    pub url: String,
+    #[serde(default)]
+    pub schema_mode: SchemaMode,
+    #[serde(default)]
+    pub confirm_recreate: bool,
+    pub url: Secret,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Control {
     pub embed: bool,
     pub client_id: String,
@@ -34,40 +76,302 @@ pub struct Control {
     pub port: u16,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Argon2 {
+    pub algorithm: String,
+    pub version: u32,
+    pub memory_cost: u32,
+    pub time_cost: u32,
+    pub parallelism: u32,
+    pub pepper: Option<Secret>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub max_length: usize,
+    pub require_lowercase: bool,
+    pub require_uppercase: bool,
+    pub require_digit: bool,
+    pub require_symbol: bool,
+    pub reject_identity: bool,
+    pub breached_list: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OidcProvider {
+    pub issuer: String,
+    pub client_id: String,
+    pub client_secret: Secret,
+    pub redirect_uri: String,
+    #[serde(default = "OidcProvider::default_scopes")]
+    pub scopes: Vec<String>,
+    /// Link first logins to the local account with the same verified email; only for providers that own the emails.
+    #[serde(default)]
+    pub trust_email: bool,
+    /// Accepted ID token algorithms; empty uses the provider's `id_token_signing_alg_values_supported`.
+    #[serde(default)]
+    pub signing_algorithms: Vec<Algorithm>,
+}
+
+impl OidcProvider {
+    fn default_scopes() -> Vec<String> {
+        vec!["openid".into(), "email".into(), "profile".into()]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Auth {
-    pub secret: String,
+    pub secret: Secret,
     pub expiration: u64,
+    pub argon2: Argon2,
+    pub password_policy: PasswordPolicy,
+    #[serde(default)]
+    pub oidc: BTreeMap<String, OidcProvider>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Docs {
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Metrics {
+    pub enabled: bool,
+    pub address: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OtlpProtocol {
+    Grpc,
+    Http,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Otlp {
+    pub endpoint: String,
+    pub protocol: OtlpProtocol,
+    pub service_name: String,
+    pub sample_ratio: f64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Telemetry {
+    pub otlp: Option<Otlp>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RateLimitKey {
+    Ip,
+    User,
+    ApiKey,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RateLimitStore {
+    #[default]
+    Memory,
+    Database,
+}
+
+/// A bucket of `capacity` requests per client, refilled evenly over `period` seconds.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RateLimitPolicy {
+    pub scope: String,
+    pub key: RateLimitKey,
+    pub capacity: u32,
+    pub period: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RateLimit {
+    pub enabled: bool,
+    #[serde(default)]
+    pub store: RateLimitStore,
+    /// Lets requests through when the database store is unreachable instead of answering 503.
+    #[serde(default)]
+    pub fail_open: bool,
+    #[serde(default)]
+    pub policies: Vec<RateLimitPolicy>,
+}
+
+impl RateLimit {
+    /// The policies to enforce, none when disabled; each one is checked either way.
+    pub fn active_policies(&self) -> Result<Vec<RateLimitPolicy>, ConfigError> {
+        for (index, policy) in self.policies.iter().enumerate() {
+            let invalid = |field: &str, reason: &str| ConfigError::InvalidValueError {
+                path: format!("$.rate_limit.policies[{index}].{field}"),
+                reason: reason.into(),
+            };
+
+            if !policy.scope.starts_with('/') {
+                return Err(invalid("scope", "Scopes are path prefixes and must start with '/'"));
+            }
+            if policy.capacity == 0 {
+                return Err(invalid("capacity", "Capacity must be at least 1"));
+            }
+            if policy.period == 0 {
+                return Err(invalid("period", "Period must be at least 1 second"));
+            }
+        }
+
+        Ok(if self.enabled { self.policies.clone() } else { Vec::new() })
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Settings {
     pub server: Server,
     pub logger: Logger,
+    pub docs: Docs,
+    pub metrics: Metrics,
+    #[serde(default)]
+    pub telemetry: Telemetry,
+    #[serde(default)]
+    pub rate_limit: RateLimit,
     pub database: Database,
     pub control: Control,
     pub auth: Auth,
+    #[serde(skip)]
+    pub run_mode: String,
+    /// Layer of every dotted key supplied above the defaults.
+    #[serde(skip)]
+    pub origins: BTreeMap<String, ConfigOrigin>,
+}
+
+const LOG_LEVELS: [&str; 6] = ["trace", "debug", "info", "warn", "error", "off"];
+const MIN_SECRET_LENGTH: usize = 32;
+
+/// Prefix of environment variables overriding settings, e.g. `SMARINTH__DATABASE__URL` for `database.url`.
+pub const ENV_PREFIX: &str = "SMARINTH__";
+
+/// Key holding merge strategies in a layer, either for the table itself (`__merge = "replace"`) or per key
+/// (`__merge = { allowed_origins = "replace", providers = "by_key:name", metrics = "delete" }`).
+const MERGE_DIRECTIVE: &str = "__merge";
+
+/// How a layer combines a value with the one below it.
+#[derive(Debug, Clone, Default, PartialEq)]
+enum MergeStrategy {
+    /// Tables merge key by key, arrays append and anything else is replaced.
+    #[default]
+    Merge,
+    Replace,
+    Append,
+    Prepend,
+    /// Array items with the same value of the field merge, the others are appended.
+    ByKey(String),
+    /// Removes the key from the lower layers; a value given next to it starts from scratch.
+    Delete,
+}
+
+impl MergeStrategy {
+    fn parse(raw: &str, path: &str) -> Result<Self, ConfigError> {
+        match raw {
+            "merge" => Ok(Self::Merge),
+            "replace" => Ok(Self::Replace),
+            "append" => Ok(Self::Append),
+            "prepend" => Ok(Self::Prepend),
+            "delete" => Ok(Self::Delete),
+            _ => match raw.strip_prefix("by_key:").filter(|key| !key.is_empty()) {
+                Some(key) => Ok(Self::ByKey(key.to_string())),
+                None => Err(ConfigError::InvalidValueError {
+                    path: path.to_string(),
+                    reason: format!(
+                        "Unknown merge strategy '{raw}', expected merge, replace, append, prepend, delete or by_key:<field>"
+                    ),
+                }),
+            },
+        }
+    }
 }
 
 impl Settings {
     pub fn new() -> Result<Self, ConfigError> {
+        Self::load(&[], &[])
+    }
+
+    /// Layers, lowest precedence first: the embedded `default.toml`, `configs/{RUN_MODE}.toml`, each of
+    /// `config_files` in order, `SMARINTH__*` environment variables, then `key=value` `overrides`.
+    /// Files merge over the layers below them as directed by their `__merge` keys, see [`MERGE_DIRECTIVE`].
+    pub fn load<P: AsRef<Path>>(config_files: &[P], overrides: &[String]) -> Result<Self, ConfigError> {
         let run_mode = env::var("RUN_MODE").unwrap_or("development".into());
 
-        let mut settings: Settings = toml::from_str(
-            include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/configs/default.toml"))
-        )?;
+        let mut value = Value::from_str(include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/configs/default.toml")))?;
+        Self::resolve_references(&mut value, "$")?;
+
+        let mut origins = BTreeMap::new();
 
-        if let Ok(settings_path) = Self::normalize_path(&format!("configs/{run_mode}.toml")) {
-            if settings_path.exists() {
-                let merged_settings = Self::merge(
-                    Value::try_from(settings.clone())?,
-                    Value::from_str(&String::from_utf8(fs::read(settings_path)?)?)?,
-                    "$",
-                )?;
-                settings = Value::try_into(merged_settings)?;
+        for (index, settings_path) in Self::layer_paths(config_files)?.into_iter().enumerate() {
+            if index == 0 && !settings_path.exists() {
+                continue;
             }
+
+            let mut layer = Value::from_str(&String::from_utf8(fs::read(&settings_path)?)?)?;
+            Self::resolve_references(&mut layer, "$")?;
+
+            let origin = if index == 0 {
+                ConfigOrigin::RunMode(settings_path)
+            } else {
+                ConfigOrigin::File(settings_path)
+            };
+            let mut supplied = layer.clone();
+            Self::strip_directives(&mut supplied, "$")?;
+            Self::record_origin(&mut origins, "", Some(&supplied), &origin);
+
+            value = Self::merge(value, layer, "$")?;
+        }
+
+        let layered = value.clone();
+        let mut assigned = Vec::new();
+
+        for (key, raw) in Self::env_overrides(env::vars()) {
+            Self::set_path(&mut value, &key, &raw)?;
+            assigned.push((key.clone(), raw.clone()));
+
+            let name = format!("{ENV_PREFIX}{}", key.to_ascii_uppercase().replace('.', "__"));
+            Self::record_origin(&mut origins, &key, Self::lookup(&value, &key), &ConfigOrigin::Env(name));
         }
 
+        for assignment in overrides {
+            let (key, raw) = assignment.split_once('=').ok_or_else(|| ConfigError::InvalidValueError {
+                path: assignment.clone(),
+                reason: "Overrides must be written as section.key=value".into(),
+            })?;
+            Self::set_path(&mut value, key.trim(), raw)?;
+            assigned.push((key.trim().to_string(), raw.to_string()));
+            Self::record_origin(&mut origins, key.trim(), Self::lookup(&value, key.trim()), &ConfigOrigin::Cli);
+        }
+
+        // Overrides of keys that no file defines were typed by guessing; when that does not deserialize, the
+        // fields may want the text instead, as an `Option<Secret>` does with `auth.argon2.pepper=12345678`.
+        let guessed: Vec<_> = assigned
+            .into_iter()
+            .rev()
+            .filter(|(key, _)| Self::lookup(&layered, key).is_none())
+            .filter(|(key, _)| Self::lookup(&value, key).is_some_and(|leaf| !leaf.is_str() && !leaf.is_table()))
+            .collect();
+
+        // Keys deleted by a later layer no longer have an origin.
+        origins.retain(|key, _| Self::lookup(&value, key).is_some_and(|leaf| !leaf.is_table()));
+
+        let mut settings: Settings = match value.clone().try_into() {
+            Ok(settings) => settings,
+            Err(err) if !guessed.is_empty() => {
+                for (key, raw) in guessed {
+                    let leaf = key.split('.').try_fold(&mut value, |current, segment| current.get_mut(segment));
+                    if let Some(leaf) = leaf.filter(|leaf| !leaf.is_str()) {
+                        *leaf = Value::String(raw);
+                    }
+                }
+
+                value.try_into().map_err(|_| err)?
+            }
+            Err(err) => Err(err)?,
+        };
+
         if let Some(migrate) = &mut settings.database.migration_path {
             settings.database.migration_path = if Path::new(migrate).is_dir() {
                 Some(Self::normalize_path(migrate)?.to_string_lossy().to_string())
@@ -76,45 +380,451 @@ impl Settings {
             };
         }
 
+        settings.run_mode = run_mode;
+        settings.origins = origins;
+
+        settings.validate()?;
+
         Ok(settings)
     }
 
-    fn merge_table(value: &mut Map<String, Value>, other: Map<String, Value>, path: &str) -> Result<(), ConfigError> {
-        for (name, inner) in other {
-            if let Some(existing) = value.remove(&name) {
-                let inner_path = format!("{path}.{name}");
-                value.insert(name, Self::merge(existing, inner, &inner_path)?);
-            } else {
-                value.insert(name, inner);
+    /// Files layered over the defaults: `configs/{RUN_MODE}.toml`, which may not exist, then `config_files`.
+    pub fn layer_paths<P: AsRef<Path>>(config_files: &[P]) -> io::Result<Vec<PathBuf>> {
+        let run_mode = env::var("RUN_MODE").unwrap_or("development".into());
+
+        let mut paths = vec![Self::normalize_path(&format!("configs/{run_mode}.toml"))?];
+        for config_file in config_files {
+            paths.push(Self::normalize_path(&config_file.as_ref().to_string_lossy())?);
+        }
+
+        Ok(paths)
+    }
+
+    /// The layer that supplied a dotted key, or [`ConfigOrigin::Default`] when none overrode it.
+    pub fn origin(&self, key: &str) -> &ConfigOrigin {
+        static DEFAULT: ConfigOrigin = ConfigOrigin::Default;
+
+        self.origins.get(key).unwrap_or(&DEFAULT)
+    }
+
+    /// Settings with secrets redacted, keyed by dotted path. Unless `effective`, only keys supplied above the
+    /// defaults are kept.
+    pub fn entries(&self, effective: bool) -> Result<BTreeMap<String, Value>, ConfigError> {
+        let mut entries = BTreeMap::new();
+        Self::flatten(&Value::try_from(self)?, "", &mut entries);
+
+        if !effective {
+            entries.retain(|key, _| *self.origin(key) != ConfigOrigin::Default);
+        }
+
+        Ok(entries)
+    }
+
+    pub fn is_production(&self) -> bool {
+        self.run_mode == "production"
+    }
+
+    /// Checks what deserialization cannot, reporting every problem at once.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut errors = Vec::new();
+        let mut invalid = |path: &str, reason: String| {
+            errors.push(ConfigError::InvalidValueError { path: path.into(), reason });
+        };
+
+        let server_ip = self.server.host.parse::<IpAddr>().ok();
+        if server_ip.is_none() {
+            invalid("$.server.host", format!("'{}' is not an IP address", self.server.host));
+        }
+
+        let control_ip = self.control.host.parse::<IpAddr>().ok();
+        if self.control.embed && control_ip.is_none() {
+            invalid("$.control.host", format!("'{}' is not an IP address", self.control.host));
+        }
+
+        let metrics_address = match self.metrics.address.as_ref().filter(|_| self.metrics.enabled) {
+            Some(address) => match address.parse::<SocketAddr>() {
+                Ok(address) => Some(address),
+                Err(_) => {
+                    invalid("$.metrics.address", format!("'{address}' is not an ip:port address"));
+                    None
+                }
+            },
+            None => None,
+        };
+
+        let mut listeners = vec![("$.server.port", server_ip, self.server.port)];
+        if self.control.embed {
+            listeners.push(("$.control.port", control_ip, self.control.port));
+        }
+        if let Some(address) = metrics_address {
+            listeners.push(("$.metrics.address", Some(address.ip()), address.port()));
+        }
+        for (index, (path, ip, port)) in listeners.iter().enumerate() {
+            for (other_path, other_ip, other_port) in &listeners[index + 1..] {
+                let overlaps = match (ip, other_ip) {
+                    (Some(ip), Some(other_ip)) => ip == other_ip || ip.is_unspecified() || other_ip.is_unspecified(),
+                    _ => false,
+                };
+                if port == other_port && overlaps {
+                    invalid(other_path, format!("Port {port} is already used by {path}"));
+                }
+            }
+        }
+
+        let url = self.database.url.expose();
+        if DatabaseScheme::parse(url).is_none() {
+            let scheme = url.split_once(':').map(|(scheme, _)| scheme).unwrap_or_default();
+            let expected = DatabaseScheme::URL_SCHEMES.map(|(name, _)| name).join(", ");
+            invalid("$.database.url", format!("Unsupported scheme '{scheme}', expected one of {expected}"));
+        }
+
+        if self.database.schema_mode == SchemaMode::Recreate && self.run_mode != "development" && !self.database.confirm_recreate {
+            invalid(
+                "$.database.schema_mode",
+                format!(
+                    "recreate drops every table and is refused in run mode '{}' unless database.confirm_recreate is true",
+                    self.run_mode
+                ),
+            );
+        }
+
+        if self.run_mode != "development" && self.auth.secret.expose().len() < MIN_SECRET_LENGTH {
+            invalid(
+                "$.auth.secret",
+                format!("Must be at least {MIN_SECRET_LENGTH} characters outside development"),
+            );
+        }
+
+        if let Some(list) = self.auth.password_policy.breached_list.as_ref().filter(|list| !Path::new(list).exists()) {
+            invalid("$.auth.password_policy.breached_list", format!("'{list}' does not exist"));
+        }
+
+        if self.auth.expiration == 0 {
+            invalid("$.auth.expiration", "Must be greater than zero".into());
+        }
+
+        if !LOG_LEVELS.contains(&self.logger.level.to_ascii_lowercase().as_str()) {
+            invalid(
+                "$.logger.level",
+                format!("'{}' is not one of {}", self.logger.level, LOG_LEVELS.join(", ")),
+            );
+        }
+
+        // Sections with their own parsers, also used by the components, so their errors are reported with the rest.
+        let component_errors = [
+            self.server.cors.parse().err(),
+            self.rate_limit.active_policies().err(),
+            Argon2Hash::with_settings(self).err(),
+            TraceExporter::validate(self).err(),
+        ];
+        errors.extend(component_errors.into_iter().flatten());
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::ValidationError(errors))
+        }
+    }
+
+    fn merge_table(
+        value: &mut Map<String, Value>,
+        other: Map<String, Value>,
+        strategies: BTreeMap<String, MergeStrategy>,
+        path: &str,
+    ) -> Result<(), ConfigError> {
+        for (name, strategy) in &strategies {
+            if *strategy == MergeStrategy::Delete {
+                value.remove(name);
             }
         }
 
+        for (name, mut inner) in other {
+            let inner_path = format!("{path}.{name}");
+
+            let merged = match value.remove(&name) {
+                Some(existing) => Self::merge_with(existing, inner, strategies.get(&name).cloned(), &inner_path)?,
+                None => {
+                    Self::strip_directives(&mut inner, &inner_path)?;
+                    inner
+                }
+            };
+            value.insert(name, merged);
+        }
+
         Ok(())
     }
 
     fn merge(value: Value, other: Value, path: &str) -> Result<Value, ConfigError> {
-        match (value, other) {
-            (Value::String(_), Value::String(inner)) => Ok(Value::String(inner)),
-            (Value::Integer(_), Value::Integer(inner)) => Ok(Value::Integer(inner)),
-            (Value::Float(_), Value::Float(inner)) => Ok(Value::Float(inner)),
-            (Value::Boolean(_), Value::Boolean(inner)) => Ok(Value::Boolean(inner)),
-            (Value::Datetime(_), Value::Datetime(inner)) => Ok(Value::Datetime(inner)),
-            (Value::Array(mut existing), Value::Array(inner)) => {
-                existing.extend(inner);
-                Ok(Value::Array(existing))
-            }
-            (Value::Table(mut existing), Value::Table(inner)) => {
-                Self::merge_table(&mut existing, inner, path)?;
+        Self::merge_with(value, other, None, path)
+    }
+
+    /// Merges `other` over `value`. `strategy` comes from the parent's `__merge` table and wins over a
+    /// `__merge = "..."` string inside `other` itself; without either, tables merge deeply and arrays append.
+    fn merge_with(
+        value: Value,
+        mut other: Value,
+        strategy: Option<MergeStrategy>,
+        path: &str,
+    ) -> Result<Value, ConfigError> {
+        let (own, strategies) = Self::merge_directives(&mut other, path)?;
+        let strategy = strategy.or(own).unwrap_or_default();
+
+        match (strategy, value, other) {
+            (MergeStrategy::Merge, Value::Table(mut existing), Value::Table(inner)) => {
+                Self::merge_table(&mut existing, inner, strategies, path)?;
                 Ok(Value::Table(existing))
             }
-            (v, o) => Err(
-                ConfigError::IncompatibleTypeError {
-                    path: String::from(path),
-                    expected_type: String::from(v.type_str()),
-                    actual_type: String::from(o.type_str()),
+            (MergeStrategy::ByKey(key), Value::Array(existing), Value::Array(inner)) => {
+                Ok(Value::Array(Self::merge_by_key(existing, inner, &key, path)?))
+            }
+            (strategy, value, mut other) => {
+                Self::strip_directives(&mut other, path)?;
+
+                match (strategy, value, other) {
+                    (MergeStrategy::Replace | MergeStrategy::Delete, _, inner) => Ok(inner),
+                    (MergeStrategy::Merge | MergeStrategy::Append, Value::Array(mut existing), Value::Array(inner)) => {
+                        existing.extend(inner);
+                        Ok(Value::Array(existing))
+                    }
+                    (MergeStrategy::Prepend, Value::Array(existing), Value::Array(mut inner)) => {
+                        inner.extend(existing);
+                        Ok(Value::Array(inner))
+                    }
+                    (MergeStrategy::Append | MergeStrategy::Prepend | MergeStrategy::ByKey(_), v, o) => {
+                        Err(ConfigError::IncompatibleTypeError {
+                            path: String::from(path),
+                            expected_type: String::from("array"),
+                            actual_type: String::from(if v.is_array() { o.type_str() } else { v.type_str() }),
+                        })
+                    }
+                    (MergeStrategy::Merge, Value::String(_), Value::String(inner)) => Ok(Value::String(inner)),
+                    (MergeStrategy::Merge, Value::Integer(_), Value::Integer(inner)) => Ok(Value::Integer(inner)),
+                    (MergeStrategy::Merge, Value::Float(_), Value::Float(inner)) => Ok(Value::Float(inner)),
+                    (MergeStrategy::Merge, Value::Boolean(_), Value::Boolean(inner)) => Ok(Value::Boolean(inner)),
+                    (MergeStrategy::Merge, Value::Datetime(_), Value::Datetime(inner)) => Ok(Value::Datetime(inner)),
+                    (_, v, o) => Err(
+                        ConfigError::IncompatibleTypeError {
+                            path: String::from(path),
+                            expected_type: String::from(v.type_str()),
+                            actual_type: String::from(o.type_str()),
+                        }
+                    ),
+                }
+            }
+        }
+    }
+
+    /// Merges array items sharing the same `key` field deeply and appends the rest.
+    fn merge_by_key(mut existing: Vec<Value>, inner: Vec<Value>, key: &str, path: &str) -> Result<Vec<Value>, ConfigError> {
+        for (index, mut item) in inner.into_iter().enumerate() {
+            let item_path = format!("{path}[{index}]");
+            let id = item.get(key).cloned().ok_or_else(|| ConfigError::InvalidValueError {
+                path: item_path.clone(),
+                reason: format!("Items merged by key need a '{key}' field"),
+            })?;
+
+            match existing.iter().position(|candidate| candidate.get(key) == Some(&id)) {
+                Some(position) => {
+                    let current = existing.remove(position);
+                    existing.insert(position, Self::merge(current, item, &item_path)?);
+                }
+                None => {
+                    Self::strip_directives(&mut item, &item_path)?;
+                    existing.push(item);
+                }
+            }
+        }
+
+        Ok(existing)
+    }
+
+    /// Takes the `__merge` key out of a table: the table's own strategy and the strategies of its keys.
+    fn merge_directives(
+        value: &mut Value,
+        path: &str,
+    ) -> Result<(Option<MergeStrategy>, BTreeMap<String, MergeStrategy>), ConfigError> {
+        let directive_path = format!("{path}.{MERGE_DIRECTIVE}");
+
+        match value.as_table_mut().and_then(|table| table.remove(MERGE_DIRECTIVE)) {
+            None => Ok((None, BTreeMap::new())),
+            Some(Value::String(raw)) => match MergeStrategy::parse(&raw, &directive_path)? {
+                MergeStrategy::Delete => Err(ConfigError::InvalidValueError {
+                    path: directive_path,
+                    reason: "delete only applies to keys of a parent's __merge table".into(),
+                }),
+                strategy => Ok((Some(strategy), BTreeMap::new())),
+            },
+            Some(Value::Table(directives)) => {
+                let mut strategies = BTreeMap::new();
+
+                for (name, raw) in directives {
+                    let key_path = format!("{directive_path}.{name}");
+                    let raw = raw.as_str().ok_or_else(|| ConfigError::IncompatibleTypeError {
+                        path: key_path.clone(),
+                        expected_type: "string".into(),
+                        actual_type: raw.type_str().into(),
+                    })?;
+                    strategies.insert(name, MergeStrategy::parse(raw, &key_path)?);
+                }
+
+                Ok((None, strategies))
+            }
+            Some(other) => Err(ConfigError::IncompatibleTypeError {
+                path: directive_path,
+                expected_type: "string or table".into(),
+                actual_type: other.type_str().into(),
+            }),
+        }
+    }
+
+    /// Drops every `__merge` key from a value that is taken as is.
+    fn strip_directives(value: &mut Value, path: &str) -> Result<(), ConfigError> {
+        Self::merge_directives(value, path)?;
+
+        match value {
+            Value::Table(table) => {
+                for (name, inner) in table.iter_mut() {
+                    Self::strip_directives(inner, &format!("{path}.{name}"))?;
+                }
+            }
+            Value::Array(items) => {
+                for (index, item) in items.iter_mut().enumerate() {
+                    Self::strip_directives(item, &format!("{path}[{index}]"))?;
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Replaces every `{ file = "..." }` or `{ env = "..." }` table with the string it points to.
+    fn resolve_references(value: &mut Value, path: &str) -> Result<(), ConfigError> {
+        match value {
+            Value::Table(table) => {
+                if let Some(resolved) = Self::reference(table, path)? {
+                    *value = Value::String(resolved);
+                    return Ok(());
+                }
+
+                for (name, inner) in table.iter_mut() {
+                    Self::resolve_references(inner, &format!("{path}.{name}"))?;
+                }
+            }
+            Value::Array(items) => {
+                for (index, item) in items.iter_mut().enumerate() {
+                    Self::resolve_references(item, &format!("{path}[{index}]"))?;
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn reference(table: &Map<String, Value>, path: &str) -> Result<Option<String>, ConfigError> {
+        if table.len() != 1 {
+            return Ok(None);
+        }
+
+        let invalid = |reason: String| ConfigError::InvalidValueError { path: path.to_string(), reason };
+
+        match table.iter().next() {
+            Some((kind, Value::String(file))) if kind == "file" => fs::read_to_string(file)
+                .map(|content| Some(content.trim_end_matches(['\n', '\r']).to_string()))
+                .map_err(|e| invalid(format!("Cannot read secret file '{file}': {e}"))),
+            Some((kind, Value::String(name))) if kind == "env" => env::var(name)
+                .map(Some)
+                .map_err(|_| invalid(format!("Environment variable '{name}' is not set"))),
+            _ => Ok(None),
+        }
+    }
+
+    /// Arrays are leaves, so a list is attributed to the last layer that changed it.
+    fn flatten(value: &Value, key: &str, entries: &mut BTreeMap<String, Value>) {
+        match value {
+            Value::Table(table) => {
+                for (name, inner) in table {
+                    let inner_key = if key.is_empty() { name.clone() } else { format!("{key}.{name}") };
+                    Self::flatten(inner, &inner_key, entries);
                 }
-            ),
+            }
+            _ => {
+                entries.insert(key.to_string(), value.clone());
+            }
+        }
+    }
+
+    fn record_origin(origins: &mut BTreeMap<String, ConfigOrigin>, key: &str, value: Option<&Value>, origin: &ConfigOrigin) {
+        let mut leaves = BTreeMap::new();
+        if let Some(value) = value {
+            Self::flatten(value, key, &mut leaves);
+        }
+
+        for leaf in leaves.into_keys() {
+            origins.insert(leaf, origin.clone());
+        }
+    }
+
+    fn lookup<'a>(value: &'a Value, key: &str) -> Option<&'a Value> {
+        key.split('.').try_fold(value, |current, segment| current.get(segment))
+    }
+
+    /// `SMARINTH__SECTION__KEY=value` pairs as `section.key` paths.
+    fn env_overrides(vars: impl Iterator<Item = (String, String)>) -> Vec<(String, String)> {
+        let mut overrides: Vec<_> = vars
+            .filter_map(|(name, raw)| {
+                let key = name.strip_prefix(ENV_PREFIX)?;
+
+                (!key.is_empty()).then(|| (key.to_ascii_lowercase().replace("__", "."), raw))
+            })
+            .collect();
+        overrides.sort();
+
+        overrides
+    }
+
+    /// Replaces the value at a dotted `path`. Strings stay verbatim where the current value is a string,
+    /// anything else is read as a TOML literal so `8080`, `true` or `["a", "b"]` keep their types.
+    fn set_path(value: &mut Value, path: &str, raw: &str) -> Result<(), ConfigError> {
+        let mut current = value;
+        let mut segments = path.split('.').peekable();
+        let mut walked = String::from("$");
+
+        while let Some(segment) = segments.next() {
+            if segment.is_empty() {
+                return Err(ConfigError::InvalidValueError {
+                    path: path.to_string(),
+                    reason: "Keys cannot contain empty segments".into(),
+                });
+            }
+            let actual_type = current.type_str();
+            let table = current.as_table_mut().ok_or_else(|| ConfigError::IncompatibleTypeError {
+                path: walked.clone(),
+                expected_type: "table".into(),
+                actual_type: actual_type.into(),
+            })?;
+            walked = format!("{walked}.{segment}");
+
+            if segments.peek().is_none() {
+                let parsed = match table.get(segment) {
+                    Some(Value::String(_)) => Value::String(raw.to_string()),
+                    _ => toml::from_str::<Map<String, Value>>(&format!("value = {raw}"))
+                        .ok()
+                        .and_then(|mut literal| literal.remove("value"))
+                        .unwrap_or_else(|| Value::String(raw.to_string())),
+                };
+                table.insert(segment.to_string(), parsed);
+
+                return Ok(());
+            }
+
+            current = table
+                .entry(segment.to_string())
+                .or_insert_with(|| Value::Table(Map::new()));
         }
+
+        Ok(())
     }
 
     fn normalize_path(path: &str) -> io::Result<PathBuf> {
@@ -156,4 +866,240 @@ port = 9090
         assert_eq!(merged_table["server"]["host"], "127.0.0.1".into());
         assert_eq!(merged_table["server"]["port"], 9090.into());
     }
+
+    #[test]
+    fn test_merge_strategies() {
+        let table1: Value = toml::from_str(
+            r#"
+[server.cors]
+allowed_origins = ["https://a.example.com", "https://b.example.com"]
+allowed_methods = ["GET"]
+allowed_headers = ["authorization"]
+
+[metrics]
+enabled = true
+
+[[auth.oidc.providers]]
+name = "google"
+client_id = "google-client"
+
+[[auth.oidc.providers]]
+name = "github"
+client_id = "github-client"
+"#
+        )
+            .unwrap();
+
+        let table2: Value = toml::from_str(
+            r#"
+__merge = { metrics = "delete" }
+
+[server.cors]
+__merge = { allowed_origins = "replace", allowed_methods = "prepend" }
+allowed_origins = ["https://c.example.com"]
+allowed_methods = ["POST"]
+allowed_headers = ["content-type"]
+
+[auth.oidc]
+__merge = { providers = "by_key:name" }
+
+[[auth.oidc.providers]]
+name = "github"
+client_id = "github-client-2"
+
+[[auth.oidc.providers]]
+name = "gitlab"
+client_id = "gitlab-client"
+"#,
+        )
+            .unwrap();
+
+        let merged = Settings::merge(table1, table2, "$").unwrap();
+        let cors = &merged["server"]["cors"];
+        let providers = merged["auth"]["oidc"]["providers"].as_array().unwrap();
+
+        assert_eq!(cors["allowed_origins"], Value::Array(vec!["https://c.example.com".into()]));
+        assert_eq!(cors["allowed_methods"], Value::Array(vec!["POST".into(), "GET".into()]));
+        assert_eq!(cors["allowed_headers"], Value::Array(vec!["authorization".into(), "content-type".into()]));
+        assert!(merged.get("metrics").is_none(), "Deleted keys should not survive the merge.");
+        assert!(merged.get(MERGE_DIRECTIVE).is_none() && cors.get(MERGE_DIRECTIVE).is_none());
+        assert_eq!(providers.len(), 3);
+        assert_eq!(providers[1]["client_id"], "github-client-2".into());
+        assert_eq!(providers[2]["name"], "gitlab".into());
+
+        let invalid: Value = toml::from_str("[server]\n__merge = { port = \"sideways\" }\nport = 1").unwrap();
+        assert!(Settings::merge(merged, invalid, "$").is_err());
+    }
+
+    #[test]
+    fn test_env_overrides() {
+        let vars = [
+            ("SMARINTH__DATABASE__URL".to_string(), "postgres://db/smarinth".to_string()),
+            ("SMARINTH__DATABASE__SCHEMA_MODE".to_string(), "none".to_string()),
+            ("OTHER__DATABASE__URL".to_string(), "ignored".to_string()),
+        ];
+
+        let overrides = Settings::env_overrides(vars.into_iter());
+
+        assert_eq!(
+            overrides,
+            vec![
+                ("database.schema_mode".to_string(), "none".to_string()),
+                ("database.url".to_string(), "postgres://db/smarinth".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_set_path_keeps_types() {
+        let mut value: Value = toml::from_str(
+            r#"
+[auth]
+secret = "smarinth-secret"
+expiration = 233333
+"#
+        )
+            .unwrap();
+
+        Settings::set_path(&mut value, "auth.secret", "123456").unwrap();
+        Settings::set_path(&mut value, "auth.expiration", "60").unwrap();
+        Settings::set_path(&mut value, "metrics.address", "127.0.0.1:9090").unwrap();
+
+        assert_eq!(value["auth"]["secret"], "123456".into(), "Strings should not be reparsed as numbers.");
+        assert_eq!(value["auth"]["expiration"], 60.into());
+        assert_eq!(value["metrics"]["address"], "127.0.0.1:9090".into());
+        assert!(Settings::set_path(&mut value, "auth.secret.nested", "x").is_err());
+    }
+
+    #[test]
+    fn test_untyped_override_falls_back_to_string() {
+        let settings = Settings::load::<&str>(
+            &[],
+            &["auth.argon2.pepper=12345678".to_string(), "server.port=9494".to_string()],
+        )
+            .unwrap();
+
+        assert_eq!(settings.auth.argon2.pepper.as_ref().map(Secret::expose), Some("12345678"));
+        assert_eq!(settings.server.port, 9494);
+    }
+
+    #[test]
+    fn test_load_layers_files_and_overrides() {
+        let config_path = env::temp_dir().join("smarinth_test_load_layers.toml");
+        fs::write(&config_path, "[server]\nport = 9090\n\n[logger]\nlevel = \"warn\"\n").unwrap();
+
+        let settings = Settings::load(&[&config_path], &["logger.level=error".to_string()]).unwrap();
+
+        assert_eq!(settings.server.port, 9090);
+        assert_eq!(settings.logger.level, "error", "--set should win over config files.");
+        assert!(Settings::load(&[&config_path], &["logger.level".to_string()]).is_err());
+
+        fs::remove_file(config_path).unwrap();
+    }
+
+    #[test]
+    fn test_resolve_secret_references() {
+        let secret_path = env::temp_dir().join("smarinth_test_jwt_secret");
+        fs::write(&secret_path, "test_file_secret\n").unwrap();
+        env::set_var("SMARINTH_TEST_DB_URL", "postgres://smarinth:test_password@db/smarinth");
+
+        let mut value: Value = toml::from_str(&format!(
+            r#"
+[auth]
+secret = {{ file = "{}" }}
+
+[database]
+url = {{ env = "SMARINTH_TEST_DB_URL" }}
+"#,
+            secret_path.display()
+        ))
+            .unwrap();
+
+        Settings::resolve_references(&mut value, "$").unwrap();
+
+        assert_eq!(value["auth"]["secret"], "test_file_secret".into());
+        assert_eq!(value["database"]["url"], "postgres://smarinth:test_password@db/smarinth".into());
+
+        let mut missing: Value = toml::from_str(r#"secret = { env = "SMARINTH_TEST_UNSET_VARIABLE" }"#).unwrap();
+
+        assert!(Settings::resolve_references(&mut missing, "$").is_err());
+
+        fs::remove_file(secret_path).unwrap();
+    }
+
+    #[test]
+    fn test_redact_secrets() {
+        let settings = Settings::new().unwrap();
+
+        let debug = format!("{settings:?}");
+        let dump = toml::to_string(&settings).unwrap();
+
+        assert!(!debug.contains(settings.auth.secret.expose()));
+        assert!(!dump.contains(settings.auth.secret.expose()));
+        assert!(dump.contains("[redacted]"));
+    }
+
+    #[test]
+    fn test_validate_reports_every_problem() {
+        let mut settings = Settings::new().unwrap();
+        settings.run_mode = "production".into();
+        settings.server.host = "localhost".into();
+        settings.control.port = settings.server.port;
+        settings.control.host = "0.0.0.0".into();
+        settings.database.url = "oracle://db/smarinth".into();
+        settings.auth.expiration = 0;
+        settings.logger.level = "verbose".into();
+        settings.server.cors.allowed_origins = vec!["https://example.com/".into()];
+        settings.rate_limit.policies[0].capacity = 0;
+        settings.auth.argon2.algorithm = "argon3".into();
+
+        let Err(ConfigError::ValidationError(errors)) = settings.validate() else {
+            panic!("Invalid settings should not validate.");
+        };
+        let paths: Vec<_> = errors
+            .iter()
+            .map(|error| match error {
+                ConfigError::InvalidValueError { path, .. } => path.as_str(),
+                _ => unreachable!(),
+            })
+            .collect();
+
+        assert_eq!(
+            paths,
+            vec![
+                "$.server.host",
+                "$.database.url",
+                "$.auth.secret",
+                "$.auth.expiration",
+                "$.logger.level",
+                "$.server.cors.allowed_origins[0]",
+                "$.rate_limit.policies[0].capacity",
+                "$.auth.argon2.algorithm",
+            ]
+        );
+
+        settings.server.host = "127.0.0.1".into();
+        let Err(ConfigError::ValidationError(errors)) = settings.validate() else {
+            panic!("Invalid settings should not validate.");
+        };
+
+        assert!(errors[0].to_string().contains("$.control.port"), "0.0.0.0 overlaps every other host.");
+    }
+
+    #[test]
+    fn test_refuse_recreate_outside_development() {
+        let mut settings = Settings::new().unwrap();
+        settings.database.schema_mode = SchemaMode::Recreate;
+
+        assert!(settings.validate().is_ok(), "Development may recreate the schema.");
+
+        settings.run_mode = "prodution".into();
+        settings.auth.secret = "a-production-secret-of-32-characters".into();
+
+        assert!(settings.validate().unwrap_err().to_string().contains("$.database.schema_mode"));
+
+        settings.database.confirm_recreate = true;
+
+        assert!(settings.validate().is_ok());
+    }
 }
@@ -0,0 +1,117 @@
+use std::sync::Arc;
+
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry::{global, KeyValue};
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{Sampler, Tracer, TracerProvider};
+use opentelemetry_sdk::{runtime, Resource};
+
+use crate::errors::ConfigError;
+use super::settings::{OtlpProtocol, Settings};
+
+/// Exports `tracing` spans over OTLP while alive; flushes pending spans on shutdown.
+pub struct TraceExporter {
+    provider: TracerProvider,
+}
+
+impl TraceExporter {
+    /// Checks `[telemetry.otlp]` without installing anything.
+    pub fn validate(settings: &Settings) -> Result<(), ConfigError> {
+        let Some(config) = &settings.telemetry.otlp else {
+            return Ok(());
+        };
+
+        if !(0.0..=1.0).contains(&config.sample_ratio) {
+            Err(ConfigError::InvalidValueError {
+                path: "$.telemetry.otlp.sample_ratio".into(),
+                reason: "must be between 0.0 and 1.0".into(),
+            })?
+        }
+
+        if let Err(e) = url::Url::parse(&config.endpoint) {
+            Err(ConfigError::InvalidValueError {
+                path: "$.telemetry.otlp.endpoint".into(),
+                reason: e.to_string(),
+            })?
+        }
+
+        Ok(())
+    }
+
+    /// Installs the exporter described by `[telemetry.otlp]`, or nothing when the section is absent.
+    pub fn with_settings(settings: &Arc<Settings>) -> Result<Option<Self>, ConfigError> {
+        global::set_text_map_propagator(TraceContextPropagator::new());
+
+        Self::validate(settings)?;
+
+        let Some(config) = &settings.telemetry.otlp else {
+            return Ok(None);
+        };
+
+        let exporter = match config.protocol {
+            OtlpProtocol::Grpc => SpanExporter::builder().with_tonic().with_endpoint(&config.endpoint).build(),
+            OtlpProtocol::Http => SpanExporter::builder().with_http().with_endpoint(&config.endpoint).build(),
+        }
+        .map_err(|e| ConfigError::InvalidValueError {
+            path: "$.telemetry.otlp.endpoint".into(),
+            reason: e.to_string(),
+        })?;
+
+        let provider = TracerProvider::builder()
+            .with_batch_exporter(exporter, runtime::Tokio)
+            .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(config.sample_ratio))))
+            .with_resource(Resource::new(vec![
+                KeyValue::new("service.name", config.service_name.clone()),
+                KeyValue::new("service.version", env!("CARGO_PKG_VERSION")),
+            ]))
+            .build();
+
+        global::set_tracer_provider(provider.clone());
+
+        Ok(Some(Self { provider }))
+    }
+
+    pub fn tracer(&self) -> Tracer {
+        self.provider.tracer(env!("CARGO_PKG_NAME"))
+    }
+
+    pub fn shutdown(&self) {
+        if let Err(err) = self.provider.shutdown() {
+            tracing::warn!("failed to flush trace exporter: {err}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod telemetry_tests {
+    use super::*;
+    use crate::configs::settings::Otlp;
+
+    fn settings_with(otlp: Option<Otlp>) -> Arc<Settings> {
+        let mut settings = Settings::new().unwrap();
+        settings.telemetry.otlp = otlp;
+
+        Arc::new(settings)
+    }
+
+    #[test]
+    fn test_disabled_without_config() {
+        assert!(TraceExporter::with_settings(&settings_with(None)).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_reject_invalid_sample_ratio() {
+        let settings = settings_with(Some(Otlp {
+            endpoint: "http://127.0.0.1:4318/v1/traces".into(),
+            protocol: OtlpProtocol::Http,
+            service_name: "smarinth".into(),
+            sample_ratio: 1.5,
+        }));
+
+        assert!(matches!(
+            TraceExporter::with_settings(&settings),
+            Err(ConfigError::InvalidValueError { path, .. }) if path == "$.telemetry.otlp.sample_ratio"
+        ));
+    }
+}
@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+
+use crate::entities::{Column, ColumnType, Table, TableDefinition};
+
+pub const AUDIT_SUCCESS: &str = "success";
+pub const AUDIT_FAILURE: &str = "failure";
+/// Length of the short text columns; longer values are truncated before they are written.
+pub const AUDIT_TEXT_LENGTH: u32 = 255;
+
+#[derive(sqlx::FromRow, Clone, Deserialize, Serialize)]
+pub struct AuditEvent {
+    pub id: i64,
+    pub created_at: i64,
+    pub action: String,
+    pub outcome: String,
+    pub actor_id: Option<i32>,
+    pub actor: Option<String>,
+    pub target: Option<String>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub diff: Option<String>,
+}
+
+/// Append-only: events are never updated, and outlive the users they mention.
+#[derive(Clone)]
+pub struct AuditEventTable;
+
+impl Table for AuditEventTable {
+    fn definition(&self) -> TableDefinition {
+        TableDefinition::new("audit_events")
+            .column(Column::new("id", ColumnType::BigId))
+            .column(Column::new("created_at", ColumnType::BigInteger))
+            .column(Column::new("action", ColumnType::Text(AUDIT_TEXT_LENGTH)))
+            .column(Column::new("outcome", ColumnType::Text(AUDIT_TEXT_LENGTH)))
+            .column(Column::new("actor_id", ColumnType::Integer).nullable())
+            .column(Column::new("actor", ColumnType::Text(AUDIT_TEXT_LENGTH)).nullable())
+            .column(Column::new("target", ColumnType::Text(AUDIT_TEXT_LENGTH)).nullable())
+            .column(Column::new("ip", ColumnType::Text(AUDIT_TEXT_LENGTH)).nullable())
+            .column(Column::new("user_agent", ColumnType::Text(AUDIT_TEXT_LENGTH)).nullable())
+            .column(Column::new("diff", ColumnType::LongText).nullable())
+            .check("audit_events_outcome", format!("outcome IN ('{AUDIT_SUCCESS}', '{AUDIT_FAILURE}')"))
+            .index("audit_events_created_at", &["created_at"])
+    }
+}
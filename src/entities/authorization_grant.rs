@@ -0,0 +1,33 @@
+use serde::{Deserialize, Serialize};
+
+use crate::entities::{Column, ColumnType, ForeignKey, ReferentialAction, Table, TableDefinition};
+
+/// A pending authorization code; only the SHA-256 of the code is stored.
+#[derive(sqlx::FromRow, Clone, Deserialize, Serialize)]
+pub struct AuthorizationGrant {
+    pub code_hash: String,
+    pub client_id: String,
+    pub user_id: i32,
+    pub redirect_uri: String,
+    pub scope: String,
+    pub code_challenge: String,
+    pub created_at: i64,
+}
+
+#[derive(Clone)]
+pub struct AuthorizationGrantTable;
+
+impl Table for AuthorizationGrantTable {
+    fn definition(&self) -> TableDefinition {
+        TableDefinition::new("oauth_grants")
+            .column(Column::new("code_hash", ColumnType::Text(255)).primary())
+            .column(Column::new("client_id", ColumnType::Text(255)))
+            .column(Column::new("user_id", ColumnType::Integer))
+            .column(Column::new("redirect_uri", ColumnType::Text(2048)))
+            .column(Column::new("scope", ColumnType::Text(2048)))
+            .column(Column::new("code_challenge", ColumnType::Text(255)))
+            .column(Column::new("created_at", ColumnType::BigInteger))
+            .foreign_key(ForeignKey::new(&["user_id"], "users", &["id"]).on_delete(ReferentialAction::Cascade))
+            .index("oauth_grants_created_at", &["created_at"])
+    }
+}
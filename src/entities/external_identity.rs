@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+
+use crate::entities::{Column, ColumnType, ForeignKey, ReferentialAction, Table, TableDefinition};
+
+#[derive(sqlx::FromRow, Clone, Deserialize, Serialize)]
+pub struct ExternalIdentity {
+    pub id: i32,
+    pub user_id: i32,
+    pub provider: String,
+    pub subject: String,
+    pub email: Option<String>,
+}
+
+#[derive(Clone)]
+pub struct ExternalIdentityTable;
+
+impl Table for ExternalIdentityTable {
+    fn definition(&self) -> TableDefinition {
+        TableDefinition::new("user_identities")
+            .column(Column::new("id", ColumnType::Id))
+            .column(Column::new("user_id", ColumnType::Integer))
+            .column(Column::new("provider", ColumnType::Text(255)))
+            .column(Column::new("subject", ColumnType::Text(255)))
+            .column(Column::new("email", ColumnType::Text(255)).nullable())
+            .unique(&["provider", "subject"])
+            .foreign_key(ForeignKey::new(&["user_id"], "users", &["id"]).on_delete(ReferentialAction::Cascade))
+            .index("user_identities_user_id", &["user_id"])
+    }
+}
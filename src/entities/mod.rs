@@ -1,15 +1,15 @@
+mod audit_event;
+mod authorization_grant;
+mod external_identity;
+mod oauth_client;
+mod rate_limit_bucket;
+mod table;
 mod user;
 
-pub use user::{User, UserTable};
-
-use crate::configs::DatabaseScheme;
-
-pub trait Table {
-    fn name(&self) -> &'static str;
-
-    fn create(&self, scheme: &DatabaseScheme) -> String;
-
-    fn dispose(&self) -> String;
-
-    fn dependencies(&self) -> Vec<&'static str>;
-}
\ No newline at end of file
+pub use audit_event::{AuditEvent, AuditEventTable, AUDIT_FAILURE, AUDIT_SUCCESS, AUDIT_TEXT_LENGTH};
+pub use authorization_grant::{AuthorizationGrant, AuthorizationGrantTable};
+pub use external_identity::{ExternalIdentity, ExternalIdentityTable};
+pub use oauth_client::{OAuthClient, OAuthClientTable};
+pub use rate_limit_bucket::{RateLimitBucket, RateLimitBucketTable, TOKEN_SCALE};
+pub use table::{Column, ColumnType, ForeignKey, Index, ReferentialAction, Table, TableDefinition};
+pub use user::{User, UserTable, ADMIN_ROLE, USER_ROLE};
@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+
+use crate::entities::{Column, ColumnType, Table, TableDefinition};
+
+#[derive(sqlx::FromRow, Clone, Deserialize, Serialize)]
+pub struct OAuthClient {
+    pub id: i32,
+    pub client_id: String,
+    pub client_secret: Option<String>,
+    pub name: String,
+    pub redirect_uris: String,
+    pub scopes: String,
+    pub grant_types: String,
+}
+
+impl OAuthClient {
+    pub fn is_confidential(&self) -> bool {
+        self.client_secret.is_some()
+    }
+
+    pub fn allows_redirect(&self, redirect_uri: &str) -> bool {
+        self.redirect_uris.split_whitespace().any(|uri| uri == redirect_uri)
+    }
+
+    pub fn allows_grant(&self, grant_type: &str) -> bool {
+        self.grant_types.split_whitespace().any(|grant| grant == grant_type)
+    }
+
+    pub fn allows_scope(&self, scope: &str) -> bool {
+        scope.split_whitespace().all(|requested| self.scopes.split_whitespace().any(|allowed| allowed == requested))
+    }
+}
+
+#[derive(Clone)]
+pub struct OAuthClientTable;
+
+impl Table for OAuthClientTable {
+    fn definition(&self) -> TableDefinition {
+        TableDefinition::new("oauth_clients")
+            .column(Column::new("id", ColumnType::Id))
+            .column(Column::new("client_id", ColumnType::Text(255)).unique())
+            .column(Column::new("client_secret", ColumnType::Text(255)).nullable())
+            .column(Column::new("name", ColumnType::Text(255)))
+            .column(Column::new("redirect_uris", ColumnType::Text(2048)))
+            .column(Column::new("scopes", ColumnType::Text(2048)))
+            .column(Column::new("grant_types", ColumnType::Text(255)))
+    }
+}
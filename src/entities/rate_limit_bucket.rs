@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+
+use crate::entities::{Column, ColumnType, Table, TableDefinition};
+
+/// Tokens are stored in thousandths so partial refills survive integer columns.
+pub const TOKEN_SCALE: i64 = 1000;
+
+#[derive(sqlx::FromRow, Clone, Deserialize, Serialize)]
+pub struct RateLimitBucket {
+    pub bucket_key: String,
+    pub tokens: i64,
+    pub updated_at: i64,
+}
+
+impl RateLimitBucket {
+    pub fn full(bucket_key: &str, capacity: u32, now: i64) -> Self {
+        Self {
+            bucket_key: bucket_key.to_string(),
+            tokens: capacity as i64 * TOKEN_SCALE,
+            updated_at: now,
+        }
+    }
+
+    /// Adds the tokens earned since the last update, `capacity` tokens per `period` seconds; `now` is in milliseconds.
+    pub fn refill(&mut self, capacity: u32, period: u64, now: i64) {
+        let elapsed = (now - self.updated_at).max(0);
+        let earned = elapsed.saturating_mul(capacity as i64) / period as i64;
+
+        self.tokens = self.tokens.saturating_add(earned).min(capacity as i64 * TOKEN_SCALE);
+        self.updated_at = now;
+    }
+
+    pub fn take(&mut self) -> bool {
+        let allowed = self.tokens >= TOKEN_SCALE;
+
+        if allowed {
+            self.tokens -= TOKEN_SCALE;
+        }
+
+        allowed
+    }
+
+    pub fn remaining(&self) -> u32 {
+        (self.tokens / TOKEN_SCALE) as u32
+    }
+
+    /// Whole seconds until the bucket holds `tokens` (unscaled) again.
+    pub fn seconds_until(&self, tokens: u32, capacity: u32, period: u64) -> u64 {
+        let missing = (tokens as i64 * TOKEN_SCALE - self.tokens).max(0) as u64;
+        let millis = missing * period / capacity as u64;
+
+        millis.div_ceil(1000)
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimitBucketTable;
+
+impl Table for RateLimitBucketTable {
+    fn definition(&self) -> TableDefinition {
+        TableDefinition::new("rate_limit_buckets")
+            .column(Column::new("bucket_key", ColumnType::Text(255)).primary())
+            .column(Column::new("tokens", ColumnType::BigInteger))
+            .column(Column::new("updated_at", ColumnType::BigInteger))
+    }
+}
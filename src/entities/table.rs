@@ -0,0 +1,392 @@
+use crate::configs::DatabaseScheme;
+
+/// Logical column types, rendered per dialect.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColumnType {
+    /// Auto-incrementing `INT` primary key.
+    Id,
+    /// Auto-incrementing `BIGINT` primary key.
+    BigId,
+    Integer,
+    BigInteger,
+    Text(u32),
+    /// Unbounded text.
+    LongText,
+    Bool,
+    Timestamp,
+    Json,
+    Bytes,
+    Decimal(u8, u8),
+}
+
+impl ColumnType {
+    /// The bare SQL type, as the database reports it back.
+    pub fn data_type(&self, scheme: &DatabaseScheme) -> String {
+        match (self, scheme) {
+            (ColumnType::Id | ColumnType::BigId, DatabaseScheme::SQLITE) => "INTEGER".into(),
+            (ColumnType::Id | ColumnType::Integer, _) => "INT".into(),
+            (ColumnType::BigId | ColumnType::BigInteger, _) => "BIGINT".into(),
+            (ColumnType::Text(length), _) => format!("VARCHAR({length})"),
+            (ColumnType::LongText, _) => "TEXT".into(),
+            (ColumnType::Bool, _) => "BOOLEAN".into(),
+            (ColumnType::Timestamp, DatabaseScheme::POSTGRES) => "TIMESTAMP WITH TIME ZONE".into(),
+            (ColumnType::Timestamp, DatabaseScheme::MYSQL) => "DATETIME(6)".into(),
+            (ColumnType::Timestamp, DatabaseScheme::SQLITE) => "TIMESTAMP".into(),
+            (ColumnType::Json, DatabaseScheme::POSTGRES) => "JSONB".into(),
+            (ColumnType::Json, DatabaseScheme::MYSQL) => "JSON".into(),
+            (ColumnType::Json, DatabaseScheme::SQLITE) => "TEXT".into(),
+            (ColumnType::Bytes, DatabaseScheme::POSTGRES) => "BYTEA".into(),
+            (ColumnType::Bytes, _) => "BLOB".into(),
+            (ColumnType::Decimal(precision, scale), _) => format!("DECIMAL({precision}, {scale})"),
+        }
+    }
+
+    /// The type as declared in `CREATE TABLE`, including the identity clause of id columns.
+    pub fn render(&self, scheme: &DatabaseScheme) -> String {
+        let data_type = self.data_type(scheme);
+
+        match (self.is_id(), scheme) {
+            (true, DatabaseScheme::POSTGRES) => format!("{data_type} GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY"),
+            (true, DatabaseScheme::SQLITE) => format!("{data_type} PRIMARY KEY AUTOINCREMENT"),
+            (true, DatabaseScheme::MYSQL) => format!("{data_type} AUTO_INCREMENT PRIMARY KEY"),
+            (false, _) => data_type,
+        }
+    }
+
+    pub fn is_id(&self) -> bool {
+        matches!(self, ColumnType::Id | ColumnType::BigId)
+    }
+}
+
+/// A column, `NOT NULL` unless marked [`Column::nullable`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Column {
+    pub name: &'static str,
+    pub column_type: ColumnType,
+    pub nullable: bool,
+    /// SQL expression, e.g. `'user'` or `0`.
+    pub default: Option<String>,
+    pub unique: bool,
+    pub primary: bool,
+}
+
+impl Column {
+    pub fn new(name: &'static str, column_type: ColumnType) -> Self {
+        Self {
+            name,
+            column_type,
+            nullable: false,
+            default: None,
+            unique: false,
+            primary: false,
+        }
+    }
+
+    pub fn nullable(mut self) -> Self {
+        self.nullable = true;
+        self
+    }
+
+    pub fn default(mut self, expression: impl Into<String>) -> Self {
+        self.default = Some(expression.into());
+        self
+    }
+
+    pub fn unique(mut self) -> Self {
+        self.unique = true;
+        self
+    }
+
+    pub fn primary(mut self) -> Self {
+        self.primary = true;
+        self
+    }
+
+    pub fn render(&self, scheme: &DatabaseScheme) -> String {
+        let mut sql = format!("{} {}", self.name, self.column_type.render(scheme));
+
+        if !self.nullable && !self.column_type.is_id() {
+            sql.push_str(" NOT NULL");
+        }
+        if let Some(default) = &self.default {
+            sql.push_str(&format!(" DEFAULT {default}"));
+        }
+        if self.unique {
+            sql.push_str(" UNIQUE");
+        }
+        if self.primary && !self.column_type.is_id() {
+            sql.push_str(" PRIMARY KEY");
+        }
+
+        sql
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReferentialAction {
+    Cascade,
+    SetNull,
+    Restrict,
+    NoAction,
+}
+
+impl ReferentialAction {
+    fn as_sql(&self) -> &'static str {
+        match self {
+            ReferentialAction::Cascade => "CASCADE",
+            ReferentialAction::SetNull => "SET NULL",
+            ReferentialAction::Restrict => "RESTRICT",
+            ReferentialAction::NoAction => "NO ACTION",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ForeignKey {
+    pub columns: Vec<&'static str>,
+    pub table: &'static str,
+    pub references: Vec<&'static str>,
+    pub on_delete: Option<ReferentialAction>,
+}
+
+impl ForeignKey {
+    pub fn new(columns: &[&'static str], table: &'static str, references: &[&'static str]) -> Self {
+        Self {
+            columns: columns.to_vec(),
+            table,
+            references: references.to_vec(),
+            on_delete: None,
+        }
+    }
+
+    pub fn on_delete(mut self, action: ReferentialAction) -> Self {
+        self.on_delete = Some(action);
+        self
+    }
+
+    fn render(&self) -> String {
+        let mut sql = format!(
+            "FOREIGN KEY ({}) REFERENCES {} ({})",
+            self.columns.join(", "),
+            self.table,
+            self.references.join(", ")
+        );
+
+        if let Some(action) = self.on_delete {
+            sql.push_str(&format!(" ON DELETE {}", action.as_sql()));
+        }
+
+        sql
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Index {
+    pub name: &'static str,
+    pub columns: Vec<&'static str>,
+    pub unique: bool,
+}
+
+impl Index {
+    /// `CREATE INDEX` for PostgreSQL and SQLite; MySQL has no `IF NOT EXISTS` for indexes so they are declared
+    /// inside `CREATE TABLE` instead, see [`Index::render_inline`].
+    pub fn render(&self, table: &str) -> String {
+        let unique = if self.unique { "UNIQUE " } else { "" };
+
+        format!("CREATE {unique}INDEX IF NOT EXISTS {} ON {table} ({});", self.name, self.columns.join(", "))
+    }
+
+    pub fn render_inline(&self) -> String {
+        let unique = if self.unique { "UNIQUE " } else { "" };
+
+        format!("{unique}INDEX {} ({})", self.name, self.columns.join(", "))
+    }
+}
+
+/// A table declared column by column; [`Table`] implementations build one in `definition`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableDefinition {
+    pub name: &'static str,
+    pub columns: Vec<Column>,
+    pub primary_key: Vec<&'static str>,
+    pub unique: Vec<Vec<&'static str>>,
+    pub foreign_keys: Vec<ForeignKey>,
+    pub indexes: Vec<Index>,
+    /// Named `CHECK` constraints and their SQL expressions.
+    pub checks: Vec<(&'static str, String)>,
+}
+
+impl TableDefinition {
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            columns: Vec::new(),
+            primary_key: Vec::new(),
+            unique: Vec::new(),
+            foreign_keys: Vec::new(),
+            indexes: Vec::new(),
+            checks: Vec::new(),
+        }
+    }
+
+    pub fn column(mut self, column: Column) -> Self {
+        self.columns.push(column);
+        self
+    }
+
+    /// Composite primary key; single-column keys use [`Column::primary`].
+    pub fn primary_key(mut self, columns: &[&'static str]) -> Self {
+        self.primary_key = columns.to_vec();
+        self
+    }
+
+    /// Composite unique constraint; single columns use [`Column::unique`].
+    pub fn unique(mut self, columns: &[&'static str]) -> Self {
+        self.unique.push(columns.to_vec());
+        self
+    }
+
+    pub fn foreign_key(mut self, foreign_key: ForeignKey) -> Self {
+        self.foreign_keys.push(foreign_key);
+        self
+    }
+
+    pub fn index(mut self, name: &'static str, columns: &[&'static str]) -> Self {
+        self.indexes.push(Index { name, columns: columns.to_vec(), unique: false });
+        self
+    }
+
+    pub fn unique_index(mut self, name: &'static str, columns: &[&'static str]) -> Self {
+        self.indexes.push(Index { name, columns: columns.to_vec(), unique: true });
+        self
+    }
+
+    pub fn check(mut self, name: &'static str, expression: impl Into<String>) -> Self {
+        self.checks.push((name, expression.into()));
+        self
+    }
+
+    pub fn column_named(&self, name: &str) -> Option<&Column> {
+        self.columns.iter().find(|column| column.name == name)
+    }
+
+    /// `CREATE TABLE IF NOT EXISTS` followed by the index statements the dialect needs separately.
+    pub fn create(&self, scheme: &DatabaseScheme) -> Vec<String> {
+        let mut definitions: Vec<String> = self.columns.iter().map(|column| column.render(scheme)).collect();
+
+        if !self.primary_key.is_empty() {
+            definitions.push(format!("PRIMARY KEY ({})", self.primary_key.join(", ")));
+        }
+        for columns in &self.unique {
+            definitions.push(format!("UNIQUE ({})", columns.join(", ")));
+        }
+        for foreign_key in &self.foreign_keys {
+            definitions.push(foreign_key.render());
+        }
+        for (name, expression) in &self.checks {
+            definitions.push(format!("CONSTRAINT {name} CHECK ({expression})"));
+        }
+
+        let mut statements = Vec::with_capacity(self.indexes.len() + 1);
+
+        match scheme {
+            DatabaseScheme::MYSQL => definitions.extend(self.indexes.iter().map(Index::render_inline)),
+            _ => statements.extend(self.indexes.iter().map(|index| index.render(self.name))),
+        }
+
+        statements.insert(0, format!("CREATE TABLE IF NOT EXISTS {} ({});", self.name, definitions.join(", ")));
+
+        statements
+    }
+
+    pub fn dispose(&self) -> String {
+        format!("DROP TABLE IF EXISTS {};", self.name)
+    }
+
+    /// Tables referenced by foreign keys, excluding self-references.
+    pub fn dependencies(&self) -> Vec<&'static str> {
+        let mut dependencies: Vec<_> = self.foreign_keys.iter()
+            .map(|foreign_key| foreign_key.table)
+            .filter(|table| *table != self.name)
+            .collect();
+        dependencies.sort();
+        dependencies.dedup();
+
+        dependencies
+    }
+}
+
+pub trait Table {
+    fn definition(&self) -> TableDefinition;
+
+    fn name(&self) -> &'static str {
+        self.definition().name
+    }
+
+    fn create(&self, scheme: &DatabaseScheme) -> Vec<String> {
+        self.definition().create(scheme)
+    }
+
+    fn dispose(&self) -> String {
+        self.definition().dispose()
+    }
+
+    fn dependencies(&self) -> Vec<&'static str> {
+        self.definition().dependencies()
+    }
+}
+
+#[cfg(test)]
+mod table_tests {
+    use super::*;
+
+    fn definition() -> TableDefinition {
+        TableDefinition::new("memberships")
+            .column(Column::new("id", ColumnType::Id))
+            .column(Column::new("user_id", ColumnType::Integer))
+            .column(Column::new("group_id", ColumnType::Integer))
+            .column(Column::new("admin", ColumnType::Bool).default("FALSE"))
+            .column(Column::new("quota", ColumnType::Decimal(10, 2)).nullable())
+            .unique(&["user_id", "group_id"])
+            .foreign_key(ForeignKey::new(&["user_id"], "users", &["id"]).on_delete(ReferentialAction::Cascade))
+            .foreign_key(ForeignKey::new(&["group_id"], "groups", &["id"]))
+            .foreign_key(ForeignKey::new(&["user_id"], "users", &["id"]))
+            .index("memberships_group_id", &["group_id"])
+            .check("memberships_quota", "quota >= 0")
+    }
+
+    #[test]
+    fn test_render_per_dialect() {
+        let postgres = definition().create(&DatabaseScheme::POSTGRES);
+
+        assert_eq!(
+            postgres[0],
+            "CREATE TABLE IF NOT EXISTS memberships (\
+                id INT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY, \
+                user_id INT NOT NULL, \
+                group_id INT NOT NULL, \
+                admin BOOLEAN NOT NULL DEFAULT FALSE, \
+                quota DECIMAL(10, 2), \
+                UNIQUE (user_id, group_id), \
+                FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE, \
+                FOREIGN KEY (group_id) REFERENCES groups (id), \
+                FOREIGN KEY (user_id) REFERENCES users (id), \
+                CONSTRAINT memberships_quota CHECK (quota >= 0));"
+        );
+        assert_eq!(postgres[1], "CREATE INDEX IF NOT EXISTS memberships_group_id ON memberships (group_id);");
+
+        let sqlite = definition().create(&DatabaseScheme::SQLITE);
+
+        assert!(sqlite[0].contains("id INTEGER PRIMARY KEY AUTOINCREMENT, "));
+
+        let mysql = definition().create(&DatabaseScheme::MYSQL);
+
+        assert_eq!(mysql.len(), 1, "MySQL declares indexes inside CREATE TABLE.");
+        assert!(mysql[0].ends_with("INDEX memberships_group_id (group_id));"));
+    }
+
+    #[test]
+    fn test_derive_dependencies_from_foreign_keys() {
+        assert_eq!(definition().dependencies(), vec!["groups", "users"]);
+    }
+}
@@ -1,7 +1,9 @@
 use serde::{Deserialize, Serialize};
 
-use crate::configs::DatabaseScheme;
-use crate::entities::Table;
+use crate::entities::{Column, ColumnType, Table, TableDefinition};
+
+pub const ADMIN_ROLE: &str = "admin";
+pub const USER_ROLE: &str = "user";
 
 #[derive(sqlx::FromRow, Clone, Deserialize, Serialize)]
 pub struct User {
@@ -9,40 +11,19 @@ pub struct User {
     pub username: String,
     pub email: String,
     pub password: String,
+    pub role: String,
 }
 
 #[derive(Clone)]
 pub struct UserTable;
 
 impl Table for UserTable {
-    fn name(&self) -> &'static str {
-        "users"
-    }
-
-    fn create(&self, scheme: &DatabaseScheme) -> String {
-        let id_type = match scheme {
-            DatabaseScheme::POSTGRES => "INT GENERATED BY DEFAULT AS IDENTITY",
-            DatabaseScheme::SQLITE => "INTEGER PRIMARY KEY AUTOINCREMENT",
-            DatabaseScheme::MYSQL => "INT AUTO_INCREMENT PRIMARY KEY",
-        };
-
-        let text_type = "VARCHAR(255)";
-
-        format!(
-            "CREATE TABLE IF NOT EXISTS {} (\
-                id {id_type}, \
-                username {text_type} NOT NULL UNIQUE, \
-                email {text_type} NOT NULL UNIQUE, \
-                password {text_type} NOT NULL);",
-            self.name()
-        )
-    }
-
-    fn dispose(&self) -> String {
-        format!("DROP TABLE IF EXISTS {};", self.name())
-    }
-
-    fn dependencies(&self) -> Vec<&'static str> {
-        vec![]
+    fn definition(&self) -> TableDefinition {
+        TableDefinition::new("users")
+            .column(Column::new("id", ColumnType::Id))
+            .column(Column::new("username", ColumnType::Text(255)).unique())
+            .column(Column::new("email", ColumnType::Text(255)).unique())
+            .column(Column::new("password", ColumnType::Text(255)))
+            .column(Column::new("role", ColumnType::Text(255)).default(format!("'{USER_ROLE}'")))
     }
 }
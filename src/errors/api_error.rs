@@ -1,10 +1,14 @@
 use ntex::http::StatusCode;
-use ntex::web::WebResponseError;
+use ntex::web::{HttpRequest, HttpResponse, WebResponseError};
 
 use super::config_error::ConfigError;
 use super::database_error::DatabaseError;
 use super::auth_error::AuthError;
+use super::oauth_error::OAuthError;
+use super::oidc_error::OidcError;
+use super::rate_limit_error::RateLimitError;
 use super::user_error::UserError;
+use super::validation_error::ValidationError;
 
 #[derive(thiserror::Error, Debug)]
 pub enum ApiError {
@@ -19,6 +23,18 @@ pub enum ApiError {
 
     #[error(transparent)]
     UserError(#[from] UserError),
+
+    #[error(transparent)]
+    OidcError(#[from] OidcError),
+
+    #[error(transparent)]
+    OAuthError(#[from] OAuthError),
+
+    #[error(transparent)]
+    ValidationError(#[from] ValidationError),
+
+    #[error(transparent)]
+    RateLimitError(#[from] RateLimitError),
 }
 
 impl WebResponseError for ApiError {
@@ -28,6 +44,23 @@ impl WebResponseError for ApiError {
             ApiError::DatabaseError(error) => error.status_code(),
             ApiError::TokenError(error) => error.status_code(),
             ApiError::UserError(error) => error.status_code(),
+            ApiError::OidcError(error) => error.status_code(),
+            ApiError::OAuthError(error) => error.status_code(),
+            ApiError::ValidationError(error) => error.status_code(),
+            ApiError::RateLimitError(error) => error.status_code(),
+        }
+    }
+
+    fn error_response(&self, req: &HttpRequest) -> HttpResponse {
+        match self {
+            ApiError::ConfigError(error) => error.error_response(req),
+            ApiError::DatabaseError(error) => error.error_response(req),
+            ApiError::TokenError(error) => error.error_response(req),
+            ApiError::UserError(error) => error.error_response(req),
+            ApiError::OidcError(error) => error.error_response(req),
+            ApiError::OAuthError(error) => error.error_response(req),
+            ApiError::ValidationError(error) => error.error_response(req),
+            ApiError::RateLimitError(error) => error.error_response(req),
         }
     }
 }
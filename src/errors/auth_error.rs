@@ -1,5 +1,55 @@
+use std::fmt;
+
 use ntex::http::StatusCode;
-use ntex::web::WebResponseError;
+use ntex::web::{HttpRequest, HttpResponse, WebResponseError};
+
+use crate::payload::FieldErrorDto;
+use super::problem::{problem_response, Problem};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PasswordViolation {
+    TooShort { min: usize },
+    TooLong { max: usize },
+    MissingLowercase,
+    MissingUppercase,
+    MissingDigit,
+    MissingSymbol,
+    ContainsUsername,
+    ContainsEmail,
+    Breached,
+}
+
+impl PasswordViolation {
+    pub fn code(&self) -> &'static str {
+        match self {
+            PasswordViolation::TooShort { .. } => "too_short",
+            PasswordViolation::TooLong { .. } => "too_long",
+            PasswordViolation::MissingLowercase => "missing_lowercase",
+            PasswordViolation::MissingUppercase => "missing_uppercase",
+            PasswordViolation::MissingDigit => "missing_digit",
+            PasswordViolation::MissingSymbol => "missing_symbol",
+            PasswordViolation::ContainsUsername => "contains_username",
+            PasswordViolation::ContainsEmail => "contains_email",
+            PasswordViolation::Breached => "breached",
+        }
+    }
+}
+
+impl fmt::Display for PasswordViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PasswordViolation::TooShort { min } => write!(f, "must be at least {min} characters long"),
+            PasswordViolation::TooLong { max } => write!(f, "must be at most {max} characters long"),
+            PasswordViolation::MissingLowercase => write!(f, "must contain a lowercase letter"),
+            PasswordViolation::MissingUppercase => write!(f, "must contain an uppercase letter"),
+            PasswordViolation::MissingDigit => write!(f, "must contain a digit"),
+            PasswordViolation::MissingSymbol => write!(f, "must contain a symbol"),
+            PasswordViolation::ContainsUsername => write!(f, "must not contain the username"),
+            PasswordViolation::ContainsEmail => write!(f, "must not contain the email"),
+            PasswordViolation::Breached => write!(f, "appears in a list of breached passwords"),
+        }
+    }
+}
 
 #[derive(thiserror::Error, Debug)]
 pub enum AuthError {
@@ -20,6 +70,15 @@ pub enum AuthError {
 
     #[error("Authentication Error: Failed to hash the password.")]
     PasswordHashError(String),
+
+    #[error("Authentication Error: The password hash format is not supported.")]
+    UnsupportedPasswordHash,
+
+    #[error("Authorization Error: The authenticated user is not allowed to perform this action.")]
+    Forbidden,
+
+    #[error("Password Policy Error: The password {}.", .0.iter().map(ToString::to_string).collect::<Vec<_>>().join(", "))]
+    PasswordPolicyViolation(Vec<PasswordViolation>),
 }
 
 impl WebResponseError for AuthError {
@@ -31,6 +90,43 @@ impl WebResponseError for AuthError {
             AuthError::TokenCreationError(_) => StatusCode::INTERNAL_SERVER_ERROR,
             AuthError::InvalidPassword => StatusCode::BAD_REQUEST,
             AuthError::PasswordHashError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            AuthError::UnsupportedPasswordHash => StatusCode::BAD_REQUEST,
+            AuthError::Forbidden => StatusCode::FORBIDDEN,
+            AuthError::PasswordPolicyViolation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        }
+    }
+
+    fn error_response(&self, req: &HttpRequest) -> HttpResponse {
+        problem_response(self, req)
+    }
+}
+
+impl Problem for AuthError {
+    fn code(&self) -> &'static str {
+        match self {
+            AuthError::InvalidToken(_) => "auth.invalid_token",
+            AuthError::TokenExpired => "auth.token_expired",
+            AuthError::TokenCreationError(_) => "auth.token_creation_failed",
+            AuthError::MissingToken => "auth.missing_token",
+            AuthError::InvalidPassword => "auth.invalid_password",
+            AuthError::PasswordHashError(_) => "auth.password_hash_failed",
+            AuthError::UnsupportedPasswordHash => "auth.unsupported_password_hash",
+            AuthError::Forbidden => "auth.forbidden",
+            AuthError::PasswordPolicyViolation(_) => "auth.password_policy_violation",
+        }
+    }
+
+    fn field_errors(&self) -> Vec<FieldErrorDto> {
+        match self {
+            AuthError::PasswordPolicyViolation(violations) => violations
+                .iter()
+                .map(|violation| FieldErrorDto {
+                    field: "password".into(),
+                    code: violation.code().into(),
+                    message: violation.to_string(),
+                })
+                .collect(),
+            _ => Vec::new(),
         }
     }
 }
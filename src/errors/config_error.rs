@@ -2,29 +2,59 @@ use std::io;
 use std::string::FromUtf8Error;
 
 use ntex::http::StatusCode;
-use ntex::web::WebResponseError;
+use ntex::web::{HttpRequest, HttpResponse, WebResponseError};
 use toml::{de, ser};
 
+use super::problem::{problem_response, Problem};
+
 #[derive(thiserror::Error, Debug)]
 pub enum ConfigError {
     #[error("Configuration Error: Type mismatch detected at path '{path}'. Expected type '{expected_type}', but received type '{actual_type}'.")]
     IncompatibleTypeError { path: String, expected_type: String, actual_type: String },
 
+    #[error("Configuration Error: Invalid value at path '{path}'. {reason}.")]
+    InvalidValueError { path: String, reason: String },
+
     #[error("Configuration Loading Error: Failed to load the configuration file. Please ensure the file is encoded in valid UTF-8 format.")]
     Utf8LoadError(#[from] FromUtf8Error),
 
     #[error("Configuration Serialization Error: Unable to serialize the configuration data.")]
     SerializeError(#[from] ser::Error),
 
-    #[error("Configuration Deserialization Error: Unable to deserialize the configuration data.")]
+    #[error("Configuration Deserialization Error: Unable to deserialize the configuration data. {0}")]
     DeserializeError(#[from] de::Error),
 
     #[error("File Path Error: Operation on the file path failed with error: {0}.")]
     PathError(#[from] io::Error),
+
+    #[error("Configuration Validation Error: Found {} problem(s).{}", .0.len(), bullet_list(.0))]
+    ValidationError(Vec<ConfigError>),
+}
+
+fn bullet_list(errors: &[ConfigError]) -> String {
+    errors.iter().map(|error| format!("\n  - {error}")).collect()
 }
 
 impl WebResponseError for ConfigError {
     fn status_code(&self) -> StatusCode {
         StatusCode::INTERNAL_SERVER_ERROR
     }
+
+    fn error_response(&self, req: &HttpRequest) -> HttpResponse {
+        problem_response(self, req)
+    }
+}
+
+impl Problem for ConfigError {
+    fn code(&self) -> &'static str {
+        match self {
+            ConfigError::IncompatibleTypeError { .. } => "config.incompatible_type",
+            ConfigError::InvalidValueError { .. } => "config.invalid_value",
+            ConfigError::Utf8LoadError(_) => "config.invalid_encoding",
+            ConfigError::SerializeError(_) => "config.serialize_failed",
+            ConfigError::DeserializeError(_) => "config.deserialize_failed",
+            ConfigError::PathError(_) => "config.path_failed",
+            ConfigError::ValidationError(_) => "config.validation_failed",
+        }
+    }
 }
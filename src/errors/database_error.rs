@@ -1,5 +1,7 @@
 use ntex::http::StatusCode;
-use ntex::web::WebResponseError;
+use ntex::web::{HttpRequest, HttpResponse, WebResponseError};
+
+use super::problem::{problem_response, Problem};
 
 #[derive(thiserror::Error, Debug)]
 pub enum DatabaseError {
@@ -14,6 +16,9 @@ pub enum DatabaseError {
 
     #[error("Database Constraint Violation: A unique constraint violation has been detected.")]
     UniqueConstraintViolation,
+
+    #[error("Database Schema Drift: The live schema differs from the managed tables, {0}.")]
+    SchemaDriftError(String),
 }
 
 impl WebResponseError for DatabaseError {
@@ -23,6 +28,22 @@ impl WebResponseError for DatabaseError {
             _ => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
+
+    fn error_response(&self, req: &HttpRequest) -> HttpResponse {
+        problem_response(self, req)
+    }
+}
+
+impl Problem for DatabaseError {
+    fn code(&self) -> &'static str {
+        match self {
+            DatabaseError::DatabaseMigrateError(_) => "database.migration_failed",
+            DatabaseError::DatabaseAccessError(_) => "database.access_failed",
+            DatabaseError::DatabaseExecuteError(_) => "database.execution_failed",
+            DatabaseError::UniqueConstraintViolation => "database.unique_violation",
+            DatabaseError::SchemaDriftError(_) => "database.schema_drift",
+        }
+    }
 }
 
 impl From<sqlx::Error> for DatabaseError {
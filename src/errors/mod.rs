@@ -2,10 +2,19 @@ mod api_error;
 mod auth_error;
 mod config_error;
 mod database_error;
+mod oauth_error;
+mod oidc_error;
+mod problem;
+mod rate_limit_error;
 mod user_error;
+mod validation_error;
 
 pub use api_error::ApiError;
-pub use auth_error::AuthError;
+pub use auth_error::{AuthError, PasswordViolation};
 pub use config_error::ConfigError;
 pub use database_error::DatabaseError;
+pub use oauth_error::OAuthError;
+pub use oidc_error::OidcError;
+pub use rate_limit_error::RateLimitError;
 pub use user_error::UserError;
+pub use validation_error::ValidationError;
@@ -0,0 +1,60 @@
+use ntex::http::StatusCode;
+use ntex::web::{HttpRequest, HttpResponse, WebResponseError};
+
+use crate::payload::OAuthErrorDto;
+
+#[derive(thiserror::Error, Debug)]
+pub enum OAuthError {
+    #[error("OAuth Request Error: The request is malformed. Details: {0}.")]
+    InvalidRequest(String),
+
+    #[error("OAuth Client Error: Client authentication failed.")]
+    InvalidClient,
+
+    #[error("OAuth Grant Error: The authorization grant is invalid, expired or revoked. Details: {0}.")]
+    InvalidGrant(String),
+
+    #[error("OAuth Client Error: The client is not allowed to use this grant type.")]
+    UnauthorizedClient,
+
+    #[error("OAuth Grant Error: The grant type '{0}' is not supported.")]
+    UnsupportedGrantType(String),
+
+    #[error("OAuth Scope Error: The requested scope '{0}' is invalid or exceeds the client's scopes.")]
+    InvalidScope(String),
+
+    #[error("OAuth Consent Error: The resource owner denied the request.")]
+    AccessDenied,
+}
+
+impl OAuthError {
+    /// Error code defined by RFC 6749, section 5.2.
+    pub fn code(&self) -> &'static str {
+        match self {
+            OAuthError::InvalidRequest(_) => "invalid_request",
+            OAuthError::InvalidClient => "invalid_client",
+            OAuthError::InvalidGrant(_) => "invalid_grant",
+            OAuthError::UnauthorizedClient => "unauthorized_client",
+            OAuthError::UnsupportedGrantType(_) => "unsupported_grant_type",
+            OAuthError::InvalidScope(_) => "invalid_scope",
+            OAuthError::AccessDenied => "access_denied",
+        }
+    }
+}
+
+impl WebResponseError for OAuthError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            OAuthError::InvalidClient => StatusCode::UNAUTHORIZED,
+            OAuthError::AccessDenied => StatusCode::FORBIDDEN,
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+
+    fn error_response(&self, _: &HttpRequest) -> HttpResponse {
+        HttpResponse::build(self.status_code()).json(&OAuthErrorDto {
+            error: self.code().into(),
+            error_description: self.to_string(),
+        })
+    }
+}
@@ -0,0 +1,55 @@
+use ntex::http::StatusCode;
+use ntex::web::{HttpRequest, HttpResponse, WebResponseError};
+
+use super::problem::{problem_response, Problem};
+
+#[derive(thiserror::Error, Debug)]
+pub enum OidcError {
+    #[error("OIDC Provider Error: The identity provider '{0}' is not configured.")]
+    UnknownProvider(String),
+
+    #[error("OIDC State Error: The login state is unknown, expired or belongs to another provider.")]
+    InvalidState,
+
+    #[error("OIDC Authorization Error: The identity provider denied the login. Details: {0}.")]
+    AuthorizationDenied(String),
+
+    #[error("OIDC Discovery Error: Unable to reach the identity provider. Details: {0}.")]
+    ProviderUnavailable(String),
+
+    #[error("OIDC Token Error: The identity provider returned an invalid ID token. Details: {0}.")]
+    InvalidIdToken(String),
+
+    #[error("OIDC Account Error: A local account already uses this email and the identity provider is not trusted to link it.")]
+    EmailInUse,
+}
+
+impl WebResponseError for OidcError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            OidcError::UnknownProvider(_) => StatusCode::NOT_FOUND,
+            OidcError::InvalidState => StatusCode::BAD_REQUEST,
+            OidcError::AuthorizationDenied(_) => StatusCode::UNAUTHORIZED,
+            OidcError::ProviderUnavailable(_) => StatusCode::BAD_GATEWAY,
+            OidcError::InvalidIdToken(_) => StatusCode::UNAUTHORIZED,
+            OidcError::EmailInUse => StatusCode::CONFLICT,
+        }
+    }
+
+    fn error_response(&self, req: &HttpRequest) -> HttpResponse {
+        problem_response(self, req)
+    }
+}
+
+impl Problem for OidcError {
+    fn code(&self) -> &'static str {
+        match self {
+            OidcError::UnknownProvider(_) => "oidc.unknown_provider",
+            OidcError::InvalidState => "oidc.invalid_state",
+            OidcError::AuthorizationDenied(_) => "oidc.authorization_denied",
+            OidcError::ProviderUnavailable(_) => "oidc.provider_unavailable",
+            OidcError::InvalidIdToken(_) => "oidc.invalid_id_token",
+            OidcError::EmailInUse => "oidc.email_in_use",
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use std::fmt::Display;
+use std::sync::Arc;
+
+use ntex::web::{HttpRequest, HttpResponse, WebResponseError};
+
+use crate::configs::Settings;
+use crate::middlewares::REQUEST_ID_HEADER;
+use crate::payload::{FieldErrorDto, ProblemDto};
+
+pub const PROBLEM_CONTENT_TYPE: &str = "application/problem+json";
+
+/// Machine-readable description of an error, rendered as `application/problem+json`.
+pub trait Problem: WebResponseError + Display {
+    /// Stable identifier clients can match on instead of the human-readable message.
+    fn code(&self) -> &'static str;
+
+    fn field_errors(&self) -> Vec<FieldErrorDto> {
+        Vec::new()
+    }
+}
+
+pub fn problem_response<E: Problem>(error: &E, req: &HttpRequest) -> HttpResponse {
+    let status = error.status_code();
+
+    let redact = status.is_server_error()
+        && req.app_state::<Arc<Settings>>().is_some_and(|settings| settings.is_production());
+
+    let problem = ProblemDto {
+        problem_type: "about:blank".into(),
+        title: status.canonical_reason().unwrap_or("Unknown Error").into(),
+        status: status.as_u16(),
+        code: error.code().into(),
+        detail: (!redact).then(|| error.to_string()),
+        instance: Some(req.path().to_string()),
+        request_id: req
+            .headers()
+            .get(REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string),
+        errors: error.field_errors(),
+    };
+
+    HttpResponse::build(status)
+        .content_type(PROBLEM_CONTENT_TYPE)
+        .json(&problem)
+}
+
+#[cfg(test)]
+mod problem_tests {
+    use ntex::http::StatusCode;
+    use ntex::web::{self, test, App};
+    use serde_json::{from_slice, Value};
+
+    use crate::errors::{AuthError, DatabaseError, PasswordViolation};
+    use super::*;
+
+    #[ntex::test]
+    async fn test_field_errors_and_request_id() {
+        let app = App::new().route(
+            "/auth/register",
+            web::post().to(|| async {
+                Err::<HttpResponse, _>(AuthError::PasswordPolicyViolation(vec![PasswordViolation::TooShort { min: 8 }]))
+            }),
+        );
+        let container = test::init_service(app).await;
+
+        let req = test::TestRequest::post()
+            .uri("/auth/register")
+            .header(REQUEST_ID_HEADER, "test-request-id")
+            .to_request();
+        let resp = test::call_service(&container, req).await;
+
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(resp.headers().get("content-type").unwrap(), PROBLEM_CONTENT_TYPE);
+
+        let body: Value = from_slice(&test::read_body(resp).await).unwrap();
+
+        assert_eq!(body["status"], 422);
+        assert_eq!(body["code"], "auth.password_policy_violation");
+        assert_eq!(body["instance"], "/auth/register");
+        assert_eq!(body["request_id"], "test-request-id");
+        assert_eq!(body["errors"][0]["field"], "password");
+        assert_eq!(body["errors"][0]["code"], "too_short");
+    }
+
+    #[ntex::test]
+    async fn test_redact_internal_details_in_production() {
+        let mut settings = Settings::new().unwrap();
+        settings.run_mode = "production".into();
+
+        let app = App::new().state(Arc::new(settings)).route(
+            "/users",
+            web::get().to(|| async {
+                Err::<HttpResponse, _>(DatabaseError::DatabaseExecuteError("no such table: users".into()))
+            }),
+        );
+        let container = test::init_service(app).await;
+
+        let req = test::TestRequest::get().uri("/users").to_request();
+        let resp = test::call_service(&container, req).await;
+
+        assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        let body: Value = from_slice(&test::read_body(resp).await).unwrap();
+
+        assert_eq!(body["code"], "database.execution_failed");
+        assert!(body.get("detail").is_none(), "SQL messages should not leak in production.");
+    }
+}
@@ -0,0 +1,48 @@
+use ntex::http::header::{HeaderName, HeaderValue};
+use ntex::http::StatusCode;
+use ntex::web::{HttpRequest, HttpResponse, WebResponseError};
+
+use crate::payload::RateLimitStatusDto;
+
+use super::problem::{problem_response, Problem};
+
+#[derive(thiserror::Error, Debug)]
+pub enum RateLimitError {
+    #[error("Rate Limit Error: Too many requests. Retry after {} seconds.", .0.retry_after.unwrap_or_default())]
+    TooManyRequests(RateLimitStatusDto),
+
+    #[error("Rate Limit Error: The rate limit store is unavailable. Details: {0}.")]
+    StoreUnavailable(String),
+}
+
+impl WebResponseError for RateLimitError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            RateLimitError::TooManyRequests(_) => StatusCode::TOO_MANY_REQUESTS,
+            RateLimitError::StoreUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+
+    fn error_response(&self, req: &HttpRequest) -> HttpResponse {
+        let mut response = problem_response(self, req);
+
+        if let RateLimitError::TooManyRequests(status) = self {
+            for (name, value) in status.headers() {
+                if let Ok(value) = HeaderValue::from_str(&value) {
+                    response.headers_mut().insert(HeaderName::from_static(name), value);
+                }
+            }
+        }
+
+        response
+    }
+}
+
+impl Problem for RateLimitError {
+    fn code(&self) -> &'static str {
+        match self {
+            RateLimitError::TooManyRequests(_) => "rate_limit.exceeded",
+            RateLimitError::StoreUnavailable(_) => "rate_limit.store_unavailable",
+        }
+    }
+}
@@ -1,5 +1,7 @@
 use ntex::http::StatusCode;
-use ntex::web::WebResponseError;
+use ntex::web::{HttpRequest, HttpResponse, WebResponseError};
+
+use super::problem::{problem_response, Problem};
 
 #[derive(thiserror::Error, Debug)]
 pub enum UserError {
@@ -17,6 +19,9 @@ pub enum UserError {
 
     #[error("User Update Failure: Unable to update the user account. Please verify the details and attempt the operation again.")]
     UserUpdateFail,
+
+    #[error("User Role Error: '{0}' is not a known role.")]
+    InvalidRole(String),
 }
 
 impl WebResponseError for UserError {
@@ -27,6 +32,24 @@ impl WebResponseError for UserError {
             UserError::UserAlreadyExists => StatusCode::BAD_REQUEST,
             UserError::UserCreateFail => StatusCode::INTERNAL_SERVER_ERROR,
             UserError::UserUpdateFail => StatusCode::INTERNAL_SERVER_ERROR,
+            UserError::InvalidRole(_) => StatusCode::BAD_REQUEST,
+        }
+    }
+
+    fn error_response(&self, req: &HttpRequest) -> HttpResponse {
+        problem_response(self, req)
+    }
+}
+
+impl Problem for UserError {
+    fn code(&self) -> &'static str {
+        match self {
+            UserError::MissingIdentity => "user.missing_identity",
+            UserError::UserNotFound => "user.not_found",
+            UserError::UserAlreadyExists => "user.already_exists",
+            UserError::UserCreateFail => "user.create_failed",
+            UserError::UserUpdateFail => "user.update_failed",
+            UserError::InvalidRole(_) => "user.invalid_role",
         }
     }
 }
@@ -0,0 +1,91 @@
+use std::borrow::Cow;
+
+use ntex::http::StatusCode;
+use ntex::web::{HttpRequest, HttpResponse, WebResponseError};
+use validator::{ValidationErrors, ValidationErrorsKind};
+
+use crate::payload::FieldErrorDto;
+use super::problem::{problem_response, Problem};
+
+#[derive(thiserror::Error, Debug)]
+pub enum ValidationError {
+    #[error("Request Body Error: The request body could not be parsed. Details: {0}.")]
+    MalformedBody(String),
+
+    #[error("Validation Error: {} field(s) of the request are invalid.", .0.len())]
+    InvalidFields(Vec<FieldErrorDto>),
+}
+
+impl ValidationError {
+    fn collect(errors: &ValidationErrors, prefix: &str, fields: &mut Vec<FieldErrorDto>) {
+        for (name, kind) in errors.errors() {
+            let field = if prefix.is_empty() { name.to_string() } else { format!("{prefix}.{name}") };
+
+            match kind {
+                ValidationErrorsKind::Field(errors) => fields.extend(errors.iter().map(|error| FieldErrorDto {
+                    field: field.clone(),
+                    code: error.code.to_string(),
+                    message: error.message.clone().unwrap_or_else(|| Self::describe(error)).to_string(),
+                })),
+                ValidationErrorsKind::Struct(errors) => Self::collect(errors, &field, fields),
+                ValidationErrorsKind::List(errors) => {
+                    for (index, errors) in errors {
+                        Self::collect(errors, &format!("{field}[{index}]"), fields);
+                    }
+                }
+            }
+        }
+    }
+
+    fn describe(error: &validator::ValidationError) -> Cow<'static, str> {
+        let param = |name: &str| error.params.get(name).map(ToString::to_string);
+
+        match (error.code.as_ref(), param("min"), param("max")) {
+            ("length", Some(min), Some(max)) => format!("must be between {min} and {max} characters long").into(),
+            ("length", Some(min), None) => format!("must be at least {min} characters long").into(),
+            ("length", None, Some(max)) => format!("must be at most {max} characters long").into(),
+            ("email", _, _) => "must be a valid email address".into(),
+            ("url", _, _) => "must be a valid URL".into(),
+            (code, _, _) => format!("failed the '{code}' check").into(),
+        }
+    }
+}
+
+impl From<ValidationErrors> for ValidationError {
+    fn from(errors: ValidationErrors) -> Self {
+        let mut fields = Vec::new();
+        Self::collect(&errors, "", &mut fields);
+        fields.sort_by(|a, b| a.field.cmp(&b.field));
+
+        ValidationError::InvalidFields(fields)
+    }
+}
+
+impl WebResponseError for ValidationError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ValidationError::MalformedBody(_) => StatusCode::BAD_REQUEST,
+            ValidationError::InvalidFields(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        }
+    }
+
+    fn error_response(&self, req: &HttpRequest) -> HttpResponse {
+        problem_response(self, req)
+    }
+}
+
+impl Problem for ValidationError {
+    fn code(&self) -> &'static str {
+        match self {
+            ValidationError::MalformedBody(_) => "request.malformed_body",
+            ValidationError::InvalidFields(_) => "request.invalid_fields",
+        }
+    }
+
+    fn field_errors(&self) -> Vec<FieldErrorDto> {
+        match self {
+            ValidationError::InvalidFields(fields) => fields.clone(),
+            _ => Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,3 @@
+mod valid_json;
+
+pub use valid_json::ValidJson;
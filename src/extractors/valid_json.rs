@@ -0,0 +1,43 @@
+use std::ops::Deref;
+
+use ntex::http::Payload;
+use ntex::web::{types, ErrorRenderer, FromRequest, HttpRequest};
+use serde::de::DeserializeOwned;
+use validator::Validate;
+
+use crate::errors::ValidationError;
+
+/// JSON body extractor that rejects the request with every failing field before the handler runs.
+pub struct ValidJson<T>(pub T);
+
+impl<T> ValidJson<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for ValidJson<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T, Err> FromRequest<Err> for ValidJson<T>
+    where
+        T: DeserializeOwned + Validate + 'static,
+        Err: ErrorRenderer,
+{
+    type Error = ValidationError;
+
+    async fn from_request(req: &HttpRequest, payload: &mut Payload) -> Result<Self, Self::Error> {
+        let types::Json(data) = <types::Json<T> as FromRequest<Err>>::from_request(req, payload)
+            .await
+            .map_err(|e| ValidationError::MalformedBody(e.to_string()))?;
+
+        data.validate()?;
+
+        Ok(ValidJson(data))
+    }
+}
@@ -0,0 +1,266 @@
+use std::sync::Arc;
+
+use ntex::web::{get, post, types, Error, HttpRequest, HttpResponse, Responder};
+use serde_json::{json, Value};
+
+use crate::configs::Settings;
+use crate::extractors::ValidJson;
+use crate::handlers::audit_handler::audit_context;
+use crate::payload::{
+    AuditRecordDto, ConfigDto, ConfigQueryDto, ProblemDto, UserImportBatchDto, UserImportDto, UserImportReportDto,
+};
+use crate::services::AuditService;
+use crate::states::{AuditState, UserState};
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/users/import",
+    tag = "admin",
+    request_body = Vec<UserImportDto>,
+    responses(
+        (status = 200, description = "Imported and rejected users", body = UserImportReportDto),
+        (status = 401, description = "Missing or invalid token", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 403, description = "Not an administrator", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid user fields", body = ProblemDto, content_type = "application/problem+json"),
+    ),
+    security(("bearer" = []))
+)]
+#[post("/users/import")]
+pub async fn import_users(
+    req: HttpRequest,
+    payload: ValidJson<UserImportBatchDto>,
+    user_state: types::State<UserState>,
+    audit_state: types::State<AuditState>,
+) -> Result<impl Responder, Error> {
+    let ValidJson(UserImportBatchDto(import_data)) = payload;
+
+    let result = user_state.user_service.import_users(import_data).await?;
+
+    let context = audit_context(&req);
+    for user in &result.imported {
+        let record = AuditRecordDto {
+            action: "users.import".into(),
+            success: true,
+            target: Some(format!("username:{}", user.username)),
+            diff: Some(AuditService::diff(&Value::Null, &json!(user))),
+        };
+        audit_state.audit_service.record(context.clone(), record).await;
+    }
+    for rejection in &result.rejected {
+        let record = AuditRecordDto {
+            action: "users.import".into(),
+            success: false,
+            target: Some(format!("username:{}", rejection.username)),
+            diff: None,
+        };
+        audit_state.audit_service.record(context.clone(), record).await;
+    }
+
+    Ok(HttpResponse::Ok().json(&result))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/config",
+    tag = "admin",
+    params(ConfigQueryDto),
+    responses(
+        (status = 200, description = "Settings loaded at startup, secrets redacted", body = ConfigDto),
+        (status = 401, description = "Missing or invalid token", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 403, description = "Not an administrator", body = ProblemDto, content_type = "application/problem+json"),
+    ),
+    security(("bearer" = []))
+)]
+#[get("/config")]
+pub async fn show_config(
+    query: types::Query<ConfigQueryDto>,
+    settings: types::State<Arc<Settings>>,
+) -> Result<impl Responder, Error> {
+    let types::Query(query_data) = query;
+
+    let entries = settings.entries(query_data.effective)?;
+
+    let result = ConfigDto {
+        origins: query_data.origin.then(|| {
+            entries.keys().map(|key| (key.clone(), settings.origin(key).to_string())).collect()
+        }),
+        settings: entries
+            .into_iter()
+            .map(|(key, value)| (key, serde_json::to_value(value).unwrap_or_default()))
+            .collect(),
+    };
+
+    Ok(HttpResponse::Ok().json(&result))
+}
+
+#[cfg(test)]
+mod tests {
+    use ntex::http::StatusCode;
+    use ntex::web::{test, App, Error};
+    use serde_json::{from_slice, json, Value};
+
+    use crate::configs::{Database, MultiHash, Password, PasswordChecker, SchemaManager, Settings};
+    use crate::errors::ApiError;
+    use crate::payload::{UserAuthDto, UserIdentity};
+    use crate::repository::{AuditRepository, UserRepository};
+    use crate::services::{AuditService, AuthService, UserService};
+    use super::*;
+
+    struct AdminEnvironment {
+        user_repo: Arc<UserRepository>,
+        auth_service: AuthService,
+        user_state: UserState,
+        audit_state: AuditState,
+    }
+
+    impl AdminEnvironment {
+        async fn new() -> Result<Self, ApiError> {
+            let settings = Arc::new(Settings::new()?);
+            let database = Arc::new(Database::new(&settings, &SchemaManager::default()).await?);
+            let hasher = Arc::new(MultiHash::new()) as Arc<dyn Password>;
+            let policy = Arc::new(PasswordChecker::new(&settings));
+
+            let user_repo = Arc::new(UserRepository::new(&hasher, &policy, &database));
+            let audit_repo = Arc::new(AuditRepository::new(&database));
+
+            let auth_service = AuthService::new(&user_repo, &hasher);
+            let user_state = UserState {
+                user_service: Arc::new(UserService::new(&user_repo)),
+            };
+
+            let audit_state = AuditState {
+                audit_service: Arc::new(AuditService::new(&audit_repo)),
+            };
+
+            Ok(Self { user_repo, auth_service, user_state, audit_state })
+        }
+    }
+
+    #[ntex::test]
+    async fn test_import_users() -> Result<(), Error> {
+        let AdminEnvironment { user_repo, auth_service, user_state, audit_state } = AdminEnvironment::new().await?;
+
+        let app = App::new().state(user_state).state(audit_state).service(import_users);
+        let container = test::init_service(app).await;
+
+        let password = "test_import_password";
+        let bcrypt_hash = bcrypt::hash(password, 4).unwrap();
+
+        let payload = json!([
+            {
+                "username": "test_import_user",
+                "email": "test_import_user@sieluna.com",
+                "password_hash": bcrypt_hash,
+                "role": null
+            },
+            {
+                "username": "test_import_unknown_user",
+                "email": "test_import_unknown_user@sieluna.com",
+                "password_hash": "$md5$unsupported",
+                "role": null
+            },
+            {
+                "username": "test_import_superuser",
+                "email": "test_import_superuser@sieluna.com",
+                "password_hash": bcrypt_hash,
+                "role": "superuser"
+            },
+            {
+                "username": "test_import_truncated_user",
+                "email": "test_import_truncated_user@sieluna.com",
+                "password_hash": &bcrypt_hash[..40],
+                "role": null
+            }
+        ]);
+
+        let req = test::TestRequest::post().uri("/users/import").set_json(&payload).to_request();
+        let resp = container.call(req).await?;
+
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let body: Value = from_slice(test::read_body(resp).await.as_ref())?;
+
+        assert_eq!(body["imported"][0]["username"], "test_import_user");
+        assert_eq!(body["imported"][0]["role"], "user");
+        assert_eq!(body["rejected"][0]["username"], "test_import_unknown_user");
+        assert_eq!(body["rejected"][1]["username"], "test_import_superuser", "Unknown roles should be rejected.");
+        assert_eq!(body["rejected"][2]["username"], "test_import_truncated_user", "Malformed hashes should be rejected.");
+
+        let login = UserAuthDto {
+            identity: UserIdentity::Username("test_import_user".to_string()),
+            password: password.to_string(),
+        };
+        auth_service.authorization_user(login).await?;
+
+        let user = user_repo.find_by_username("test_import_user").await.unwrap();
+
+        assert!(user.password.starts_with("$argon2id$"), "Legacy hash should be upgraded on login.");
+        Ok(())
+    }
+
+    #[ntex::test]
+    async fn test_import_users_validates_fields() -> Result<(), Error> {
+        let AdminEnvironment { user_state, audit_state, .. } = AdminEnvironment::new().await?;
+
+        let app = App::new().state(user_state).state(audit_state).service(import_users);
+        let container = test::init_service(app).await;
+
+        let payload = json!([
+            {
+                "username": "test_import_valid_user",
+                "email": "test_import_valid_user@sieluna.com",
+                "password_hash": "$2b$04$abcdefghijklmnopqrstuu",
+                "role": null
+            },
+            {
+                "username": "test import user",
+                "email": "not_an_email",
+                "password_hash": "$2b$04$abcdefghijklmnopqrstuu",
+                "role": null
+            }
+        ]);
+
+        let req = test::TestRequest::post().uri("/users/import").set_json(&payload).to_request();
+        let resp = container.call(req).await?;
+
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let body: Value = from_slice(test::read_body(resp).await.as_ref())?;
+        let fields: Vec<_> = body["errors"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|error| error["field"].as_str().unwrap_or_default())
+            .collect();
+
+        assert_eq!(fields, vec!["[1].email", "[1].username"]);
+        Ok(())
+    }
+
+    #[ntex::test]
+    async fn test_show_config() -> Result<(), Error> {
+        let settings = Arc::new(Settings::load::<&str>(&[], &["server.port=9393".to_string()])?);
+
+        let app = App::new().state(settings).service(show_config);
+        let container = test::init_service(app).await;
+
+        let req = test::TestRequest::get().uri("/config?origin=true").to_request();
+        let resp = container.call(req).await?;
+
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let body: Value = from_slice(test::read_body(resp).await.as_ref())?;
+
+        assert_eq!(body["settings"]["server.port"], 9393);
+        assert_eq!(body["origins"]["server.port"], "cli:--set");
+        assert!(body["settings"].get("server.host").is_none());
+
+        let req = test::TestRequest::get().uri("/config?effective=true").to_request();
+        let resp = container.call(req).await?;
+        let body: Value = from_slice(test::read_body(resp).await.as_ref())?;
+
+        assert_eq!(body["settings"]["auth.secret"], "[redacted]");
+        assert!(body.get("origins").is_none());
+        Ok(())
+    }
+}
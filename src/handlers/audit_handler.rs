@@ -0,0 +1,184 @@
+use ntex::http::header;
+use ntex::web::{get, types, Error, HttpRequest, HttpResponse, Responder};
+
+use crate::payload::{AuditContextDto, AuditEventDto, AuditExportFormat, AuditQueryDto, ProblemDto, UserDto};
+use crate::states::AuditState;
+
+const CSV_COLUMNS: &str = "id,created_at,action,outcome,actor_id,actor,target,ip,user_agent,diff";
+
+/// Actor and origin of the request: the authenticated user, if any, and the peer address.
+pub(super) fn audit_context(req: &HttpRequest) -> AuditContextDto {
+    let user = req.extensions().get::<UserDto>().cloned();
+
+    AuditContextDto {
+        actor_id: user.as_ref().map(|user| user.id),
+        actor: user.map(|user| user.username),
+        ip: req.peer_addr().map(|addr| addr.ip().to_string()),
+        user_agent: req
+            .headers()
+            .get(header::USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string),
+    }
+}
+
+/// Quotes fields as RFC 4180 requires, and prefixes ones a spreadsheet would evaluate as a formula with `'`.
+fn csv_field(value: Option<String>) -> String {
+    let value = value.map(|value| {
+        if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+            format!("'{value}")
+        } else {
+            value
+        }
+    });
+
+    match value {
+        Some(value) if value.contains([',', '"', '\n', '\r']) => format!("\"{}\"", value.replace('"', "\"\"")),
+        Some(value) => value,
+        None => String::new(),
+    }
+}
+
+fn to_csv(events: Vec<AuditEventDto>) -> String {
+    let mut body = format!("{CSV_COLUMNS}\n");
+
+    for event in events {
+        let row = [
+            Some(event.id.to_string()),
+            Some(event.created_at.to_string()),
+            Some(event.action),
+            Some(event.outcome),
+            event.actor_id.map(|id| id.to_string()),
+            event.actor,
+            event.target,
+            event.ip,
+            event.user_agent,
+            event.diff.map(|diff| diff.to_string()),
+        ];
+
+        body.push_str(&row.map(csv_field).join(","));
+        body.push('\n');
+    }
+
+    body
+}
+
+fn to_ndjson(events: Vec<AuditEventDto>) -> String {
+    events
+        .iter()
+        .filter_map(|event| serde_json::to_string(event).ok())
+        .map(|line| line + "\n")
+        .collect()
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/audit",
+    tag = "admin",
+    params(AuditQueryDto),
+    responses(
+        (status = 200, description = "Matching audit events, newest first", body = Vec<AuditEventDto>),
+        (status = 403, description = "Not an administrator", body = ProblemDto, content_type = "application/problem+json"),
+    ),
+    security(("bearer" = []))
+)]
+#[get("/audit")]
+pub async fn audit_events(
+    query: types::Query<AuditQueryDto>,
+    audit_state: types::State<AuditState>,
+) -> Result<impl Responder, Error> {
+    let types::Query(query_data) = query;
+    let format = query_data.format.unwrap_or_default();
+
+    let result = audit_state.audit_service.list(query_data).await?;
+
+    Ok(match format {
+        AuditExportFormat::Json => HttpResponse::Ok().json(&result),
+        AuditExportFormat::Ndjson => HttpResponse::Ok().content_type("application/x-ndjson").body(to_ndjson(result)),
+        AuditExportFormat::Csv => HttpResponse::Ok().content_type("text/csv; charset=utf-8").body(to_csv(result)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use ntex::http::StatusCode;
+    use ntex::web::{test, App, Error};
+    use serde_json::{from_slice, json, Value};
+
+    use crate::configs::{Database, SchemaManager, Settings};
+    use crate::errors::ApiError;
+    use crate::payload::AuditRecordDto;
+    use crate::repository::AuditRepository;
+    use crate::services::AuditService;
+    use super::*;
+
+    async fn audit_state() -> Result<AuditState, ApiError> {
+        let settings = Arc::new(Settings::new()?);
+        let database = Arc::new(Database::new(&settings, &SchemaManager::default()).await?);
+
+        let audit_repo = Arc::new(AuditRepository::new(&database));
+
+        Ok(AuditState {
+            audit_service: Arc::new(AuditService::new(&audit_repo)),
+        })
+    }
+
+    #[ntex::test]
+    async fn test_filter_and_export_audit_events() -> Result<(), Error> {
+        let audit_state = audit_state().await?;
+
+        let context = AuditContextDto {
+            actor_id: Some(1),
+            actor: Some("test_audit_admin".into()),
+            ip: Some("127.0.0.1".into()),
+            user_agent: Some("test, agent".into()),
+        };
+        for (action, success) in [("auth.login", false), ("auth.login", true), ("users.import", true)] {
+            let record = AuditRecordDto {
+                action: action.into(),
+                success,
+                target: Some("username:test_audit_user".into()),
+                diff: (action == "users.import").then(|| json!({ "role": { "from": null, "to": "user" } })),
+            };
+            audit_state.audit_service.record(context.clone(), record).await;
+        }
+
+        let app = App::new().state(audit_state).service(audit_events);
+        let container = test::init_service(app).await;
+
+        let req = test::TestRequest::get().uri("/audit?action=auth.login&target=username:test_audit_user").to_request();
+        let resp = container.call(req).await?;
+
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let body: Value = from_slice(test::read_body(resp).await.as_ref())?;
+        let outcomes: Vec<_> = body.as_array().unwrap().iter().map(|event| event["outcome"].clone()).collect();
+
+        assert_eq!(outcomes, vec!["success", "failure"], "Should list matching events newest first.");
+        assert_eq!(body[0]["ip"], "127.0.0.1");
+
+        let req = test::TestRequest::get().uri("/audit?action=users.import&target=username:test_audit_user&format=csv").to_request();
+        let resp = container.call(req).await?;
+
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+        let lines: Vec<_> = body.lines().collect();
+
+        assert_eq!(lines[0], CSV_COLUMNS);
+        assert!(lines[1].contains(",users.import,success,1,test_audit_admin,"));
+        assert!(lines[1].contains("\"test, agent\""), "Fields with commas should be quoted.");
+        assert!(lines[1].ends_with("\"{\"\"role\"\":{\"\"from\"\":null,\"\"to\"\":\"\"user\"\"}}\""));
+        Ok(())
+    }
+
+    #[test]
+    fn test_escape_csv_formulas() {
+        assert_eq!(csv_field(Some("=HYPERLINK(\"http://evil\")".into())), "\"'=HYPERLINK(\"\"http://evil\"\")\"");
+        assert_eq!(csv_field(Some("@SUM(A1)".into())), "'@SUM(A1)");
+        assert_eq!(csv_field(Some("-1+1".into())), "'-1+1");
+        assert_eq!(csv_field(Some("test_audit_user".into())), "test_audit_user");
+    }
+}
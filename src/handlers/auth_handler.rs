@@ -1,30 +1,83 @@
-use ntex::web::{post, types, Error, HttpResponse, Responder};
+use ntex::web::{post, types, Error, HttpRequest, HttpResponse, Responder};
 
-use crate::payload::{UserAuthDto, UserCreateDto};
-use crate::states::AuthState;
+use crate::extractors::ValidJson;
+use crate::handlers::audit_handler::audit_context;
+use crate::payload::{AuditRecordDto, ProblemDto, TokenDto, UserAuthDto, UserCreateDto, UserDto};
+use crate::states::{AuditState, AuthState};
 
+#[utoipa::path(
+    post,
+    path = "/auth/login",
+    tag = "auth",
+    request_body = UserAuthDto,
+    responses(
+        (status = 200, description = "Access token for the user", body = TokenDto),
+        (status = 400, description = "Wrong password", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown user", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid request fields", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 429, description = "Too many attempts", body = ProblemDto, content_type = "application/problem+json"),
+    )
+)]
 #[post("/login")]
 pub async fn auth(
-    payload: types::Json<UserAuthDto>,
+    req: HttpRequest,
+    payload: ValidJson<UserAuthDto>,
     auth_state: types::State<AuthState>,
+    audit_state: types::State<AuditState>,
 ) -> Result<impl Responder, Error> {
-    let types::Json(user_data) = payload;
+    let ValidJson(user_data) = payload;
+    let target = user_data.identity.to_string();
 
-    let user = auth_state.auth_service.authorization_user(user_data).await?;
+    let user = auth_state.auth_service.authorization_user(user_data).await;
+
+    let record = AuditRecordDto {
+        action: "auth.login".into(),
+        success: user.is_ok(),
+        target: Some(target),
+        diff: None,
+    };
+    audit_state.audit_service.record(audit_context(&req), record).await;
+
+    let user = user?;
 
     let result = auth_state.token_service.generate_token(user)?;
 
     Ok(HttpResponse::Ok().json(&result))
 }
 
+#[utoipa::path(
+    post,
+    path = "/auth/register",
+    tag = "auth",
+    request_body = UserCreateDto,
+    responses(
+        (status = 200, description = "Created user", body = UserDto),
+        (status = 400, description = "User already exists", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid request fields or password policy violation", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 429, description = "Too many attempts", body = ProblemDto, content_type = "application/problem+json"),
+    )
+)]
 #[post("/register")]
 pub async fn register(
-    payload: types::Json<UserCreateDto>,
+    req: HttpRequest,
+    payload: ValidJson<UserCreateDto>,
     auth_state: types::State<AuthState>,
+    audit_state: types::State<AuditState>,
 ) -> Result<impl Responder, Error> {
-    let types::Json(create_data) = payload;
+    let ValidJson(create_data) = payload;
+    let target = format!("username:{}", create_data.username);
+
+    let result = auth_state.auth_service.create_user(create_data).await;
 
-    let result = auth_state.auth_service.create_user(create_data).await?;
+    let record = AuditRecordDto {
+        action: "auth.register".into(),
+        success: result.is_ok(),
+        target: Some(target),
+        diff: None,
+    };
+    audit_state.audit_service.record(audit_context(&req), record).await;
+
+    let result = result?;
 
     Ok(HttpResponse::Ok().json(&result))
 }
@@ -33,20 +86,24 @@ pub async fn register(
 mod tests {
     use std::sync::Arc;
 
+    use argon2::{Algorithm, Argon2, Params, PasswordHasher, Version};
+    use argon2::password_hash::{rand_core, SaltString};
     use ntex::http::StatusCode;
     use ntex::web::{test, App, Error};
     use serde_json::{from_slice, json, Value};
 
-    use crate::configs::{Argon2Hash, Database, Password, SchemaManager, Settings};
+    use crate::configs::{Argon2Hash, Database, Password, PasswordChecker, SchemaManager, Settings};
     use crate::errors::{ApiError, DatabaseError};
-    use crate::repository::UserRepository;
-    use crate::services::{AuthService, TokenService};
+    use crate::payload::AuditQueryDto;
+    use crate::repository::{AuditRepository, UserRepository};
+    use crate::services::{AuditService, AuthService, TokenService};
     use crate::sql;
     use super::*;
 
     struct AuthEnvironment {
         user_repo: Arc<UserRepository>,
         auth_state: AuthState,
+        audit_state: AuditState,
     }
 
     impl AuthEnvironment {
@@ -54,23 +111,29 @@ mod tests {
             let settings = Arc::new(Settings::new()?);
             let database = Arc::new(Database::new(&settings, &SchemaManager::default()).await?);
             let hasher = Arc::new(Argon2Hash::new()) as Arc<dyn Password>;
+            let policy = Arc::new(PasswordChecker::new(&settings));
 
-            let user_repo = Arc::new(UserRepository::new(&hasher, &database));
+            let user_repo = Arc::new(UserRepository::new(&hasher, &policy, &database));
+            let audit_repo = Arc::new(AuditRepository::new(&database));
 
             let auth_state = AuthState {
                 auth_service: Arc::new(AuthService::new(&user_repo, &hasher)),
                 token_service: Arc::new(TokenService::new(&settings)),
             };
 
-            Ok(Self { user_repo, auth_state })
+            let audit_state = AuditState {
+                audit_service: Arc::new(AuditService::new(&audit_repo)),
+            };
+
+            Ok(Self { user_repo, auth_state, audit_state })
         }
     }
 
     #[ntex::test]
     async fn test_auth() -> Result<(), Error> {
-        let AuthEnvironment { user_repo, auth_state } = AuthEnvironment::new().await?;
+        let AuthEnvironment { user_repo, auth_state, audit_state } = AuthEnvironment::new().await?;
     
-        let app = App::new().state(auth_state).service(auth);
+        let app = App::new().state(auth_state).state(audit_state.clone()).service(auth);
         let container = test::init_service(app).await;
     
         let username = "test_auth_user";
@@ -104,14 +167,87 @@ mod tests {
         assert!(body["token"].is_string());
         assert!(body["iat"].is_number());
         assert!(body["exp"].is_number());
+
+        let payload = json!({
+            "identity": { "email": email },
+            "password": "test_auth_wrong_password"
+        });
+
+        let req = test::TestRequest::post().uri("/login").set_json(&payload).to_request();
+        let resp = container.call(req).await?;
+
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let query = AuditQueryDto {
+            action: Some("auth.login".into()),
+            target: Some(format!("email:{email}")),
+            ..Default::default()
+        };
+        let outcomes: Vec<_> = audit_state
+            .audit_service
+            .list(query)
+            .await?
+            .into_iter()
+            .map(|event| event.outcome)
+            .collect();
+
+        assert_eq!(outcomes, vec!["failure", "success"], "Both login attempts should be audited.");
+        Ok(())
+    }
+
+    #[ntex::test]
+    async fn test_auth_upgrades_outdated_hash() -> Result<(), Error> {
+        let AuthEnvironment { user_repo, auth_state, audit_state } = AuthEnvironment::new().await?;
+
+        let app = App::new().state(auth_state).state(audit_state).service(auth);
+        let container = test::init_service(app).await;
+
+        // Legacy accounts may predate the username rules and still need to log in.
+        let username = "Test Rehash User";
+        let email = "test_rehash_user@sieluna.com";
+        let password = "test_rehash_password";
+
+        let weak_hasher = Argon2::new(Algorithm::Argon2id, Version::V0x13, Params::new(8, 1, 1, None).unwrap());
+        let weak_hash = weak_hasher
+            .hash_password(password.as_bytes(), &SaltString::generate(&mut rand_core::OsRng))
+            .unwrap()
+            .to_string();
+
+        let statement = sql!(
+            user_repo.database.scheme,
+            "INSERT INTO users (username, email, password) VALUES ($1, $2, $3)"
+        );
+        sqlx::query(&statement)
+            .bind(&username)
+            .bind(&email)
+            .bind(&weak_hash)
+            .execute(&user_repo.database.pool)
+            .await
+            .map_err(DatabaseError::from)?;
+
+        let payload = json!({
+            "identity": { "username": username },
+            "password": password
+        });
+
+        let req = test::TestRequest::post().uri("/login").set_json(&payload).to_request();
+        let resp = container.call(req).await?;
+
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let user = user_repo.find_by_username(username).await.unwrap();
+
+        assert_ne!(user.password, weak_hash);
+        assert!(!user_repo.password.needs_rehash(&user.password));
+        assert!(user_repo.password.verify(password, &user.password)?);
         Ok(())
     }
 
     #[ntex::test]
     async fn test_register() -> Result<(), Error> {
-        let AuthEnvironment { auth_state, .. } = AuthEnvironment::new().await?;
+        let AuthEnvironment { auth_state, audit_state, .. } = AuthEnvironment::new().await?;
 
-        let app = App::new().state(auth_state).service(register);
+        let app = App::new().state(auth_state).state(audit_state).service(register);
         let container = test::init_service(app).await;
 
         let payload = json!({
@@ -131,4 +267,44 @@ mod tests {
         assert_eq!(body["email"], "test_register_user@sieluna.com");
         Ok(())
     }
+
+    #[ntex::test]
+    async fn test_register_reports_every_invalid_field() -> Result<(), Error> {
+        let AuthEnvironment { auth_state, audit_state, .. } = AuthEnvironment::new().await?;
+
+        let app = App::new().state(auth_state).state(audit_state).service(register);
+        let container = test::init_service(app).await;
+
+        let payload = json!({
+            "username": "x y",
+            "email": "not-an-email",
+            "password": "test_register_password"
+        });
+
+        let req = test::TestRequest::post().uri("/register").set_json(&payload).to_request();
+        let resp = container.call(req).await?;
+
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let body: Value = from_slice(test::read_body(resp).await.as_ref())?;
+        let fields: Vec<_> = body["errors"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|error| error["field"].as_str().unwrap())
+            .collect();
+
+        assert_eq!(body["code"], "request.invalid_fields");
+        assert_eq!(fields, vec!["email", "username"]);
+
+        let req = test::TestRequest::post()
+            .uri("/register")
+            .header("content-type", "application/json")
+            .set_payload("{")
+            .to_request();
+        let resp = container.call(req).await?;
+
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        Ok(())
+    }
 }
@@ -0,0 +1,116 @@
+use std::sync::Arc;
+
+use ntex::http::header;
+use ntex::web::{get, types, Error, HttpResponse, Responder};
+use utoipa::openapi::security::{Http, HttpAuthScheme, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+use utoipa_swagger_ui::Config;
+
+use crate::payload::{FieldErrorDto, OAuthErrorDto, ProblemDto};
+
+const OPENAPI_PATH: &str = "/openapi.json";
+
+struct SecuritySchemes;
+
+impl Modify for SecuritySchemes {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(Http::builder().scheme(HttpAuthScheme::Bearer).bearer_format("JWT").build()),
+        );
+        components.add_security_scheme("basic", SecurityScheme::Http(Http::new(HttpAuthScheme::Basic)));
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Smarinth"),
+    paths(
+        super::auth_handler::auth,
+        super::auth_handler::register,
+        super::oidc_handler::oidc_start,
+        super::oidc_handler::oidc_callback,
+        super::oauth_handler::oauth_token,
+        super::oauth_handler::oauth_introspect,
+        super::oauth_handler::oauth_consent,
+        super::oauth_handler::oauth_authorize,
+        super::oauth_handler::register_oauth_client,
+        super::admin_handler::import_users,
+        super::admin_handler::show_config,
+        super::audit_handler::audit_events,
+        super::health_handler::healthz,
+        super::health_handler::readyz,
+        super::health_handler::status,
+        super::metrics_handler::metrics,
+    ),
+    components(schemas(ProblemDto, FieldErrorDto, OAuthErrorDto)),
+    modifiers(&SecuritySchemes),
+    tags(
+        (name = "auth", description = "Login, registration and external identity providers"),
+        (name = "oauth", description = "OAuth2 authorization server for third-party applications"),
+        (name = "health", description = "Liveness and readiness probes"),
+        (name = "admin", description = "Administration, restricted to the admin role"),
+    )
+)]
+pub struct ApiDoc;
+
+#[get("/openapi.json")]
+pub async fn openapi_json() -> Result<impl Responder, Error> {
+    Ok(HttpResponse::Ok().json(&ApiDoc::openapi()))
+}
+
+#[get("/docs{tail}*")]
+pub async fn docs(path: types::Path<String>) -> Result<impl Responder, Error> {
+    let tail = path.into_inner();
+
+    // The bundled UI loads its assets relative to the page, so it must be served from a directory.
+    let Some(file) = tail.strip_prefix('/') else {
+        return Ok(HttpResponse::Found().header(header::LOCATION, "/docs/").finish());
+    };
+
+    match utoipa_swagger_ui::serve(file, Arc::new(Config::from(OPENAPI_PATH))) {
+        Ok(Some(file)) => Ok(HttpResponse::Ok().content_type(file.content_type).body(file.bytes.to_vec())),
+        Ok(None) => Ok(HttpResponse::NotFound().finish()),
+        Err(e) => Ok(HttpResponse::InternalServerError().body(e.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ntex::http::StatusCode;
+    use ntex::web::{test, App, Error};
+    use serde_json::{from_slice, Value};
+
+    use super::*;
+
+    #[ntex::test]
+    async fn test_openapi_and_docs() -> Result<(), Error> {
+        let app = App::new().service(openapi_json).service(docs);
+        let container = test::init_service(app).await;
+
+        let req = test::TestRequest::get().uri(OPENAPI_PATH).to_request();
+        let resp = test::call_service(&container, req).await;
+
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let body: Value = from_slice(&test::read_body(resp).await)?;
+
+        assert!(body["paths"]["/auth/login"]["post"].is_object());
+        assert!(body["paths"]["/api/admin/users/import"]["post"].is_object());
+        assert!(body["components"]["securitySchemes"]["bearer"].is_object());
+        assert!(body["components"]["schemas"]["ProblemDto"].is_object());
+
+        let req = test::TestRequest::get().uri("/docs").to_request();
+        let resp = test::call_service(&container, req).await;
+
+        assert_eq!(resp.status(), StatusCode::FOUND);
+
+        let req = test::TestRequest::get().uri("/docs/").to_request();
+        let resp = test::call_service(&container, req).await;
+
+        assert_eq!(resp.status(), StatusCode::OK);
+        Ok(())
+    }
+}
@@ -0,0 +1,135 @@
+use ntex::web::{get, types, Error, HttpResponse, Responder};
+use serde_json::json;
+
+use crate::payload::{ProblemDto, ReadinessDto, StatusDto};
+use crate::states::HealthState;
+
+#[utoipa::path(
+    get,
+    path = "/healthz",
+    tag = "health",
+    responses((status = 200, description = "The process is up"))
+)]
+#[get("/healthz")]
+pub async fn healthz() -> Result<impl Responder, Error> {
+    Ok(HttpResponse::Ok().json(&json!({ "status": "ok" })))
+}
+
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    tag = "health",
+    responses(
+        (status = 200, description = "Every dependency is available", body = ReadinessDto),
+        (status = 503, description = "At least one dependency is unavailable", body = ReadinessDto),
+    )
+)]
+#[get("/readyz")]
+pub async fn readyz(health_state: types::State<HealthState>) -> Result<impl Responder, Error> {
+    let result = health_state.health_service.readiness().await;
+
+    let mut response = if result.ready {
+        HttpResponse::Ok()
+    } else {
+        HttpResponse::ServiceUnavailable()
+    };
+
+    Ok(response.json(&result))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/status",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Build, uptime, pool and readiness details", body = StatusDto),
+        (status = 403, description = "Not an administrator", body = ProblemDto, content_type = "application/problem+json"),
+    ),
+    security(("bearer" = []))
+)]
+#[get("/status")]
+pub async fn status(health_state: types::State<HealthState>) -> Result<impl Responder, Error> {
+    let result = health_state.health_service.status().await;
+
+    Ok(HttpResponse::Ok().json(&result))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use ntex::http::StatusCode;
+    use ntex::web::{test, App, Error};
+    use serde_json::{from_slice, Value};
+
+    use crate::configs::{Database, SchemaManager, Settings};
+    use crate::errors::ApiError;
+    use crate::services::HealthService;
+    use super::*;
+
+    async fn health_state() -> Result<(HealthState, Arc<Database>), ApiError> {
+        let settings = Arc::new(Settings::new()?);
+        let database = Arc::new(Database::new(&settings, &SchemaManager::default()).await?);
+        let health_state = HealthState {
+            health_service: Arc::new(HealthService::new(&settings, &database)),
+        };
+
+        Ok((health_state, database))
+    }
+
+    #[ntex::test]
+    async fn test_probes() -> Result<(), Error> {
+        let (health_state, _) = health_state().await?;
+        let app = App::new().state(health_state).service(healthz).service(readyz).service(status);
+        let container = test::init_service(app).await;
+
+        let req = test::TestRequest::get().uri("/healthz").to_request();
+        let resp = test::call_service(&container, req).await;
+
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let req = test::TestRequest::get().uri("/readyz").to_request();
+        let resp = test::call_service(&container, req).await;
+
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let body: Value = from_slice(&test::read_body(resp).await)?;
+
+        assert_eq!(body["ready"], true);
+        assert_eq!(body["checks"]["database"]["healthy"], true);
+
+        let req = test::TestRequest::get().uri("/status").to_request();
+        let resp = test::call_service(&container, req).await;
+        let body: Value = from_slice(&test::read_body(resp).await)?;
+
+        assert_eq!(body["build"]["version"], env!("CARGO_PKG_VERSION"));
+        assert!(body["database"]["size"].is_number());
+        Ok(())
+    }
+
+    #[ntex::test]
+    async fn test_readiness_hides_failure_detail() -> Result<(), Error> {
+        let (health_state, database) = health_state().await?;
+        database.pool.close().await;
+
+        let app = App::new().state(health_state).service(readyz).service(status);
+        let container = test::init_service(app).await;
+
+        let req = test::TestRequest::get().uri("/readyz").to_request();
+        let resp = test::call_service(&container, req).await;
+
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        let body: Value = from_slice(&test::read_body(resp).await)?;
+
+        assert_eq!(body["checks"]["database"]["healthy"], false);
+        assert!(body["checks"]["database"].get("detail").is_none());
+
+        let req = test::TestRequest::get().uri("/status").to_request();
+        let resp = test::call_service(&container, req).await;
+        let body: Value = from_slice(&test::read_body(resp).await)?;
+
+        assert!(body["readiness"]["checks"]["database"]["detail"].is_string());
+        Ok(())
+    }
+}
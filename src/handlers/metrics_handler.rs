@@ -0,0 +1,65 @@
+use ntex::web::{get, types, Error, HttpResponse, Responder};
+
+use crate::states::MetricsState;
+
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "health",
+    responses((status = 200, description = "Metrics in the Prometheus text format", content_type = "text/plain"))
+)]
+#[get("/metrics")]
+pub async fn metrics(metrics_state: types::State<MetricsState>) -> Result<impl Responder, Error> {
+    let result = metrics_state.metrics_service.render();
+
+    Ok(HttpResponse::Ok().content_type("text/plain; version=0.0.4; charset=utf-8").body(result))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use ntex::http::StatusCode;
+    use ntex::web::{test, App, Error};
+
+    use crate::configs::{Database, SchemaManager, Settings};
+    use crate::errors::ApiError;
+    use crate::handlers::healthz;
+    use crate::middlewares::RequestMetrics;
+    use crate::services::MetricsService;
+    use super::*;
+
+    async fn metrics_state() -> Result<MetricsState, ApiError> {
+        let settings = Arc::new(Settings::new()?);
+        let database = Arc::new(Database::new(&settings, &SchemaManager::default()).await?);
+
+        Ok(MetricsState {
+            metrics_service: Arc::new(MetricsService::new(&database)),
+        })
+    }
+
+    #[ntex::test]
+    async fn test_metrics() -> Result<(), Error> {
+        let app = App::new()
+            .state(metrics_state().await?)
+            .wrap(RequestMetrics)
+            .service(healthz)
+            .service(metrics);
+        let container = test::init_service(app).await;
+
+        let req = test::TestRequest::get().uri("/healthz").to_request();
+        test::call_service(&container, req).await;
+
+        let req = test::TestRequest::get().uri("/metrics").to_request();
+        let resp = test::call_service(&container, req).await;
+
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+
+        assert!(body.contains(r#"smarinth_http_requests_total{method="GET",route="/healthz",status="200"}"#));
+        assert!(body.contains("smarinth_http_request_duration_seconds_bucket"));
+        assert!(body.contains(r#"smarinth_database_connections{state="max"}"#));
+        Ok(())
+    }
+}
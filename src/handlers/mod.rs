@@ -1,4 +1,18 @@
+mod admin_handler;
+mod audit_handler;
 mod auth_handler;
+mod docs_handler;
+mod health_handler;
+mod metrics_handler;
+mod oauth_handler;
+mod oidc_handler;
 mod user_handle;
 
-pub use auth_handler::{auth, register};
\ No newline at end of file
+pub use admin_handler::{import_users, show_config};
+pub use audit_handler::audit_events;
+pub use auth_handler::{auth, register};
+pub use docs_handler::{docs, openapi_json};
+pub use health_handler::{healthz, readyz, status};
+pub use metrics_handler::metrics;
+pub use oauth_handler::{oauth_authorize, oauth_consent, oauth_introspect, oauth_token, register_oauth_client};
+pub use oidc_handler::{oidc_callback, oidc_start};
\ No newline at end of file
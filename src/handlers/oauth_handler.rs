@@ -0,0 +1,279 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ntex::http::header;
+use ntex::web::{get, post, types, Error, HttpRequest, HttpResponse, Responder};
+use serde_json::{json, Value};
+
+use crate::errors::{AuthError, OAuthError};
+use crate::extractors::ValidJson;
+use crate::handlers::audit_handler::audit_context;
+use crate::payload::{
+    AuditRecordDto,
+    OAuthAuthorizeDto, OAuthClientCreateDto, OAuthClientCredentialsDto, OAuthConsentDto, OAuthDecisionDto, OAuthErrorDto,
+    OAuthIntrospectRequestDto, OAuthIntrospectionDto, OAuthRedirectDto, OAuthTokenDto, OAuthTokenRequestDto, ProblemDto,
+    UserDto,
+};
+use crate::services::{AuditService, ClientCredentials};
+use crate::states::{AuditState, OAuthState};
+
+/// Client credentials from the `Authorization: Basic` header, falling back to the ones in the request body.
+fn client_credentials(
+    req: &HttpRequest,
+    client_id: Option<String>,
+    client_secret: Option<String>,
+) -> Result<ClientCredentials, OAuthError> {
+    let basic = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Basic "));
+
+    if let Some(encoded) = basic {
+        let decoded = STANDARD
+            .decode(encoded.trim())
+            .ok()
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+            .ok_or(OAuthError::InvalidClient)?;
+        let (client_id, client_secret) = decoded.split_once(':').ok_or(OAuthError::InvalidClient)?;
+
+        return Ok(ClientCredentials {
+            client_id: client_id.to_string(),
+            client_secret: Some(client_secret.to_string()),
+        });
+    }
+
+    Ok(ClientCredentials {
+        client_id: client_id.ok_or(OAuthError::InvalidClient)?,
+        client_secret,
+    })
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/oauth/token",
+    tag = "oauth",
+    request_body(content = OAuthTokenRequestDto, content_type = "application/x-www-form-urlencoded"),
+    responses(
+        (status = 200, description = "Access token for the client", body = OAuthTokenDto),
+        (status = 400, description = "Invalid grant, scope or request", body = OAuthErrorDto),
+        (status = 401, description = "Client authentication failed", body = OAuthErrorDto),
+    ),
+    security((), ("basic" = []))
+)]
+#[post("/oauth/token")]
+pub async fn oauth_token(
+    req: HttpRequest,
+    payload: types::Form<OAuthTokenRequestDto>,
+    oauth_state: types::State<OAuthState>,
+) -> Result<impl Responder, Error> {
+    let types::Form(token_data) = payload;
+
+    let credentials = client_credentials(&req, token_data.client_id.clone(), token_data.client_secret.clone())?;
+
+    let result = oauth_state.oauth_service.exchange(token_data, credentials).await?;
+
+    Ok(HttpResponse::Ok().header(header::CACHE_CONTROL, "no-store").json(&result))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/oauth/introspect",
+    tag = "oauth",
+    request_body(content = OAuthIntrospectRequestDto, content_type = "application/x-www-form-urlencoded"),
+    responses(
+        (status = 200, description = "Whether the token is active, with its claims", body = OAuthIntrospectionDto),
+        (status = 400, description = "Public clients cannot introspect tokens", body = OAuthErrorDto),
+        (status = 401, description = "Client authentication failed", body = OAuthErrorDto),
+    ),
+    security((), ("basic" = []))
+)]
+#[post("/oauth/introspect")]
+pub async fn oauth_introspect(
+    req: HttpRequest,
+    payload: types::Form<OAuthIntrospectRequestDto>,
+    oauth_state: types::State<OAuthState>,
+) -> Result<impl Responder, Error> {
+    let types::Form(introspect_data) = payload;
+
+    let credentials = client_credentials(&req, introspect_data.client_id, introspect_data.client_secret)?;
+
+    let result = oauth_state.oauth_service.introspect(&introspect_data.token, credentials).await?;
+
+    Ok(HttpResponse::Ok().json(&result))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/oauth/authorize",
+    tag = "oauth",
+    params(OAuthAuthorizeDto),
+    responses(
+        (status = 200, description = "Client and scopes to show on the consent screen", body = OAuthConsentDto),
+        (status = 400, description = "Invalid authorization request", body = OAuthErrorDto),
+    ),
+    security(("bearer" = []))
+)]
+#[get("/oauth/authorize")]
+pub async fn oauth_consent(
+    query: types::Query<OAuthAuthorizeDto>,
+    oauth_state: types::State<OAuthState>,
+) -> Result<impl Responder, Error> {
+    let types::Query(authorize_data) = query;
+
+    let result = oauth_state.oauth_service.consent(&authorize_data).await?;
+
+    Ok(HttpResponse::Ok().json(&result))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/oauth/authorize",
+    tag = "oauth",
+    request_body = OAuthDecisionDto,
+    responses(
+        (status = 200, description = "Redirect back to the client with a code or an error", body = OAuthRedirectDto),
+        (status = 400, description = "Invalid authorization request", body = OAuthErrorDto),
+    ),
+    security(("bearer" = []))
+)]
+#[post("/oauth/authorize")]
+pub async fn oauth_authorize(
+    req: HttpRequest,
+    payload: types::Json<OAuthDecisionDto>,
+    oauth_state: types::State<OAuthState>,
+    audit_state: types::State<AuditState>,
+) -> Result<impl Responder, Error> {
+    let types::Json(OAuthDecisionDto { request, approve }) = payload;
+
+    let user = req.extensions().get::<UserDto>().cloned().ok_or(AuthError::MissingToken)?;
+    let target = format!("client:{}", request.client_id);
+    let diff = AuditService::diff(&Value::Null, &json!(request));
+
+    let result = oauth_state.oauth_service.authorize(&user, request, approve).await;
+
+    let record = AuditRecordDto {
+        action: if approve { "oauth.consent_granted" } else { "oauth.consent_denied" }.into(),
+        success: result.is_ok(),
+        target: Some(target),
+        diff: Some(diff),
+    };
+    audit_state.audit_service.record(audit_context(&req), record).await;
+
+    let result = result?;
+
+    Ok(HttpResponse::Ok().json(&result))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/oauth/clients",
+    tag = "admin",
+    request_body = OAuthClientCreateDto,
+    responses(
+        (status = 201, description = "Registered client; the secret is only returned once", body = OAuthClientCredentialsDto),
+        (status = 403, description = "Not an administrator", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid request fields", body = ProblemDto, content_type = "application/problem+json"),
+    ),
+    security(("bearer" = []))
+)]
+#[post("/oauth/clients")]
+pub async fn register_oauth_client(
+    req: HttpRequest,
+    payload: ValidJson<OAuthClientCreateDto>,
+    oauth_state: types::State<OAuthState>,
+    audit_state: types::State<AuditState>,
+) -> Result<impl Responder, Error> {
+    let ValidJson(client_data) = payload;
+
+    let result = oauth_state.oauth_service.register_client(client_data).await?;
+
+    let record = AuditRecordDto {
+        action: "oauth.client_registered".into(),
+        success: true,
+        target: Some(format!("client:{}", result.client_id)),
+        diff: Some(AuditService::diff(&Value::Null, &json!(result))),
+    };
+    audit_state.audit_service.record(audit_context(&req), record).await;
+
+    Ok(HttpResponse::Created().json(&result))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use ntex::http::StatusCode;
+    use ntex::web::{test, App, Error};
+    use serde_json::{from_slice, Value};
+
+    use crate::configs::{Database, MultiHash, Password, PasswordChecker, SchemaManager, Settings};
+    use crate::errors::ApiError;
+    use crate::repository::{ClientRepository, GrantRepository, UserRepository};
+    use crate::services::{OAuthService, TokenService};
+    use super::*;
+
+    async fn oauth_state() -> Result<OAuthState, ApiError> {
+        let settings = Arc::new(Settings::new()?);
+        let database = Arc::new(Database::new(&settings, &SchemaManager::default()).await?);
+        let hasher = Arc::new(MultiHash::new()) as Arc<dyn Password>;
+        let policy = Arc::new(PasswordChecker::new(&settings));
+
+        let user_repo = Arc::new(UserRepository::new(&hasher, &policy, &database));
+        let client_repo = Arc::new(ClientRepository::new(&database));
+        let grant_repo = Arc::new(GrantRepository::new(&database));
+        let token_service = Arc::new(TokenService::new(&settings));
+
+        Ok(OAuthState {
+            oauth_service: Arc::new(OAuthService::new(&client_repo, &user_repo, &grant_repo, &token_service, &hasher)),
+        })
+    }
+
+    #[ntex::test]
+    async fn test_token_with_basic_credentials() -> Result<(), Error> {
+        let oauth_state = oauth_state().await?;
+
+        let client = oauth_state
+            .oauth_service
+            .register_client(OAuthClientCreateDto {
+                name: "test_oauth_basic_client".into(),
+                redirect_uris: vec![],
+                scopes: vec!["devices:read".into()],
+                grant_types: vec!["client_credentials".into()],
+                confidential: true,
+            })
+            .await?;
+
+        let app = App::new().state(oauth_state).service(oauth_token);
+        let container = test::init_service(app).await;
+
+        let basic = |secret: &str| format!("Basic {}", STANDARD.encode(format!("{}:{secret}", client.client_id)));
+
+        let req = test::TestRequest::post()
+            .uri("/oauth/token")
+            .header(header::AUTHORIZATION, basic(client.client_secret.as_deref().unwrap()))
+            .set_form(&[("grant_type", "client_credentials")])
+            .to_request();
+        let resp = test::call_service(&container, req).await;
+
+        assert_eq!(resp.status(), StatusCode::OK, "Should issue a token to a confidential client.");
+
+        let body: Value = from_slice(&test::read_body(resp).await).unwrap();
+
+        assert_eq!(body["token_type"], "Bearer");
+        assert_eq!(body["scope"], "devices:read");
+
+        let req = test::TestRequest::post()
+            .uri("/oauth/token")
+            .header(header::AUTHORIZATION, basic("wrong_secret"))
+            .set_form(&[("grant_type", "client_credentials")])
+            .to_request();
+        let resp = test::call_service(&container, req).await;
+
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED, "Should reject a wrong client secret.");
+
+        let body: Value = from_slice(&test::read_body(resp).await).unwrap();
+
+        assert_eq!(body["error"], "invalid_client");
+        Ok(())
+    }
+}
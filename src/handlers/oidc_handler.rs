@@ -0,0 +1,71 @@
+use ntex::http::header;
+use ntex::web::{get, types, Error, HttpRequest, HttpResponse, Responder};
+
+use crate::handlers::audit_handler::audit_context;
+use crate::payload::{AuditRecordDto, OidcCallbackDto, ProblemDto, TokenDto};
+use crate::states::{AuditState, OidcState};
+
+#[utoipa::path(
+    get,
+    path = "/auth/oidc/{provider}/start",
+    tag = "auth",
+    params(("provider" = String, Path, description = "Configured identity provider")),
+    responses(
+        (status = 302, description = "Redirect to the identity provider"),
+        (status = 404, description = "Unknown provider", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 502, description = "Identity provider unreachable", body = ProblemDto, content_type = "application/problem+json"),
+    )
+)]
+#[get("/oidc/{provider}/start")]
+pub async fn oidc_start(
+    path: types::Path<String>,
+    oidc_state: types::State<OidcState>,
+) -> Result<impl Responder, Error> {
+    let provider = path.into_inner();
+
+    let location = oidc_state.oidc_service.start(&provider).await?;
+
+    Ok(HttpResponse::Found().header(header::LOCATION, location).finish())
+}
+
+#[utoipa::path(
+    get,
+    path = "/auth/oidc/{provider}/callback",
+    tag = "auth",
+    params(("provider" = String, Path, description = "Configured identity provider"), OidcCallbackDto),
+    responses(
+        (status = 200, description = "Access token for the linked user", body = TokenDto),
+        (status = 400, description = "Unknown or expired login state", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 401, description = "Login denied or invalid ID token", body = ProblemDto, content_type = "application/problem+json"),
+    )
+)]
+#[get("/oidc/{provider}/callback")]
+pub async fn oidc_callback(
+    req: HttpRequest,
+    path: types::Path<String>,
+    query: types::Query<OidcCallbackDto>,
+    oidc_state: types::State<OidcState>,
+    audit_state: types::State<AuditState>,
+) -> Result<impl Responder, Error> {
+    let provider = path.into_inner();
+    let types::Query(callback_data) = query;
+
+    let user = oidc_state.oidc_service.callback(&provider, callback_data).await;
+
+    let record = AuditRecordDto {
+        action: "auth.oidc_login".into(),
+        success: user.is_ok(),
+        target: Some(match &user {
+            Ok(user) => format!("username:{}", user.username),
+            Err(_) => format!("provider:{provider}"),
+        }),
+        diff: None,
+    };
+    audit_state.audit_service.record(audit_context(&req), record).await;
+
+    let user = user?;
+
+    let result = oidc_state.token_service.generate_token(user)?;
+
+    Ok(HttpResponse::Ok().json(&result))
+}
@@ -1,22 +1,36 @@
+use std::fmt::Display;
 use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
-use std::{env, io};
+use std::{io, process};
 
-use ntex::{http, web};
+use clap::Parser;
+use ntex::web;
 use ntex::web::{scope, App};
-use ntex_cors::Cors;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-use crate::configs::{Argon2Hash, Database, Password, SchemaManager, Settings};
-use crate::handlers::{auth, register};
-use crate::middlewares::{JWTAuth, JWTAuthMiddleware};
-use crate::repository::UserRepository;
-use crate::services::{AuthService, TokenService, UserService};
-use crate::states::{AuthState, UserState};
+use crate::configs::{
+    Argon2Hash, Cli, Command, Database, LogFilter, LogFormat, MultiHash, Password, PasswordChecker, Reloadable, SchemaManager,
+    Settings, SettingsReloader, TraceExporter,
+};
+use crate::handlers::{
+    audit_events, auth, docs, healthz, import_users, metrics, oauth_authorize, oauth_consent, oauth_introspect, oauth_token,
+    oidc_callback, oidc_start, openapi_json, readyz, register, register_oauth_client, show_config, status,
+};
+use crate::middlewares::{AdminGuard, CorsPolicy, JWTAuth, JWTAuthMiddleware, RateLimit, RequestMetrics, RequestTracing};
+use crate::repository::{
+    AuditRepository, ClientRepository, GrantRepository, IdentityRepository, RateLimitRepository, UserRepository,
+};
+use crate::services::{
+    AuditService, AuthService, HealthService, MetricsService, OAuthService, OidcService, RateLimitService, TokenService,
+    UserService,
+};
+use crate::states::{AuditState, AuthState, HealthState, MetricsState, OAuthState, OidcState, RateLimitState, UserState};
 
+mod commands;
 mod configs;
 mod entities;
 mod errors;
+mod extractors;
 mod handlers;
 mod middlewares;
 mod payload;
@@ -26,35 +40,96 @@ mod states;
 
 #[ntex::main]
 async fn main() -> io::Result<()> {
-    let settings = Arc::new(Settings::new().unwrap());
-    let database = Arc::new(Database::new(&settings, &Default::default()).await.unwrap());
-    let hasher = Arc::new(Argon2Hash::new()) as Arc<dyn Password>;
+    let cli = Cli::parse();
+    let settings = match cli.settings() {
+        Ok(settings) => Arc::new(settings),
+        Err(e) => {
+            eprintln!("{e}");
+            process::exit(2);
+        }
+    };
 
-    let user_repo = Arc::new(UserRepository::new(&hasher, &database));
+    match cli.command.clone() {
+        None | Some(Command::Serve) => serve(cli, settings).await,
+        Some(command) => match commands::run(&command, &settings).await {
+            Ok(output) => {
+                print!("{output}");
+                Ok(())
+            }
+            Err(e) => {
+                eprintln!("{e}");
+                process::exit(1);
+            }
+        },
+    }
+}
+
+/// Prints the error and exits; `Settings::validate` already rejects the config errors, so these are not expected.
+fn exit_on_error<T, E: Display>(result: Result<T, E>, code: i32) -> T {
+    result.unwrap_or_else(|e| {
+        eprintln!("{e}");
+        process::exit(code)
+    })
+}
+
+async fn serve(cli: Cli, settings: Arc<Settings>) -> io::Result<()> {
+    let json_logs = settings.logger.format == LogFormat::Json;
+    let trace_exporter = exit_on_error(TraceExporter::with_settings(&settings), 2);
+    let (filter_layer, log_filter) = LogFilter::layer(&settings);
+
+    tracing_subscriber::registry()
+        .with(filter_layer)
+        .with(json_logs.then(|| tracing_subscriber::fmt::layer().json().with_current_span(true).with_span_list(false)))
+        .with((!json_logs).then(|| tracing_subscriber::fmt::layer()))
+        .with(trace_exporter.as_ref().map(|exporter| tracing_opentelemetry::layer().with_tracer(exporter.tracer())))
+        .init();
+
+    let database = Arc::new(exit_on_error(Database::new(&settings, &SchemaManager::default()).await, 1));
+    let hasher = Arc::new(MultiHash::with_primary(exit_on_error(Argon2Hash::with_settings(&settings), 2))) as Arc<dyn Password>;
+    let policy = Arc::new(PasswordChecker::new(&settings));
+
+    let user_repo = Arc::new(UserRepository::new(&hasher, &policy, &database));
+    let identity_repo = Arc::new(IdentityRepository::new(&database));
+    let client_repo = Arc::new(ClientRepository::new(&database));
+    let grant_repo = Arc::new(GrantRepository::new(&database));
+    let audit_repo = Arc::new(AuditRepository::new(&database));
+    let rate_limit_repo = Arc::new(RateLimitRepository::new(&database));
 
     let auth_service = Arc::new(AuthService::new(&user_repo, &hasher));
     let token_service = Arc::new(TokenService::new(&settings));
     let user_service = Arc::new(UserService::new(&user_repo));
+    let oidc_service = Arc::new(OidcService::new(&settings, &user_repo, &identity_repo, &hasher));
+    let oauth_service = Arc::new(OAuthService::new(&client_repo, &user_repo, &grant_repo, &token_service, &hasher));
+    let health_service = Arc::new(HealthService::new(&settings, &database));
+    let metrics_service = Arc::new(MetricsService::new(&database));
+    let audit_service = Arc::new(AuditService::new(&audit_repo));
+    let rate_limit_service = Arc::new(exit_on_error(RateLimitService::with_settings(&settings, &rate_limit_repo), 2));
 
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env().unwrap_or_else(|_| {
-                let app_name = env!("CARGO_PKG_NAME").replace('-', "_");
-                let level = settings.logger.level.as_str();
-
-                format!("{app_name}={level},tower_http={level}").into()
-            }),
-        )
-        .with(tracing_subscriber::fmt::layer())
-        .init();
+    let cors_policy = exit_on_error(CorsPolicy::with_settings(&settings), 2);
 
-    let ip_addr = settings.server.host.parse::<IpAddr>().unwrap();
+    let reloader = Arc::new(SettingsReloader::new(cli, &settings, vec![
+        Arc::new(log_filter) as Arc<dyn Reloadable>,
+        Arc::new(cors_policy.clone()),
+        rate_limit_service.clone(),
+        token_service.clone(),
+    ]));
+    ntex::rt::spawn(reloader.watch());
+
+    let ip_addr = exit_on_error(settings.server.host.parse::<IpAddr>(), 2);
 
     let address = SocketAddr::from((ip_addr, settings.server.port));
 
+    let metrics_address = settings.metrics.address.as_ref()
+        .filter(|_| settings.metrics.enabled)
+        .map(|metrics_address| exit_on_error(metrics_address.parse::<SocketAddr>(), 2));
+
+    let metrics_state = MetricsState {
+        metrics_service: Arc::clone(&metrics_service),
+    };
+
     tracing::debug!("listening on {}", address);
 
-    web::HttpServer::new(move || {
+    let server = web::HttpServer::new(move || {
         let auth_state = AuthState {
             auth_service: auth_service.clone(),
             token_service: token_service.clone(),
@@ -62,30 +137,100 @@ async fn main() -> io::Result<()> {
         let user_state = UserState {
             user_service: user_service.clone(),
         };
+        let oidc_state = OidcState {
+            oidc_service: oidc_service.clone(),
+            token_service: token_service.clone(),
+        };
+        let oauth_state = OAuthState {
+            oauth_service: oauth_service.clone(),
+        };
+        let health_state = HealthState {
+            health_service: health_service.clone(),
+        };
+        let metrics_state = MetricsState {
+            metrics_service: metrics_service.clone(),
+        };
+        let audit_state = AuditState {
+            audit_service: audit_service.clone(),
+        };
+        let rate_limit_state = RateLimitState {
+            rate_limit_service: rate_limit_service.clone(),
+            token_service: token_service.clone(),
+        };
+        let docs_enabled = settings.docs.enabled;
+        let metrics_enabled = settings.metrics.enabled && metrics_address.is_none();
 
         App::new()
+            .state(settings.clone())
             .state(auth_state.clone())
             .state(user_state.clone())
-            .wrap(
-                Cors::new()
-                    .allowed_origin("*")
-                    .allowed_methods(vec!["GET", "POST", "PUT", "DELETE"])
-                    .allowed_headers(vec![http::header::AUTHORIZATION, http::header::ACCEPT])
-                    .allowed_header(http::header::CONTENT_TYPE)
-                    .max_age(3600)
-                    .finish()
-            )
+            .state(oidc_state.clone())
+            .state(oauth_state.clone())
+            .state(health_state.clone())
+            .state(metrics_state.clone())
+            .state(audit_state.clone())
+            .wrap(RateLimit::new(&Arc::new(rate_limit_state)))
+            .wrap(cors_policy.clone())
+            .wrap(RequestMetrics)
+            .wrap(RequestTracing)
+            .service(healthz)
+            .service(readyz)
+            .configure(|cfg| {
+                if docs_enabled {
+                    cfg.service(openapi_json).service(docs);
+                }
+                if metrics_enabled {
+                    cfg.service(metrics);
+                }
+            })
             .service(
                 scope("/auth")
                     .service(auth)
-                    .service(register),
+                    .service(register)
+                    .service(oidc_start)
+                    .service(oidc_callback)
+                    .service(oauth_token)
+                    .service(oauth_introspect),
             )
             .service(
                 scope("/api")
                     .wrap(JWTAuth::new(&Arc::new(auth_state)))
+                    .service(oauth_consent)
+                    .service(oauth_authorize)
+                    .service(
+                        scope("/admin")
+                            .wrap(AdminGuard)
+                            .service(import_users)
+                            .service(register_oauth_client)
+                            .service(status)
+                            .service(audit_events)
+                            .service(show_config),
+                    ),
             )
     })
         .bind(address)?
-        .run()
-        .await
+        .run();
+
+    let result = match metrics_address {
+        Some(metrics_address) => {
+            tracing::debug!("serving metrics on {}", metrics_address);
+
+            let metrics_server = web::HttpServer::new(move || {
+                App::new()
+                    .state(metrics_state.clone())
+                    .service(metrics)
+            })
+                .bind(metrics_address)?
+                .run();
+
+            tokio::try_join!(server, metrics_server).map(|_| ())
+        }
+        None => server.await,
+    };
+
+    if let Some(exporter) = trace_exporter {
+        exporter.shutdown();
+    }
+
+    result
 }
@@ -0,0 +1,46 @@
+use ntex::{Middleware, Service, ServiceCtx};
+use ntex::web::{Error, ErrorRenderer, WebRequest, WebResponse};
+
+use crate::errors::AuthError;
+use crate::payload::UserDto;
+
+/// Restricts a scope to administrators, must be wrapped inside `JWTAuth`.
+pub struct AdminGuard;
+
+impl<S> Middleware<S> for AdminGuard {
+    type Service = AdminGuardMiddleware<S>;
+
+    fn create(&self, service: S) -> Self::Service {
+        AdminGuardMiddleware { service }
+    }
+}
+
+pub struct AdminGuardMiddleware<S> {
+    service: S,
+}
+
+impl<S, Err> Service<WebRequest<Err>> for AdminGuardMiddleware<S>
+    where
+        S: Service<WebRequest<Err>, Response = WebResponse, Error = Error> + 'static,
+        Err: ErrorRenderer + 'static,
+{
+    type Response = WebResponse;
+    type Error = Error;
+
+    ntex::forward_ready!(service);
+    ntex::forward_shutdown!(service);
+
+    async fn call(
+        &self,
+        req: WebRequest<Err>,
+        ctx: ServiceCtx<'_, Self>,
+    ) -> Result<Self::Response, Self::Error> {
+        let is_admin = req.extensions().get::<UserDto>().is_some_and(UserDto::is_admin);
+
+        if is_admin {
+            ctx.call(&self.service, req).await
+        } else {
+            Err(AuthError::Forbidden)?
+        }
+    }
+}
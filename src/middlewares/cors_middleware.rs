@@ -0,0 +1,302 @@
+use std::sync::{Arc, RwLock};
+
+use ntex::{Middleware, Service, ServiceCtx};
+use ntex::http::header::{self, HeaderName, HeaderValue};
+use ntex::http::Method;
+use ntex::web::{Error, ErrorRenderer, HttpResponse, WebRequest, WebResponse};
+
+use crate::configs::{AllowedOrigin, Reloadable, Settings};
+use crate::errors::ConfigError;
+
+struct Policy {
+    origins: Vec<AllowedOrigin>,
+    methods: Vec<Method>,
+    headers: Vec<HeaderName>,
+    exposed_headers: Option<HeaderValue>,
+    allow_credentials: bool,
+    max_age: Option<u64>,
+}
+
+impl Policy {
+    fn allows_origin(&self, origin: &str) -> bool {
+        let origin = origin.to_ascii_lowercase();
+
+        self.origins.iter().any(|allowed| allowed.matches(&origin))
+    }
+
+    /// `*` can only be sent back for anonymous requests; credentialed ones need the exact origin.
+    fn varies_by_origin(&self) -> bool {
+        let any = self.origins.iter().any(|allowed| matches!(allowed, AllowedOrigin::Any));
+
+        !any || self.allow_credentials
+    }
+
+    fn allow_origin_value(&self, origin: &HeaderValue) -> HeaderValue {
+        if self.varies_by_origin() {
+            origin.clone()
+        } else {
+            HeaderValue::from_static("*")
+        }
+    }
+
+    /// Unless every origin gets `*`, caches must key every response on `Origin`, rejections and requests without one
+    /// included.
+    fn vary(&self, mut res: WebResponse) -> WebResponse {
+        if self.varies_by_origin() {
+            res.headers_mut().append(header::VARY, HeaderValue::from_static("Origin"));
+        }
+        res
+    }
+
+    fn allows_request_headers(&self, requested: Option<&HeaderValue>) -> bool {
+        let Some(requested) = requested.and_then(|value| value.to_str().ok()) else {
+            return true;
+        };
+
+        requested
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .all(|name| self.headers.iter().any(|allowed| allowed.as_str().eq_ignore_ascii_case(name)))
+    }
+
+    fn join<T: AsRef<str>>(values: &[T]) -> Option<HeaderValue> {
+        let joined = values.iter().map(AsRef::as_ref).collect::<Vec<_>>().join(", ");
+
+        (!joined.is_empty()).then(|| HeaderValue::from_str(&joined).ok()).flatten()
+    }
+}
+
+/// Cross-origin policy from `[server.cors]`, validated at startup and replaced whole on reload.
+#[derive(Clone)]
+pub struct CorsPolicy {
+    policy: Arc<RwLock<Arc<Policy>>>,
+}
+
+impl CorsPolicy {
+    pub fn with_settings(settings: &Settings) -> Result<Self, ConfigError> {
+        Ok(Self {
+            policy: Arc::new(RwLock::new(Arc::new(Self::build(settings)?))),
+        })
+    }
+
+    fn build(settings: &Settings) -> Result<Policy, ConfigError> {
+        let cors = &settings.server.cors;
+        let rules = cors.parse()?;
+
+        Ok(Policy {
+            origins: rules.origins,
+            methods: rules.methods,
+            headers: rules.headers,
+            exposed_headers: Policy::join(&rules.exposed_headers),
+            allow_credentials: cors.allow_credentials,
+            max_age: cors.max_age,
+        })
+    }
+}
+
+impl Reloadable for CorsPolicy {
+    fn validate(&self, settings: &Settings) -> Result<(), ConfigError> {
+        Self::build(settings).map(|_| ())
+    }
+
+    fn reload(&self, settings: &Settings) -> Result<(), ConfigError> {
+        let policy = Arc::new(Self::build(settings)?);
+
+        *self.policy.write().unwrap_or_else(|e| e.into_inner()) = policy;
+
+        Ok(())
+    }
+}
+
+impl<S> Middleware<S> for CorsPolicy {
+    type Service = CorsPolicyMiddleware<S>;
+
+    fn create(&self, service: S) -> Self::Service {
+        CorsPolicyMiddleware {
+            service,
+            policy: self.policy.clone(),
+        }
+    }
+}
+
+pub struct CorsPolicyMiddleware<S> {
+    policy: Arc<RwLock<Arc<Policy>>>,
+    service: S,
+}
+
+impl<S, Err> Service<WebRequest<Err>> for CorsPolicyMiddleware<S>
+    where
+        S: Service<WebRequest<Err>, Response = WebResponse, Error = Error> + 'static,
+        Err: ErrorRenderer + 'static,
+{
+    type Response = WebResponse;
+    type Error = Error;
+
+    ntex::forward_ready!(service);
+    ntex::forward_shutdown!(service);
+
+    async fn call(
+        &self,
+        req: WebRequest<Err>,
+        ctx: ServiceCtx<'_, Self>,
+    ) -> Result<Self::Response, Self::Error> {
+        let policy = self.policy.read().unwrap_or_else(|e| e.into_inner()).clone();
+
+        let origin = match req.headers().get(header::ORIGIN) {
+            Some(origin) if origin.to_str().is_ok_and(|value| policy.allows_origin(value)) => origin.clone(),
+            Some(_) if *req.method() == Method::OPTIONS => {
+                return Ok(policy.vary(req.into_response(HttpResponse::Forbidden().finish())));
+            }
+            _ => return ctx.call(&self.service, req).await.map(|res| policy.vary(res)),
+        };
+        let allow_origin = policy.allow_origin_value(&origin);
+
+        let requested_method = req
+            .headers()
+            .get(header::ACCESS_CONTROL_REQUEST_METHOD)
+            .and_then(|value| Method::from_bytes(value.as_bytes()).ok());
+
+        if let Some(requested_method) = requested_method.filter(|_| *req.method() == Method::OPTIONS) {
+            let allowed = policy.methods.contains(&requested_method)
+                && policy.allows_request_headers(req.headers().get(header::ACCESS_CONTROL_REQUEST_HEADERS));
+            if !allowed {
+                return Ok(policy.vary(req.into_response(HttpResponse::Forbidden().finish())));
+            }
+
+            let mut response = HttpResponse::NoContent();
+            response.header(header::ACCESS_CONTROL_ALLOW_ORIGIN, allow_origin);
+            if let Some(methods) = Policy::join(&policy.methods) {
+                response.header(header::ACCESS_CONTROL_ALLOW_METHODS, methods);
+            }
+            if let Some(headers) = Policy::join(&policy.headers) {
+                response.header(header::ACCESS_CONTROL_ALLOW_HEADERS, headers);
+            }
+            if policy.allow_credentials {
+                response.header(header::ACCESS_CONTROL_ALLOW_CREDENTIALS, "true");
+            }
+            if let Some(max_age) = policy.max_age {
+                response.header(header::ACCESS_CONTROL_MAX_AGE, max_age.to_string());
+            }
+
+            return Ok(policy.vary(req.into_response(response.finish())));
+        }
+
+        let mut res = policy.vary(ctx.call(&self.service, req).await?);
+
+        let headers = res.headers_mut();
+        headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, allow_origin);
+        if let Some(exposed_headers) = &policy.exposed_headers {
+            headers.insert(header::ACCESS_CONTROL_EXPOSE_HEADERS, exposed_headers.clone());
+        }
+        if policy.allow_credentials {
+            headers.insert(header::ACCESS_CONTROL_ALLOW_CREDENTIALS, HeaderValue::from_static("true"));
+        }
+
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ntex::http::StatusCode;
+    use ntex::web::{self, test, App};
+
+    use super::*;
+
+    fn settings(origins: &[&str], allow_credentials: bool) -> Settings {
+        let mut settings = Settings::new().unwrap();
+        settings.server.cors.allowed_origins = origins.iter().map(|origin| origin.to_string()).collect();
+        settings.server.cors.allow_credentials = allow_credentials;
+        settings
+    }
+
+    #[ntex::test]
+    async fn test_credentialed_origins() {
+        let cors_policy = CorsPolicy::with_settings(&settings(&["https://app.sieluna.com", "https://*.sieluna.dev"], true)).unwrap();
+
+        let app = App::new()
+            .wrap(cors_policy)
+            .route("/users", web::patch().to(|| async { HttpResponse::Ok().finish() }));
+        let container = test::init_service(app).await;
+
+        let req = test::TestRequest::with_uri("/users")
+            .method(Method::OPTIONS)
+            .header(header::ORIGIN, "https://home.sieluna.dev")
+            .header(header::ACCESS_CONTROL_REQUEST_METHOD, "PATCH")
+            .header(header::ACCESS_CONTROL_REQUEST_HEADERS, "Authorization, Content-Type")
+            .to_request();
+        let resp = test::call_service(&container, req).await;
+
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+        assert_eq!(resp.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(), "https://home.sieluna.dev");
+        assert_eq!(resp.headers().get(header::ACCESS_CONTROL_ALLOW_CREDENTIALS).unwrap(), "true");
+        assert!(resp.headers().get(header::ACCESS_CONTROL_ALLOW_METHODS).unwrap().to_str().unwrap().contains("PATCH"));
+        assert_eq!(resp.headers().get(header::ACCESS_CONTROL_MAX_AGE).unwrap(), "3600");
+
+        let req = test::TestRequest::with_uri("/users")
+            .method(Method::PATCH)
+            .header(header::ORIGIN, "https://app.sieluna.com")
+            .to_request();
+        let resp = test::call_service(&container, req).await;
+
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(), "https://app.sieluna.com");
+        assert!(resp.headers().get(header::ACCESS_CONTROL_EXPOSE_HEADERS).unwrap().to_str().unwrap().contains("x-request-id"));
+
+        for origin in ["https://sieluna.dev", "https://evil.com", "http://app.sieluna.com", "https://app.sieluna.com.evil.com"] {
+            let req = test::TestRequest::with_uri("/users")
+                .method(Method::PATCH)
+                .header(header::ORIGIN, origin)
+                .to_request();
+            let resp = test::call_service(&container, req).await;
+
+            assert!(resp.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN).is_none(), "{origin} should not be allowed.");
+            assert_eq!(resp.headers().get(header::VARY).unwrap(), "Origin", "Rejections should vary by origin too.");
+        }
+
+        let req = test::TestRequest::with_uri("/users")
+            .method(Method::OPTIONS)
+            .header(header::ORIGIN, "https://evil.com")
+            .header(header::ACCESS_CONTROL_REQUEST_METHOD, "PATCH")
+            .to_request();
+        let resp = test::call_service(&container, req).await;
+
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        assert_eq!(resp.headers().get(header::VARY).unwrap(), "Origin");
+
+        let req = test::TestRequest::with_uri("/users").method(Method::PATCH).to_request();
+        let resp = test::call_service(&container, req).await;
+
+        assert_eq!(resp.headers().get(header::VARY).unwrap(), "Origin", "Requests without an origin should vary too.");
+    }
+
+    #[ntex::test]
+    async fn test_wildcard_does_not_vary() {
+        let cors_policy = CorsPolicy::with_settings(&settings(&["*"], false)).unwrap();
+
+        let app = App::new()
+            .wrap(cors_policy)
+            .route("/users", web::get().to(|| async { HttpResponse::Ok().finish() }));
+        let container = test::init_service(app).await;
+
+        let req = test::TestRequest::with_uri("/users").header(header::ORIGIN, "https://app.sieluna.com").to_request();
+        let resp = test::call_service(&container, req).await;
+
+        assert_eq!(resp.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(), "*");
+        assert!(resp.headers().get(header::VARY).is_none());
+    }
+
+    #[test]
+    fn test_reject_invalid_policies() {
+        assert!(CorsPolicy::with_settings(&settings(&["*"], false)).is_ok());
+        assert!(CorsPolicy::with_settings(&settings(&["*"], true)).is_err(), "Wildcard origins cannot carry credentials.");
+        assert!(CorsPolicy::with_settings(&settings(&["https://app.sieluna.com/"], false)).is_err());
+        assert!(CorsPolicy::with_settings(&settings(&["app.sieluna.com"], false)).is_err());
+
+        let mut settings = settings(&["https://app.sieluna.com"], false);
+        settings.server.cors.allowed_methods.push("NOT A METHOD".into());
+
+        assert!(CorsPolicy::with_settings(&settings).is_err());
+    }
+}
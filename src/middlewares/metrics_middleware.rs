@@ -0,0 +1,165 @@
+use std::time::Instant;
+
+use ntex::{Middleware, Service, ServiceCtx};
+use ntex::web::{Error, ErrorRenderer, HttpRequest, WebRequest, WebResponse};
+
+use crate::services::METRICS;
+
+/// Records request counts and latencies per method, route pattern and status.
+pub struct RequestMetrics;
+
+impl<S> Middleware<S> for RequestMetrics {
+    type Service = RequestMetricsMiddleware<S>;
+
+    fn create(&self, service: S) -> Self::Service {
+        RequestMetricsMiddleware { service }
+    }
+}
+
+pub struct RequestMetricsMiddleware<S> {
+    service: S,
+}
+
+/// Puts the parameter names back into the path, so that ids do not create a series or log key each.
+///
+/// The router captures parameters in path order, so each one is matched against the next whole segment;
+/// a tail parameter (`{tail}*`) is the only one allowed to span several segments. Requests answered before
+/// the router consumed the whole path, by a middleware or the default service, are all `unmatched`.
+pub(super) fn route_pattern(req: &HttpRequest) -> String {
+    if !req.match_info().unprocessed().is_empty() {
+        return "unmatched".into();
+    }
+
+    let mut params = req.match_info().iter().filter(|(_, value)| !value.is_empty()).peekable();
+
+    let mut route = String::new();
+    let mut rest = req.path();
+
+    while !rest.is_empty() {
+        if let Some((name, value)) = params.peek().copied() {
+            if value.contains('/') && rest.ends_with(value) {
+                route.push_str(&rest[..rest.len() - value.len()]);
+                route.push_str(&format!("{{{name}}}"));
+                params.next();
+                break;
+            }
+        }
+
+        let start = usize::from(rest.starts_with('/'));
+        let end = rest[start..].find('/').map_or(rest.len(), |index| start + index);
+        let segment = &rest[start..end];
+
+        route.push_str(&rest[..start]);
+        match params.peek() {
+            Some((name, value)) if *value == segment => {
+                route.push_str(&format!("{{{name}}}"));
+                params.next();
+            }
+            _ => route.push_str(segment),
+        }
+        rest = &rest[end..];
+    }
+
+    route
+}
+
+impl<S, Err> Service<WebRequest<Err>> for RequestMetricsMiddleware<S>
+    where
+        S: Service<WebRequest<Err>, Response = WebResponse, Error = Error> + 'static,
+        Err: ErrorRenderer + 'static,
+{
+    type Response = WebResponse;
+    type Error = Error;
+
+    ntex::forward_ready!(service);
+    ntex::forward_shutdown!(service);
+
+    async fn call(
+        &self,
+        req: WebRequest<Err>,
+        ctx: ServiceCtx<'_, Self>,
+    ) -> Result<Self::Response, Self::Error> {
+        let method = req.method().to_string();
+        let started = Instant::now();
+
+        let result = ctx.call(&self.service, req).await;
+
+        // Errors raised by middlewares never reach a resource, so they have no route to report.
+        let (route, status) = match &result {
+            Ok(res) => (route_pattern(res.request()), res.status()),
+            Err(err) => ("unrouted".to_string(), err.as_response_error().status_code()),
+        };
+        let labels = [method.as_str(), route.as_str(), status.as_str()];
+
+        METRICS.http_requests.with_label_values(&labels).inc();
+        METRICS.http_request_duration.with_label_values(&labels).observe(started.elapsed().as_secs_f64());
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ntex::http::{header, Method};
+    use ntex::web::{self, test, App, HttpResponse};
+    use uuid::Uuid;
+
+    use crate::configs::Settings;
+    use crate::middlewares::CorsPolicy;
+    use super::*;
+
+    async fn echo_route(req: HttpRequest) -> HttpResponse {
+        HttpResponse::Ok().body(route_pattern(&req))
+    }
+
+    #[ntex::test]
+    async fn test_route_pattern() {
+        let app = App::new()
+            .route("/oidc/{provider}/callback", web::get().to(echo_route))
+            .route("/users/{id}/devices/{device}", web::get().to(echo_route))
+            .route("/docs{tail}*", web::get().to(echo_route));
+        let container = test::init_service(app).await;
+
+        let cases = [
+            ("/oidc/callback/callback", "/oidc/{provider}/callback"),
+            ("/users/1/devices/1", "/users/{id}/devices/{device}"),
+            ("/docs/swagger/index.html", "/docs{tail}"),
+            ("/docs", "/docs"),
+        ];
+
+        for (path, pattern) in cases {
+            let req = test::TestRequest::get().uri(path).to_request();
+            let body = test::read_body(test::call_service(&container, req).await).await;
+
+            assert_eq!(body.as_ref(), pattern.as_bytes(), "Route of {path}.");
+        }
+    }
+
+    #[ntex::test]
+    async fn test_preflight_on_unknown_path_is_unmatched() {
+        let mut settings = Settings::new().unwrap();
+        settings.server.cors.allowed_origins = vec!["https://app.sieluna.com".into()];
+        let cors_policy = CorsPolicy::with_settings(&settings).unwrap();
+
+        let app = App::new()
+            .wrap(cors_policy)
+            .wrap(RequestMetrics)
+            .route("/users", web::get().to(echo_route));
+        let container = test::init_service(app).await;
+
+        let counter = |status: &str| METRICS.http_requests.with_label_values(&["OPTIONS", "unmatched", status]).get();
+        let before = (counter("204"), counter("403"));
+
+        for origin in ["https://app.sieluna.com", "https://evil.com"] {
+            let req = test::TestRequest::with_uri(&format!("/{}", Uuid::new_v4()))
+                .method(Method::OPTIONS)
+                .header(header::ORIGIN, origin)
+                .header(header::ACCESS_CONTROL_REQUEST_METHOD, "GET")
+                .to_request();
+            test::call_service(&container, req).await;
+        }
+
+        assert!(counter("204") > before.0, "An allowed preflight should count as unmatched.");
+        assert!(counter("403") > before.1, "A rejected preflight should count as unmatched.");
+    }
+}
@@ -1,3 +1,13 @@
+mod admin_middleware;
 mod auth_middleware;
+mod cors_middleware;
+mod metrics_middleware;
+mod rate_limit_middleware;
+mod request_id_middleware;
 
-pub use auth_middleware::{JWTAuth, JWTAuthMiddleware};
\ No newline at end of file
+pub use admin_middleware::{AdminGuard, AdminGuardMiddleware};
+pub use auth_middleware::{JWTAuth, JWTAuthMiddleware};
+pub use cors_middleware::{CorsPolicy, CorsPolicyMiddleware};
+pub use metrics_middleware::{RequestMetrics, RequestMetricsMiddleware};
+pub use rate_limit_middleware::{RateLimit, RateLimitMiddleware, API_KEY_HEADER};
+pub use request_id_middleware::{RequestTracing, RequestTracingMiddleware, REQUEST_ID_HEADER};
@@ -0,0 +1,172 @@
+use std::sync::Arc;
+
+use ntex::{http, Middleware, Service, ServiceCtx};
+use ntex::http::header::{HeaderName, HeaderValue};
+use ntex::web::{Error, ErrorRenderer, WebRequest, WebResponse};
+use sha2::{Digest, Sha256};
+
+use crate::configs::RateLimitKey;
+use crate::states::RateLimitState;
+
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Applies the rate limit policy matching the request path; requests outside every policy pass through.
+pub struct RateLimit {
+    state: Arc<RateLimitState>,
+}
+
+impl RateLimit {
+    pub fn new(state: &Arc<RateLimitState>) -> Self {
+        Self {
+            state: Arc::clone(state),
+        }
+    }
+}
+
+impl<S> Middleware<S> for RateLimit {
+    type Service = RateLimitMiddleware<S>;
+
+    fn create(&self, service: S) -> Self::Service {
+        RateLimitMiddleware {
+            service,
+            state: self.state.clone(),
+        }
+    }
+}
+
+pub struct RateLimitMiddleware<S> {
+    state: Arc<RateLimitState>,
+    service: S,
+}
+
+impl<S> RateLimitMiddleware<S> {
+    /// Who the bucket belongs to; falls back to the client IP when the request lacks a valid token or API key.
+    fn subject<Err>(&self, req: &WebRequest<Err>, key: RateLimitKey) -> String {
+        let ip = || {
+            let ip = req.peer_addr().map(|addr| addr.ip().to_string());
+
+            format!("ip:{}", ip.as_deref().unwrap_or("unknown"))
+        };
+
+        match key {
+            RateLimitKey::Ip => ip(),
+            RateLimitKey::User => req
+                .headers()
+                .get(http::header::AUTHORIZATION)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.strip_prefix("Bearer "))
+                .and_then(|token| self.state.token_service.retrieve_token_claims(token.trim()).ok())
+                .map(|token_data| format!("user:{}", token_data.claims.sub))
+                .unwrap_or_else(ip),
+            RateLimitKey::ApiKey => req
+                .headers()
+                .get(API_KEY_HEADER)
+                .map(|value| format!("api_key:{:x}", Sha256::digest(value.as_bytes())))
+                .unwrap_or_else(ip),
+        }
+    }
+}
+
+impl<S, Err> Service<WebRequest<Err>> for RateLimitMiddleware<S>
+    where
+        S: Service<WebRequest<Err>, Response = WebResponse, Error = Error> + 'static,
+        Err: ErrorRenderer + 'static,
+{
+    type Response = WebResponse;
+    type Error = Error;
+
+    ntex::forward_ready!(service);
+    ntex::forward_shutdown!(service);
+
+    async fn call(
+        &self,
+        req: WebRequest<Err>,
+        ctx: ServiceCtx<'_, Self>,
+    ) -> Result<Self::Response, Self::Error> {
+        let Some(policy) = self.state.rate_limit_service.policy(req.path()) else {
+            return ctx.call(&self.service, req).await;
+        };
+
+        let subject = self.subject(&req, policy.key);
+        let status = self.state.rate_limit_service.acquire(&policy, &subject).await?;
+
+        let mut res = ctx.call(&self.service, req).await?;
+
+        for (name, value) in status.iter().flat_map(|status| status.headers()) {
+            if let Ok(value) = HeaderValue::from_str(&value) {
+                res.headers_mut().insert(HeaderName::from_static(name), value);
+            }
+        }
+
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ntex::http::StatusCode;
+    use ntex::web::{self, test, App, HttpResponse};
+
+    use crate::configs::{Database, RateLimitPolicy, RateLimitStore, SchemaManager, Settings};
+    use crate::repository::RateLimitRepository;
+    use crate::services::{RateLimitService, TokenService};
+    use super::*;
+
+    #[ntex::test]
+    async fn test_reject_when_bucket_is_empty() {
+        let mut settings = Settings::new().unwrap();
+        settings.rate_limit.enabled = true;
+        settings.rate_limit.store = RateLimitStore::Memory;
+        settings.rate_limit.policies = vec![RateLimitPolicy {
+            scope: "/auth".into(),
+            key: RateLimitKey::ApiKey,
+            capacity: 2,
+            period: 60,
+        }];
+        let settings = Arc::new(settings);
+
+        let database = Arc::new(Database::new(&settings, &SchemaManager::default()).await.unwrap());
+        let rate_limit_repo = Arc::new(RateLimitRepository::new(&database));
+        let state = Arc::new(RateLimitState {
+            rate_limit_service: Arc::new(RateLimitService::with_settings(&settings, &rate_limit_repo).unwrap()),
+            token_service: Arc::new(TokenService::new(&settings)),
+        });
+
+        let app = App::new()
+            .wrap(RateLimit::new(&state))
+            .route("/auth/register", web::post().to(|| async { HttpResponse::Ok().finish() }))
+            .route("/healthz", web::get().to(|| async { HttpResponse::Ok().finish() }));
+        let container = test::init_service(app).await;
+
+        let register = |api_key: &str| {
+            test::TestRequest::post().uri("/auth/register").header(API_KEY_HEADER, api_key).to_request()
+        };
+
+        let resp = test::call_service(&container, register("test_key")).await;
+
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers().get("ratelimit-limit").unwrap(), "2");
+        assert_eq!(resp.headers().get("ratelimit-remaining").unwrap(), "1");
+        assert_eq!(resp.headers().get("ratelimit-policy").unwrap(), "2;w=60");
+
+        test::call_service(&container, register("test_key")).await;
+        let err = container.call(register("test_key")).await.err().expect("Third request should be rejected.");
+
+        assert_eq!(err.as_response_error().status_code(), StatusCode::TOO_MANY_REQUESTS);
+
+        let resp = err.as_response_error().error_response(&test::TestRequest::default().to_http_request());
+
+        assert_eq!(resp.headers().get("retry-after").unwrap(), "30");
+        assert_eq!(resp.headers().get("ratelimit-remaining").unwrap(), "0");
+
+        let resp = test::call_service(&container, register("other_key")).await;
+
+        assert_eq!(resp.status(), StatusCode::OK, "Each API key should have its own bucket.");
+
+        let req = test::TestRequest::get().uri("/healthz").to_request();
+        let resp = test::call_service(&container, req).await;
+
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(resp.headers().get("ratelimit-limit").is_none(), "Paths outside every policy are not limited.");
+    }
+}
@@ -0,0 +1,177 @@
+use std::time::Instant;
+
+use ntex::http::header::{HeaderName, HeaderValue};
+use ntex::http::HeaderMap;
+use opentelemetry::global;
+use opentelemetry::propagation::Extractor;
+use ntex::{Middleware, Service, ServiceCtx};
+use ntex::web::{Error, ErrorRenderer, WebRequest, WebResponse};
+use tracing::field::Empty;
+use tracing::Instrument;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use uuid::Uuid;
+
+use crate::payload::UserDto;
+use super::metrics_middleware::route_pattern;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(HeaderName::as_str).collect()
+    }
+}
+
+/// Assigns every request an `X-Request-Id`, unless a well-formed one was propagated by the caller,
+/// and logs it with the outcome of the request inside a span.
+///
+/// The span continues the W3C trace context of the caller when a `traceparent` header is present.
+pub struct RequestTracing;
+
+impl<S> Middleware<S> for RequestTracing {
+    type Service = RequestTracingMiddleware<S>;
+
+    fn create(&self, service: S) -> Self::Service {
+        RequestTracingMiddleware { service }
+    }
+}
+
+pub struct RequestTracingMiddleware<S> {
+    service: S,
+}
+
+impl<S> RequestTracingMiddleware<S> {
+    fn accepts(value: &HeaderValue) -> bool {
+        let bytes = value.as_bytes();
+
+        !bytes.is_empty() && bytes.len() <= 128 && bytes.iter().all(|byte| byte.is_ascii_graphic())
+    }
+}
+
+impl<S, Err> Service<WebRequest<Err>> for RequestTracingMiddleware<S>
+    where
+        S: Service<WebRequest<Err>, Response = WebResponse, Error = Error> + 'static,
+        Err: ErrorRenderer + 'static,
+{
+    type Response = WebResponse;
+    type Error = Error;
+
+    ntex::forward_ready!(service);
+    ntex::forward_shutdown!(service);
+
+    async fn call(
+        &self,
+        mut req: WebRequest<Err>,
+        ctx: ServiceCtx<'_, Self>,
+    ) -> Result<Self::Response, Self::Error> {
+        let request_id = match req.headers().get(REQUEST_ID_HEADER).filter(|value| Self::accepts(value)) {
+            Some(value) => value.clone(),
+            None => {
+                let value = HeaderValue::from_str(&Uuid::new_v4().to_string()).unwrap();
+                req.headers_mut().insert(HeaderName::from_static(REQUEST_ID_HEADER), value.clone());
+                value
+            }
+        };
+
+        let span = tracing::info_span!(
+            "request",
+            request_id = request_id.to_str().unwrap_or_default(),
+            method = %req.method(),
+            route = Empty,
+            status = Empty,
+            latency_ms = Empty,
+            user_id = Empty,
+        );
+        span.set_parent(global::get_text_map_propagator(|propagator| {
+            propagator.extract(&HeaderExtractor(req.headers()))
+        }));
+        let started = Instant::now();
+        let http_req = req.http_request().clone();
+
+        let result = ctx.call(&self.service, req).instrument(span.clone()).await;
+
+        let latency_ms = started.elapsed().as_secs_f64() * 1000.0;
+        span.record("latency_ms", latency_ms);
+
+        match result {
+            Ok(mut res) => {
+                span.record("route", route_pattern(res.request()).as_str());
+                span.record("status", res.status().as_u16());
+                if let Some(user) = res.request().extensions().get::<UserDto>() {
+                    span.record("user_id", user.id);
+                }
+
+                res.headers_mut().insert(HeaderName::from_static(REQUEST_ID_HEADER), request_id);
+                span.in_scope(|| tracing::info!("request completed"));
+
+                Ok(res)
+            }
+            Err(err) => {
+                // Rendered here so error responses carry the request id too.
+                let mut res = WebResponse::new(err.as_response_error().error_response(&http_req), http_req);
+
+                span.record("route", "unrouted");
+                span.record("status", res.status().as_u16());
+                span.in_scope(|| tracing::info!("request rejected"));
+
+                res.headers_mut().insert(HeaderName::from_static(REQUEST_ID_HEADER), request_id);
+
+                Ok(res)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ntex::http::StatusCode;
+    use ntex::web::{self, test, App, HttpResponse};
+
+    use crate::middlewares::AdminGuard;
+    use super::*;
+
+    #[ntex::test]
+    async fn test_assign_and_propagate_request_id() {
+        let app = App::new()
+            .wrap(RequestTracing)
+            .route("/ping", web::get().to(|| async { HttpResponse::Ok().finish() }));
+        let container = test::init_service(app).await;
+
+        let req = test::TestRequest::get().uri("/ping").to_request();
+        let resp = test::call_service(&container, req).await;
+
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(Uuid::parse_str(resp.headers().get(REQUEST_ID_HEADER).unwrap().to_str().unwrap()).is_ok());
+
+        let req = test::TestRequest::get().uri("/ping").header("x-request-id", "test-request-id").to_request();
+        let resp = test::call_service(&container, req).await;
+
+        assert_eq!(resp.headers().get(REQUEST_ID_HEADER).unwrap(), "test-request-id");
+
+        let req = test::TestRequest::get().uri("/ping").header("x-request-id", "not a valid id").to_request();
+        let resp = test::call_service(&container, req).await;
+
+        assert_ne!(resp.headers().get(REQUEST_ID_HEADER).unwrap(), "not a valid id");
+    }
+
+    #[ntex::test]
+    async fn test_request_id_on_error_response() {
+        let app = App::new()
+            .wrap(AdminGuard)
+            .wrap(RequestTracing)
+            .route("/admin", web::get().to(|| async { HttpResponse::Ok().finish() }));
+        let container = test::init_service(app).await;
+
+        let req = test::TestRequest::get().uri("/admin").header("x-request-id", "test-error-id").to_request();
+        let resp = test::call_service(&container, req).await;
+
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        assert_eq!(resp.headers().get(REQUEST_ID_HEADER).unwrap(), "test-error-id");
+    }
+}
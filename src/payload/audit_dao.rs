@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AuditEventCreateDao {
+    pub created_at: i64,
+    pub action: String,
+    pub outcome: String,
+    pub actor_id: Option<i32>,
+    pub actor: Option<String>,
+    pub target: Option<String>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub diff: Option<String>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct AuditFilterDao {
+    pub actor_id: Option<i32>,
+    pub action: Option<String>,
+    pub target: Option<String>,
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+    pub limit: i64,
+    pub offset: i64,
+}
@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::entities::AuditEvent;
+
+/// Who performed an action and from where, taken from the request.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct AuditContextDto {
+    pub actor_id: Option<i32>,
+    pub actor: Option<String>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AuditRecordDto {
+    pub action: String,
+    pub success: bool,
+    pub target: Option<String>,
+    pub diff: Option<Value>,
+}
+
+#[derive(Clone, Copy, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditExportFormat {
+    #[default]
+    Json,
+    Ndjson,
+    Csv,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuditQueryDto {
+    pub actor_id: Option<i32>,
+    pub action: Option<String>,
+    pub target: Option<String>,
+    /// Unix timestamp in seconds, inclusive.
+    pub since: Option<i64>,
+    /// Unix timestamp in seconds, exclusive.
+    pub until: Option<i64>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+    pub format: Option<AuditExportFormat>,
+}
+
+#[derive(Clone, Serialize, Deserialize, ToSchema)]
+pub struct AuditEventDto {
+    pub id: i64,
+    pub created_at: i64,
+    pub action: String,
+    pub outcome: String,
+    pub actor_id: Option<i32>,
+    pub actor: Option<String>,
+    pub target: Option<String>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub diff: Option<Value>,
+}
+
+impl From<AuditEvent> for AuditEventDto {
+    fn from(value: AuditEvent) -> Self {
+        Self {
+            id: value.id,
+            created_at: value.created_at,
+            action: value.action,
+            outcome: value.outcome,
+            actor_id: value.actor_id,
+            actor: value.actor,
+            target: value.target,
+            ip: value.ip,
+            user_agent: value.user_agent,
+            diff: value.diff.and_then(|diff| serde_json::from_str(&diff).ok()),
+        }
+    }
+}
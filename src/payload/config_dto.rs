@@ -0,0 +1,24 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Clone, Default, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ConfigQueryDto {
+    /// Include every key, not only those overriding the defaults.
+    #[serde(default)]
+    pub effective: bool,
+    /// Include the layer that supplied each key.
+    #[serde(default)]
+    pub origin: bool,
+}
+
+/// Merged settings keyed by dotted path, with secrets redacted.
+#[derive(Clone, Serialize, Deserialize, ToSchema)]
+pub struct ConfigDto {
+    pub settings: BTreeMap<String, Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub origins: Option<BTreeMap<String, String>>,
+}
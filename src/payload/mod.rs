@@ -1,7 +1,27 @@
+mod audit_dao;
+mod audit_dto;
+mod config_dto;
+mod health_dto;
+mod identity_dao;
+mod oauth_dao;
+mod oauth_dto;
+mod oidc_dto;
+mod problem_dto;
+mod rate_limit_dto;
 mod token_dto;
 mod user_dao;
 mod user_dto;
 
+pub use audit_dao::*;
+pub use audit_dto::*;
+pub use config_dto::*;
+pub use health_dto::*;
+pub use identity_dao::*;
+pub use oauth_dao::*;
+pub use oauth_dto::*;
+pub use oidc_dto::*;
+pub use problem_dto::*;
+pub use rate_limit_dto::*;
 pub use token_dto::*;
 pub use user_dao::*;
 pub use user_dto::*;
@@ -0,0 +1,116 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use validator::{Validate, ValidateUrl, ValidationError};
+
+fn validate_redirect_uris(redirect_uris: &[String]) -> Result<(), ValidationError> {
+    if redirect_uris.iter().all(|uri| uri.validate_url()) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("url"))
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Validate, ToSchema)]
+pub struct OAuthClientCreateDto {
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+    #[validate(custom(function = "validate_redirect_uris"))]
+    pub redirect_uris: Vec<String>,
+    pub scopes: Vec<String>,
+    pub grant_types: Vec<String>,
+    pub confidential: bool,
+}
+
+#[derive(Clone, Serialize, Deserialize, ToSchema)]
+pub struct OAuthClientCredentialsDto {
+    pub client_id: String,
+    pub client_secret: Option<String>,
+    pub name: String,
+    pub redirect_uris: Vec<String>,
+    pub scopes: Vec<String>,
+    pub grant_types: Vec<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize, IntoParams, ToSchema)]
+#[into_params(parameter_in = Query)]
+pub struct OAuthAuthorizeDto {
+    pub response_type: String,
+    pub client_id: String,
+    pub redirect_uri: Option<String>,
+    pub scope: Option<String>,
+    pub state: Option<String>,
+    pub code_challenge: String,
+    pub code_challenge_method: Option<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize, ToSchema)]
+pub struct OAuthDecisionDto {
+    #[serde(flatten)]
+    pub request: OAuthAuthorizeDto,
+    pub approve: bool,
+}
+
+#[derive(Clone, Serialize, Deserialize, ToSchema)]
+pub struct OAuthConsentDto {
+    pub client_id: String,
+    pub client_name: String,
+    pub redirect_uri: String,
+    pub scopes: Vec<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize, ToSchema)]
+pub struct OAuthRedirectDto {
+    pub redirect_uri: String,
+}
+
+#[derive(Clone, Serialize, Deserialize, ToSchema)]
+pub struct OAuthTokenRequestDto {
+    pub grant_type: String,
+    pub code: Option<String>,
+    pub redirect_uri: Option<String>,
+    pub code_verifier: Option<String>,
+    pub scope: Option<String>,
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize, ToSchema)]
+pub struct OAuthTokenDto {
+    pub access_token: String,
+    pub token_type: String,
+    pub expires_in: u64,
+    pub scope: String,
+}
+
+#[derive(Clone, Serialize, Deserialize, ToSchema)]
+pub struct OAuthIntrospectRequestDto {
+    pub token: String,
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct OAuthIntrospectionDto {
+    pub active: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sub: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iat: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exp: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_type: Option<String>,
+}
+
+/// Error body of the OAuth endpoints, as required by RFC 6749 rather than problem details.
+#[derive(Clone, Serialize, Deserialize, ToSchema)]
+pub struct OAuthErrorDto {
+    pub error: String,
+    pub error_description: String,
+}
@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+
+/// Quota left for one client under one policy, sent back as `RateLimit-*` headers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateLimitStatusDto {
+    pub limit: u32,
+    pub period: u64,
+    pub remaining: u32,
+    /// Seconds until the bucket is full again.
+    pub reset: u64,
+    /// Seconds until the next request is allowed, only set when rejected.
+    pub retry_after: Option<u64>,
+}
+
+impl RateLimitStatusDto {
+    pub fn headers(&self) -> Vec<(&'static str, String)> {
+        let mut headers = vec![
+            ("ratelimit-limit", self.limit.to_string()),
+            ("ratelimit-remaining", self.remaining.to_string()),
+            ("ratelimit-reset", self.reset.to_string()),
+            ("ratelimit-policy", format!("{};w={}", self.limit, self.period)),
+        ];
+
+        if let Some(retry_after) = self.retry_after {
+            headers.push(("retry-after", retry_after.to_string()));
+        }
+
+        headers
+    }
+}
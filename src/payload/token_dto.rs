@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct TokenDto {
     pub token: String,
     pub iat: u64,
@@ -10,8 +11,17 @@ pub struct TokenDto {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TokenClaimsDto {
     pub sub: String,
+    /// Tells session tokens apart from OAuth access tokens signed with the same key.
+    #[serde(default)]
+    pub aud: String,
+    #[serde(default)]
     pub username: String,
+    #[serde(default)]
     pub email: String,
     pub iat: u64,
     pub exp: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
 }
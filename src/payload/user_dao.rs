@@ -14,3 +14,11 @@ pub struct UserUpdateDao {
     pub email: Option<String>,
     pub password: Option<String>,
 }
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct UserImportDao {
+    pub username: String,
+    pub email: String,
+    pub password_hash: String,
+    pub role: String,
+}
@@ -1,8 +1,38 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::{Validate, ValidateLength, ValidationError, ValidationErrors, ValidationErrorsKind};
+
+use crate::entities::{User, ADMIN_ROLE};
+
+fn validate_username(username: &str) -> Result<(), ValidationError> {
+    let allowed = |c: char| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.');
+
+    if username.chars().all(allowed) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("username_charset")
+            .with_message("may only contain letters, digits, '_', '-' and '.'".into()))
+    }
+}
 
-use crate::entities::User;
+/// Only bounds the identity, since accounts imported or created before the username rules may not follow them.
+fn validate_identity(identity: &UserIdentity) -> Result<(), ValidationError> {
+    match identity {
+        UserIdentity::Id(_) => Ok(()),
+        UserIdentity::Username(value) | UserIdentity::Email(value) if value.validate_length(Some(1), Some(255), None) => {
+            Ok(())
+        }
+        UserIdentity::Username(_) | UserIdentity::Email(_) => {
+            Err(ValidationError::new("length").with_message("must be between 1 and 255 characters long".into()))
+        }
+    }
+}
 
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum UserIdentity {
     Id(i32),
@@ -10,31 +40,120 @@ pub enum UserIdentity {
     Email(String),
 }
 
-#[derive(Clone, Serialize, Deserialize)]
+impl fmt::Display for UserIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UserIdentity::Id(id) => write!(f, "id:{id}"),
+            UserIdentity::Username(username) => write!(f, "username:{username}"),
+            UserIdentity::Email(email) => write!(f, "email:{email}"),
+        }
+    }
+}
+
+/// Parses what `Display` writes; a bare value is an email when it contains `@` and a username otherwise.
+impl FromStr for UserIdentity {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.split_once(':') {
+            Some(("id", id)) => id.parse().map(UserIdentity::Id).map_err(|_| format!("'{id}' is not a user id")),
+            Some(("username", username)) => Ok(UserIdentity::Username(username.to_string())),
+            Some(("email", email)) => Ok(UserIdentity::Email(email.to_string())),
+            _ if value.contains('@') => Ok(UserIdentity::Email(value.to_string())),
+            _ => Ok(UserIdentity::Username(value.to_string())),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Validate, ToSchema)]
 pub struct UserAuthDto {
+    #[validate(custom(function = "validate_identity"))]
     pub identity: UserIdentity,
+    #[validate(length(min = 1, max = 1024))]
     pub password: String,
 }
 
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize, Validate, ToSchema)]
 pub struct UserCreateDto {
+    #[validate(length(min = 3, max = 32), custom(function = "validate_username"))]
     pub username: String,
+    #[validate(email, length(max = 255))]
     pub email: String,
+    #[validate(length(min = 1, max = 1024))]
     pub password: String,
 }
 
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize, Validate, ToSchema)]
 pub struct UserUpdateDto {
+    #[validate(length(min = 3, max = 32), custom(function = "validate_username"))]
     pub username: Option<String>,
+    #[validate(email, length(max = 255))]
     pub email: Option<String>,
+    #[validate(length(min = 1, max = 1024))]
     pub password: Option<String>,
 }
 
-#[derive(Clone, Deserialize, Serialize)]
+#[derive(Clone, Serialize, Deserialize, Validate, ToSchema)]
+pub struct UserImportDto {
+    #[validate(length(min = 3, max = 32), custom(function = "validate_username"))]
+    pub username: String,
+    #[validate(email, length(max = 255))]
+    pub email: String,
+    #[validate(length(min = 1, max = 1024))]
+    pub password_hash: String,
+    pub role: Option<String>,
+}
+
+/// The JSON array of users to import, validated entry by entry.
+#[derive(Clone, Deserialize)]
+#[serde(transparent)]
+pub struct UserImportBatchDto(pub Vec<UserImportDto>);
+
+impl Validate for UserImportBatchDto {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let failed: BTreeMap<_, _> = self
+            .0
+            .iter()
+            .enumerate()
+            .filter_map(|(index, user)| user.validate().err().map(|errors| (index, Box::new(errors))))
+            .collect();
+
+        if failed.is_empty() {
+            return Ok(());
+        }
+
+        // The body is the array itself, so fields are reported as `[index].field`.
+        let mut errors = ValidationErrors::new();
+        errors.errors_mut().insert("".into(), ValidationErrorsKind::List(failed));
+
+        Err(errors)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, ToSchema)]
+pub struct UserImportRejectionDto {
+    pub username: String,
+    pub reason: String,
+}
+
+#[derive(Clone, Serialize, Deserialize, ToSchema)]
+pub struct UserImportReportDto {
+    pub imported: Vec<UserDto>,
+    pub rejected: Vec<UserImportRejectionDto>,
+}
+
+#[derive(Clone, Deserialize, Serialize, ToSchema)]
 pub struct UserDto {
     pub id: i32,
     pub username: String,
     pub email: String,
+    pub role: String,
+}
+
+impl UserDto {
+    pub fn is_admin(&self) -> bool {
+        self.role == ADMIN_ROLE
+    }
 }
 
 impl From<User> for UserDto {
@@ -43,6 +162,7 @@ impl From<User> for UserDto {
             id: value.id,
             username: value.username,
             email: value.email,
+            role: value.role,
         }
     }
 }
@@ -0,0 +1,101 @@
+use std::sync::Arc;
+
+use crate::configs::Database;
+use crate::entities::AuditEvent;
+use crate::errors::{ApiError, DatabaseError};
+use crate::payload::{AuditEventCreateDao, AuditFilterDao};
+use crate::sql;
+
+/// Only appends and reads; audit events are never updated or removed through the application.
+#[derive(Clone)]
+pub struct AuditRepository {
+    pub database: Arc<Database>,
+}
+
+impl AuditRepository {
+    pub fn new(db_conn: &Arc<Database>) -> Self {
+        Self {
+            database: Arc::clone(db_conn),
+        }
+    }
+
+    #[tracing::instrument(skip_all, fields(db.table = "audit_events"))]
+    pub async fn add<T: Into<AuditEventCreateDao>>(&self, data: T) -> Result<(), ApiError> {
+        let AuditEventCreateDao { created_at, action, outcome, actor_id, actor, target, ip, user_agent, diff } = data.into();
+
+        let statement = sql!(
+            self.database.scheme,
+            "INSERT INTO audit_events (created_at, action, outcome, actor_id, actor, target, ip, user_agent, diff) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)"
+        );
+
+        let query = sqlx::query(&statement)
+            .bind(created_at)
+            .bind(&action)
+            .bind(&outcome)
+            .bind(actor_id)
+            .bind(&actor)
+            .bind(&target)
+            .bind(&ip)
+            .bind(&user_agent)
+            .bind(&diff);
+
+        query.execute(&self.database.pool).await.map_err(DatabaseError::from)?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all, fields(db.table = "audit_events"))]
+    pub async fn list(&self, filter: AuditFilterDao) -> Result<Vec<AuditEvent>, ApiError> {
+        let mut conditions = Vec::new();
+
+        let filters = [
+            (filter.actor_id.is_some(), "actor_id = "),
+            (filter.action.is_some(), "action = "),
+            (filter.target.is_some(), "target = "),
+            (filter.since.is_some(), "created_at >= "),
+            (filter.until.is_some(), "created_at < "),
+        ];
+        for (_, condition) in filters.iter().filter(|(enabled, _)| *enabled) {
+            conditions.push(format!("{condition}${}", conditions.len() + 1));
+        }
+
+        let where_clause = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!(" WHERE {}", conditions.join(" AND "))
+        };
+        let statement = format!(
+            "SELECT * FROM audit_events{where_clause} ORDER BY id DESC LIMIT ${} OFFSET ${}",
+            conditions.len() + 1,
+            conditions.len() + 2
+        );
+        let statement = sql!(self.database.scheme, statement);
+
+        let mut query = sqlx::query_as::<_, AuditEvent>(&statement);
+        if let Some(actor_id) = filter.actor_id {
+            query = query.bind(actor_id);
+        }
+        if let Some(action) = filter.action {
+            query = query.bind(action);
+        }
+        if let Some(target) = filter.target {
+            query = query.bind(target);
+        }
+        if let Some(since) = filter.since {
+            query = query.bind(since);
+        }
+        if let Some(until) = filter.until {
+            query = query.bind(until);
+        }
+
+        let events = query
+            .bind(filter.limit)
+            .bind(filter.offset)
+            .fetch_all(&self.database.pool)
+            .await
+            .map_err(DatabaseError::from)?;
+
+        Ok(events)
+    }
+}
@@ -0,0 +1,52 @@
+use std::sync::Arc;
+
+use crate::configs::Database;
+use crate::entities::OAuthClient;
+use crate::errors::{ApiError, DatabaseError, OAuthError};
+use crate::payload::OAuthClientCreateDao;
+use crate::sql;
+
+#[derive(Clone)]
+pub struct ClientRepository {
+    pub database: Arc<Database>,
+}
+
+impl ClientRepository {
+    pub fn new(db_conn: &Arc<Database>) -> Self {
+        Self {
+            database: Arc::clone(db_conn),
+        }
+    }
+
+    #[tracing::instrument(skip_all, fields(db.table = "oauth_clients"))]
+    pub async fn find_by_client_id(&self, client_id: &str) -> Option<OAuthClient> {
+        let statement = sql!(self.database.scheme, "SELECT * FROM oauth_clients WHERE client_id = $1");
+
+        let query = sqlx::query_as::<_, OAuthClient>(&statement).bind(client_id);
+
+        query.fetch_optional(&self.database.pool).await.unwrap_or(None)
+    }
+
+    #[tracing::instrument(skip_all, fields(db.table = "oauth_clients"))]
+    pub async fn add<T: Into<OAuthClientCreateDao>>(&self, data: T) -> Result<OAuthClient, ApiError> {
+        let OAuthClientCreateDao { client_id, client_secret, name, redirect_uris, scopes, grant_types } = data.into();
+
+        let statement = sql!(
+            self.database.scheme,
+            "INSERT INTO oauth_clients (client_id, client_secret, name, redirect_uris, scopes, grant_types) \
+             VALUES ($1, $2, $3, $4, $5, $6)"
+        );
+
+        let query = sqlx::query(&statement)
+            .bind(&client_id)
+            .bind(&client_secret)
+            .bind(&name)
+            .bind(&redirect_uris)
+            .bind(&scopes)
+            .bind(&grant_types);
+
+        query.execute(&self.database.pool).await.map_err(DatabaseError::from)?;
+
+        self.find_by_client_id(&client_id).await.ok_or(OAuthError::InvalidClient.into())
+    }
+}
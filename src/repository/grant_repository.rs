@@ -0,0 +1,73 @@
+use std::sync::Arc;
+
+use crate::configs::Database;
+use crate::entities::AuthorizationGrant;
+use crate::errors::{ApiError, DatabaseError};
+use crate::sql;
+
+#[derive(Clone)]
+pub struct GrantRepository {
+    pub database: Arc<Database>,
+}
+
+impl GrantRepository {
+    pub fn new(db_conn: &Arc<Database>) -> Self {
+        Self {
+            database: Arc::clone(db_conn),
+        }
+    }
+
+    /// Stores a grant and purges the ones created before `expired_before`.
+    #[tracing::instrument(skip_all, fields(db.table = "oauth_grants"))]
+    pub async fn add(&self, grant: &AuthorizationGrant, expired_before: i64) -> Result<(), ApiError> {
+        let statement = sql!(self.database.scheme, "DELETE FROM oauth_grants WHERE created_at < $1");
+
+        sqlx::query(&statement)
+            .bind(expired_before)
+            .execute(&self.database.pool)
+            .await
+            .map_err(DatabaseError::from)?;
+
+        let statement = sql!(
+            self.database.scheme,
+            "INSERT INTO oauth_grants (code_hash, client_id, user_id, redirect_uri, scope, code_challenge, created_at) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7)"
+        );
+
+        sqlx::query(&statement)
+            .bind(&grant.code_hash)
+            .bind(&grant.client_id)
+            .bind(grant.user_id)
+            .bind(&grant.redirect_uri)
+            .bind(&grant.scope)
+            .bind(&grant.code_challenge)
+            .bind(grant.created_at)
+            .execute(&self.database.pool)
+            .await
+            .map_err(DatabaseError::from)?;
+
+        Ok(())
+    }
+
+    /// Removes and returns a grant. Of concurrent callers only the one whose delete succeeds gets it.
+    #[tracing::instrument(skip_all, fields(db.table = "oauth_grants"))]
+    pub async fn take(&self, code_hash: &str) -> Result<Option<AuthorizationGrant>, ApiError> {
+        let statement = sql!(self.database.scheme, "SELECT * FROM oauth_grants WHERE code_hash = $1");
+
+        let grant = sqlx::query_as::<_, AuthorizationGrant>(&statement)
+            .bind(code_hash)
+            .fetch_optional(&self.database.pool)
+            .await
+            .map_err(DatabaseError::from)?;
+
+        let statement = sql!(self.database.scheme, "DELETE FROM oauth_grants WHERE code_hash = $1");
+
+        let deleted = sqlx::query(&statement)
+            .bind(code_hash)
+            .execute(&self.database.pool)
+            .await
+            .map_err(DatabaseError::from)?;
+
+        Ok(grant.filter(|_| deleted.rows_affected() == 1))
+    }
+}
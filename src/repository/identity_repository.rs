@@ -0,0 +1,48 @@
+use std::sync::Arc;
+
+use crate::configs::Database;
+use crate::entities::ExternalIdentity;
+use crate::errors::{ApiError, DatabaseError, UserError};
+use crate::payload::ExternalIdentityCreateDao;
+use crate::sql;
+
+#[derive(Clone)]
+pub struct IdentityRepository {
+    pub database: Arc<Database>,
+}
+
+impl IdentityRepository {
+    pub fn new(db_conn: &Arc<Database>) -> Self {
+        Self {
+            database: Arc::clone(db_conn),
+        }
+    }
+
+    #[tracing::instrument(skip_all, fields(db.table = "user_identities"))]
+    pub async fn find(&self, provider: &str, subject: &str) -> Option<ExternalIdentity> {
+        let statement = sql!(
+            self.database.scheme,
+            "SELECT * FROM user_identities WHERE provider = $1 AND subject = $2"
+        );
+
+        let query = sqlx::query_as::<_, ExternalIdentity>(&statement).bind(provider).bind(subject);
+
+        query.fetch_optional(&self.database.pool).await.unwrap_or(None)
+    }
+
+    #[tracing::instrument(skip_all, fields(db.table = "user_identities"))]
+    pub async fn add<T: Into<ExternalIdentityCreateDao>>(&self, data: T) -> Result<ExternalIdentity, ApiError> {
+        let ExternalIdentityCreateDao { user_id, provider, subject, email } = data.into();
+
+        let statement = sql!(
+            self.database.scheme,
+            "INSERT INTO user_identities (user_id, provider, subject, email) VALUES ($1, $2, $3, $4)"
+        );
+
+        let query = sqlx::query(&statement).bind(user_id).bind(&provider).bind(&subject).bind(&email);
+
+        query.execute(&self.database.pool).await.map_err(DatabaseError::from)?;
+
+        self.find(&provider, &subject).await.ok_or(UserError::UserNotFound.into())
+    }
+}
@@ -1,3 +1,13 @@
+pub mod audit_repository;
+pub mod client_repository;
+pub mod grant_repository;
+pub mod identity_repository;
+pub mod rate_limit_repository;
 pub mod user_repository;
 
+pub use audit_repository::AuditRepository;
+pub use client_repository::ClientRepository;
+pub use grant_repository::GrantRepository;
+pub use identity_repository::IdentityRepository;
+pub use rate_limit_repository::RateLimitRepository;
 pub use user_repository::UserRepository;
@@ -0,0 +1,63 @@
+use std::sync::Arc;
+
+use crate::configs::{Database, DatabaseScheme};
+use crate::entities::RateLimitBucket;
+use crate::errors::{ApiError, DatabaseError};
+use crate::sql;
+
+#[derive(Clone)]
+pub struct RateLimitRepository {
+    pub database: Arc<Database>,
+}
+
+impl RateLimitRepository {
+    pub fn new(db_conn: &Arc<Database>) -> Self {
+        Self {
+            database: Arc::clone(db_conn),
+        }
+    }
+
+    /// Reads, changes and writes back a bucket in one transaction, so instances sharing the database agree on it.
+    #[tracing::instrument(skip_all, fields(db.table = "rate_limit_buckets"))]
+    pub async fn update<F>(&self, bucket_key: &str, apply: F) -> Result<RateLimitBucket, ApiError>
+        where
+            F: FnOnce(Option<RateLimitBucket>) -> RateLimitBucket,
+    {
+        let mut tx = self.database.pool.begin().await.map_err(DatabaseError::from)?;
+
+        let lock = match self.database.scheme {
+            DatabaseScheme::SQLITE => "",
+            _ => " FOR UPDATE",
+        };
+        let statement = format!("SELECT * FROM rate_limit_buckets WHERE bucket_key = $1{lock}");
+        let statement = sql!(self.database.scheme, statement);
+
+        let current = sqlx::query_as::<_, RateLimitBucket>(&statement)
+            .bind(bucket_key)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(DatabaseError::from)?;
+
+        let bucket = apply(current);
+
+        // A missing row cannot be locked, so concurrent first requests both insert; the later write wins.
+        let upsert = match self.database.scheme {
+            DatabaseScheme::MYSQL => "ON DUPLICATE KEY UPDATE tokens = VALUES(tokens), updated_at = VALUES(updated_at)",
+            _ => "ON CONFLICT (bucket_key) DO UPDATE SET tokens = excluded.tokens, updated_at = excluded.updated_at",
+        };
+        let statement = format!("INSERT INTO rate_limit_buckets (tokens, updated_at, bucket_key) VALUES ($1, $2, $3) {upsert}");
+        let statement = sql!(self.database.scheme, statement);
+
+        sqlx::query(&statement)
+            .bind(bucket.tokens)
+            .bind(bucket.updated_at)
+            .bind(&bucket.bucket_key)
+            .execute(&mut *tx)
+            .await
+            .map_err(DatabaseError::from)?;
+
+        tx.commit().await.map_err(DatabaseError::from)?;
+
+        Ok(bucket)
+    }
+}
@@ -1,25 +1,28 @@
 use std::sync::Arc;
 
-use crate::configs::{Database, Password};
+use crate::configs::{Database, Password, PasswordChecker};
 use crate::entities::User;
-use crate::errors::{ApiError, DatabaseError, UserError};
-use crate::payload::{UserCreateDao, UserUpdateDao};
+use crate::errors::{ApiError, AuthError, DatabaseError, UserError};
+use crate::payload::{UserCreateDao, UserImportDao, UserUpdateDao};
 use crate::sql;
 
 #[derive(Clone)]
 pub struct UserRepository {
     pub database: Arc<Database>,
     pub password: Arc<dyn Password>,
+    pub policy: Arc<PasswordChecker>,
 }
 
 impl UserRepository {
-    pub fn new(password: &Arc<dyn Password>, db_conn: &Arc<Database>) -> Self {
+    pub fn new(password: &Arc<dyn Password>, policy: &Arc<PasswordChecker>, db_conn: &Arc<Database>) -> Self {
         Self {
             database: Arc::clone(db_conn),
             password: Arc::clone(password),
+            policy: Arc::clone(policy),
         }
     }
 
+    #[tracing::instrument(skip_all, fields(db.table = "users"))]
     pub async fn find_by_email(&self, email: &str) -> Option<User> {
         let statement = sql!(self.database.scheme, "SELECT * FROM users WHERE email = $1");
 
@@ -28,6 +31,7 @@ impl UserRepository {
         query.fetch_optional(&self.database.pool).await.unwrap_or(None)
     }
 
+    #[tracing::instrument(skip_all, fields(db.table = "users"))]
     pub async fn find_by_username(&self, username: &str) -> Option<User> {
         let statement = sql!(self.database.scheme, "SELECT * FROM users WHERE username = $1");
 
@@ -36,6 +40,7 @@ impl UserRepository {
         query.fetch_optional(&self.database.pool).await.unwrap_or(None)
     }
 
+    #[tracing::instrument(skip_all, fields(db.table = "users"))]
     pub async fn find(&self, id: i32) -> Option<User> {
         let statement = sql!(self.database.scheme, "SELECT * FROM users WHERE id = $1");
 
@@ -44,9 +49,12 @@ impl UserRepository {
         query.fetch_optional(&self.database.pool).await.unwrap_or(None)
     }
 
+    #[tracing::instrument(skip_all, fields(db.table = "users"))]
     pub async fn add<T: Into<UserCreateDao>>(&self, data: T) -> Result<User, ApiError> {
         let UserCreateDao { username, email, password } = data.into();
 
+        self.policy.check(&password, &username, &email)?;
+
         let user_password = self.password.hash(&password)?;
 
         let statement = sql!(self.database.scheme, "INSERT INTO users (username, email, password) VALUES ($1, $2, $3)");
@@ -60,9 +68,40 @@ impl UserRepository {
         }
     }
 
+    #[tracing::instrument(skip_all, fields(db.table = "users"))]
+    pub async fn import<T: Into<UserImportDao>>(&self, data: T) -> Result<User, ApiError> {
+        let UserImportDao { username, email, password_hash, role } = data.into();
+
+        if !self.password.supports(&password_hash) {
+            Err(AuthError::UnsupportedPasswordHash)?
+        }
+
+        let statement = sql!(
+            self.database.scheme,
+            "INSERT INTO users (username, email, password, role) VALUES ($1, $2, $3, $4)"
+        );
+
+        let query = sqlx::query(&statement).bind(&username).bind(&email).bind(&password_hash).bind(&role);
+
+        if query.execute(&self.database.pool).await.map_err(DatabaseError::from)?.rows_affected() > 0 {
+            self.find_by_email(&email).await.ok_or(UserError::UserNotFound.into())
+        } else {
+            Err(UserError::UserCreateFail.into())
+        }
+    }
+
+    #[tracing::instrument(skip_all, fields(db.table = "users"))]
     pub async fn update<T: Into<UserUpdateDao>>(&self, data: T) -> Result<User, ApiError> {
         let UserUpdateDao { id, username, email, password } = data.into();
 
+        if let Some(password_value) = &password {
+            let current = self.find(id).await.ok_or(UserError::UserNotFound)?;
+            let check_username = username.as_deref().unwrap_or(&current.username);
+            let check_email = email.as_deref().unwrap_or(&current.email);
+
+            self.policy.check(password_value, check_username, check_email)?;
+        }
+
         let mut updates = Vec::new();
         let mut bindings = Vec::new();
 
@@ -100,6 +139,29 @@ impl UserRepository {
         }
     }
 
+    #[tracing::instrument(skip_all, fields(db.table = "users"))]
+    pub async fn update_password_hash(&self, id: i32, password_hash: &str) -> Result<(), ApiError> {
+        let statement = sql!(self.database.scheme, "UPDATE users SET password = $1 WHERE id = $2");
+
+        let query = sqlx::query(&statement).bind(password_hash).bind(id);
+
+        query.execute(&self.database.pool).await.map_err(DatabaseError::from)?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all, fields(db.table = "users"))]
+    pub async fn update_role(&self, id: i32, role: &str) -> Result<(), ApiError> {
+        let statement = sql!(self.database.scheme, "UPDATE users SET role = $1 WHERE id = $2");
+
+        let query = sqlx::query(&statement).bind(role).bind(id);
+
+        query.execute(&self.database.pool).await.map_err(DatabaseError::from)?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all, fields(db.table = "users"))]
     pub async fn remove(&self, id: i32) -> Result<bool, ApiError> {
         let statement = sql!(self.database.scheme, "DELETE FROM users WHERE id = $1");
 
@@ -116,14 +178,15 @@ mod user_repository_tests {
     use std::sync::Arc;
 
     use super::*;
-    use crate::configs::{Argon2Hash, Database, Password, SchemaManager, Settings};
+    use crate::configs::{Argon2Hash, Database, Password, PasswordChecker, SchemaManager, Settings};
 
     #[tokio::test]
     async fn test_crud_operations() {
         let settings = Arc::new(Settings::new().unwrap());
         let database = Arc::new(Database::new(&settings, &SchemaManager::default()).await.unwrap());
         let password = Arc::new(Argon2Hash::new()) as Arc<dyn Password>;
-        let repo = UserRepository::new(&password, &database);
+        let policy = Arc::new(PasswordChecker::new(&settings));
+        let repo = UserRepository::new(&password, &policy, &database);
 
         let mock_dao = UserCreateDao {
             username: "test_user".to_string(),
@@ -138,4 +201,23 @@ mod user_repository_tests {
 
         assert!(result, "Record should be remove.");
     }
+
+    #[tokio::test]
+    async fn test_reject_empty_password() {
+        let settings = Arc::new(Settings::new().unwrap());
+        let database = Arc::new(Database::new(&settings, &SchemaManager::default()).await.unwrap());
+        let password = Arc::new(Argon2Hash::new()) as Arc<dyn Password>;
+        let policy = Arc::new(PasswordChecker::new(&settings));
+        let repo = UserRepository::new(&password, &policy, &database);
+
+        let mock_dao = UserCreateDao {
+            username: "test_empty_password_user".to_string(),
+            email: "test_empty_password_user@test_email.com".to_string(),
+            password: "".to_string(),
+        };
+        let user = repo.add(mock_dao).await;
+
+        assert!(user.is_err(), "Should reject an empty password.");
+        assert!(repo.find_by_username("test_empty_password_user").await.is_none(), "Record should not be created.");
+    }
 }
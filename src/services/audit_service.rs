@@ -0,0 +1,160 @@
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Map, Value};
+
+use crate::entities::{AUDIT_FAILURE, AUDIT_SUCCESS, AUDIT_TEXT_LENGTH};
+use crate::errors::ApiError;
+use crate::payload::{AuditContextDto, AuditEventCreateDao, AuditEventDto, AuditFilterDao, AuditQueryDto, AuditRecordDto};
+use crate::repository::AuditRepository;
+
+const DEFAULT_LIMIT: i64 = 100;
+const MAX_LIMIT: i64 = 1000;
+/// Fields whose values never enter the log; a change to them is still recorded.
+const REDACTED_FIELDS: [&str; 5] = ["password", "client_secret", "secret", "code_challenge", "state"];
+const REDACTED: &str = "[redacted]";
+
+/// Cuts client-supplied values such as the User-Agent to the column length, so oversized ones cannot make the insert
+/// fail and leave no trace.
+fn truncate(value: String) -> String {
+    match value.char_indices().nth(AUDIT_TEXT_LENGTH as usize) {
+        Some((index, _)) => value[..index].to_string(),
+        None => value,
+    }
+}
+
+pub struct AuditService {
+    audit_repo: Arc<AuditRepository>,
+}
+
+impl AuditService {
+    pub fn new(audit_repo: &Arc<AuditRepository>) -> Self {
+        Self {
+            audit_repo: Arc::clone(audit_repo),
+        }
+    }
+
+    /// Appends an event. A failed write is logged and swallowed so it never fails the audited action.
+    pub async fn record(&self, context: AuditContextDto, record: AuditRecordDto) {
+        let created_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs() as i64)
+            .unwrap_or_default();
+
+        let action = record.action.clone();
+        let dao = AuditEventCreateDao {
+            created_at,
+            action: truncate(record.action),
+            outcome: if record.success { AUDIT_SUCCESS } else { AUDIT_FAILURE }.to_string(),
+            actor_id: context.actor_id,
+            actor: context.actor.map(truncate),
+            target: record.target.map(truncate),
+            ip: context.ip.map(truncate),
+            user_agent: context.user_agent.map(truncate),
+            diff: record.diff.map(|diff| diff.to_string()),
+        };
+
+        if let Err(e) = self.audit_repo.add(dao).await {
+            tracing::warn!(action, error = %e, "failed to record audit event");
+        }
+    }
+
+    pub async fn list(&self, query: AuditQueryDto) -> Result<Vec<AuditEventDto>, ApiError> {
+        let filter = AuditFilterDao {
+            actor_id: query.actor_id,
+            action: query.action,
+            target: query.target,
+            since: query.since,
+            until: query.until,
+            limit: query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT),
+            offset: query.offset.unwrap_or_default().max(0),
+        };
+
+        let events = self.audit_repo.list(filter).await?;
+
+        Ok(events.into_iter().map(AuditEventDto::from).collect())
+    }
+
+    /// Top-level fields that differ between two JSON objects, as `{"field": {"from": .., "to": ..}}`, with the values
+    /// of credentials replaced by `[redacted]`.
+    pub fn diff(before: &Value, after: &Value) -> Value {
+        let empty = Map::new();
+        let before = before.as_object().unwrap_or(&empty);
+        let after = after.as_object().unwrap_or(&empty);
+
+        let changes = before
+            .keys()
+            .chain(after.keys().filter(|key| !before.contains_key(*key)))
+            .filter_map(|key| {
+                let from = before.get(key).unwrap_or(&Value::Null);
+                let to = after.get(key).unwrap_or(&Value::Null);
+
+                let redact = |value: &Value| match value {
+                    Value::Null => Value::Null,
+                    _ if REDACTED_FIELDS.contains(&key.as_str()) => REDACTED.into(),
+                    _ => value.clone(),
+                };
+
+                (from != to).then(|| (key.clone(), json!({ "from": redact(from), "to": redact(to) })))
+            })
+            .collect::<Map<_, _>>();
+
+        Value::Object(changes)
+    }
+}
+
+#[cfg(test)]
+mod audit_tests {
+    use crate::configs::{Database, SchemaManager, Settings};
+    use super::*;
+
+    #[tokio::test]
+    async fn test_truncate_oversized_user_agent() -> Result<(), ApiError> {
+        let settings = Arc::new(Settings::new()?);
+        let database = Arc::new(Database::new(&settings, &SchemaManager::default()).await?);
+        let audit_service = AuditService::new(&Arc::new(AuditRepository::new(&database)));
+
+        let context = AuditContextDto {
+            user_agent: Some("Mozilla/5.0 ".repeat(100)),
+            ..Default::default()
+        };
+        let record = AuditRecordDto {
+            action: "test_oversized_user_agent".into(),
+            success: false,
+            target: Some("é".repeat(300)),
+            diff: None,
+        };
+
+        audit_service.record(context, record).await;
+
+        let query = AuditQueryDto { action: Some("test_oversized_user_agent".into()), ..Default::default() };
+        let events = audit_service.list(query).await?;
+
+        assert_eq!(events.len(), 1, "An oversized User-Agent should still leave an audit event.");
+        assert_eq!(events[0].user_agent.as_ref().unwrap().chars().count(), AUDIT_TEXT_LENGTH as usize);
+        assert_eq!(events[0].target.as_ref().unwrap().chars().count(), AUDIT_TEXT_LENGTH as usize);
+        Ok(())
+    }
+
+    #[test]
+    fn test_diff_only_reports_changed_fields() {
+        let before = json!({ "role": "user", "email": "a@sieluna.com", "username": "a" });
+        let after = json!({ "role": "admin", "email": "a@sieluna.com", "locked": true });
+
+        let diff = AuditService::diff(&before, &after);
+
+        assert_eq!(
+            diff,
+            json!({
+                "role": { "from": "user", "to": "admin" },
+                "username": { "from": "a", "to": null },
+                "locked": { "from": null, "to": true }
+            })
+        );
+
+        let diff = AuditService::diff(&Value::Null, &json!({ "client_id": "a", "client_secret": "s3cr3t" }));
+
+        assert_eq!(diff["client_secret"], json!({ "from": null, "to": "[redacted]" }));
+        assert_eq!(diff["client_id"], json!({ "from": null, "to": "a" }));
+    }
+}
@@ -4,6 +4,7 @@ use crate::configs::Password;
 use crate::errors::{ApiError, AuthError, UserError};
 use crate::payload::{TokenClaimsDto, UserAuthDto, UserCreateDao, UserCreateDto, UserDto, UserIdentity};
 use crate::repository::UserRepository;
+use crate::services::METRICS;
 
 #[derive(Clone)]
 pub struct AuthService {
@@ -20,6 +21,14 @@ impl AuthService {
     }
 
     pub async fn authorization_user(&self, data: UserAuthDto) -> Result<UserDto, ApiError> {
+        let result = self.verify_credentials(data).await;
+
+        METRICS.record_login("password", result.is_ok());
+
+        result
+    }
+
+    async fn verify_credentials(&self, data: UserAuthDto) -> Result<UserDto, ApiError> {
         let UserAuthDto { identity, password } = data.into();
 
         let user = match identity {
@@ -37,12 +46,28 @@ impl AuthService {
         };
 
         if self.password.verify(&password, &user.password).unwrap_or(false) {
+            if self.password.needs_rehash(&user.password) {
+                self.rehash_password(user.id, &password).await;
+            }
+
             Ok(user.into())
         } else {
             Err(AuthError::InvalidPassword)?
         }
     }
 
+    async fn rehash_password(&self, id: i32, password: &str) {
+        let result = match self.password.hash(password) {
+            Ok(password_hash) => self.user_repo.update_password_hash(id, &password_hash).await,
+            Err(err) => Err(err.into()),
+        };
+
+        match result {
+            Ok(()) => tracing::info!("upgraded password hash of user {id}"),
+            Err(err) => tracing::warn!("failed to upgrade password hash of user {id}: {err}"),
+        }
+    }
+
     pub async fn create_user(&self, data: UserCreateDto) -> Result<UserDto, ApiError> {
         let UserCreateDto { username, email, password } = data.into();
 
@@ -60,6 +85,10 @@ impl AuthService {
     }
 
     pub async fn authentication_user(&self, data: TokenClaimsDto) -> Result<UserDto, ApiError> {
+        if data.scope.is_some() || data.client_id.is_some() {
+            Err(AuthError::InvalidToken("delegated tokens cannot be used as a session".into()))?
+        }
+
         let id = data.sub.parse::<i32>().map_err(|e| AuthError::InvalidToken(e.to_string()))?;
 
         self.user_repo.find(id).await.ok_or(UserError::UserNotFound.into()).map(UserDto::from)
@@ -0,0 +1,77 @@
+use std::collections::BTreeMap;
+use std::env::consts;
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::configs::{Database, Settings};
+use crate::payload::{BuildInfoDto, HealthCheckDto, PoolStatsDto, ReadinessDto, StatusDto};
+
+pub struct HealthService {
+    settings: Arc<Settings>,
+    database: Arc<Database>,
+    started: Instant,
+}
+
+impl HealthService {
+    pub fn new(settings: &Arc<Settings>, database: &Arc<Database>) -> Self {
+        Self {
+            settings: Arc::clone(settings),
+            database: Arc::clone(database),
+            started: Instant::now(),
+        }
+    }
+
+    /// The public probe: failures carry no detail, since errors and migration names describe internals.
+    pub async fn readiness(&self) -> ReadinessDto {
+        let mut readiness = self.detailed_readiness().await;
+
+        readiness.checks.values_mut().for_each(|check| check.detail = None);
+        readiness
+    }
+
+    async fn detailed_readiness(&self) -> ReadinessDto {
+        let mut checks = BTreeMap::new();
+
+        let database = match self.database.ping().await {
+            Ok(()) => HealthCheckDto { healthy: true, detail: None },
+            Err(e) => HealthCheckDto { healthy: false, detail: Some(e.to_string()) },
+        };
+        checks.insert("database".to_string(), database);
+
+        let migrations = match self.database.pending_migrations().await {
+            Ok(pending) if pending.is_empty() => HealthCheckDto { healthy: true, detail: None },
+            Ok(pending) => HealthCheckDto {
+                healthy: false,
+                detail: Some(format!("pending migrations: {pending:?}")),
+            },
+            Err(e) => HealthCheckDto { healthy: false, detail: Some(e.to_string()) },
+        };
+        checks.insert("migrations".to_string(), migrations);
+
+        ReadinessDto {
+            ready: checks.values().all(|check| check.healthy),
+            checks,
+        }
+    }
+
+    pub async fn status(&self) -> StatusDto {
+        let pool = &self.database.pool;
+
+        StatusDto {
+            build: BuildInfoDto {
+                version: env!("CARGO_PKG_VERSION").into(),
+                profile: if cfg!(debug_assertions) { "debug" } else { "release" }.into(),
+                os: consts::OS.into(),
+                arch: consts::ARCH.into(),
+            },
+            run_mode: self.settings.run_mode.clone(),
+            uptime_secs: self.started.elapsed().as_secs(),
+            database: PoolStatsDto {
+                size: pool.size(),
+                idle: pool.num_idle(),
+                max_connections: pool.options().get_max_connections(),
+            },
+            readiness: self.detailed_readiness().await,
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use std::sync::{Arc, LazyLock};
+
+use prometheus::{HistogramOpts, HistogramVec, IntCounterVec, IntGaugeVec, Opts, Registry, TextEncoder};
+
+use crate::configs::Database;
+
+pub static METRICS: LazyLock<MetricsRegistry> = LazyLock::new(MetricsRegistry::new);
+
+/// Process-wide Prometheus collectors, shared by middlewares and services.
+pub struct MetricsRegistry {
+    registry: Registry,
+    pub http_requests: IntCounterVec,
+    pub http_request_duration: HistogramVec,
+    pub logins: IntCounterVec,
+    pub database_connections: IntGaugeVec,
+}
+
+impl MetricsRegistry {
+    fn new() -> Self {
+        let registry = Registry::new_custom(Some("smarinth".into()), None).expect("valid metrics prefix");
+
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP requests by method, route and status."),
+            &["method", "route", "status"],
+        )
+        .unwrap();
+        let http_request_duration = HistogramVec::new(
+            HistogramOpts::new("http_request_duration_seconds", "HTTP request latency by method, route and status."),
+            &["method", "route", "status"],
+        )
+        .unwrap();
+        let logins = IntCounterVec::new(
+            Opts::new("logins_total", "Login attempts by method and outcome."),
+            &["method", "outcome"],
+        )
+        .unwrap();
+        let database_connections = IntGaugeVec::new(
+            Opts::new("database_connections", "Database pool connections by state."),
+            &["state"],
+        )
+        .unwrap();
+
+        registry.register(Box::new(http_requests.clone())).unwrap();
+        registry.register(Box::new(http_request_duration.clone())).unwrap();
+        registry.register(Box::new(logins.clone())).unwrap();
+        registry.register(Box::new(database_connections.clone())).unwrap();
+
+        Self { registry, http_requests, http_request_duration, logins, database_connections }
+    }
+
+    pub fn record_login(&self, method: &str, success: bool) {
+        let outcome = if success { "success" } else { "failure" };
+
+        self.logins.with_label_values(&[method, outcome]).inc();
+    }
+}
+
+pub struct MetricsService {
+    database: Arc<Database>,
+}
+
+impl MetricsService {
+    pub fn new(database: &Arc<Database>) -> Self {
+        Self {
+            database: Arc::clone(database),
+        }
+    }
+
+    /// Samples the gauges and renders every collector in the Prometheus text format.
+    pub fn render(&self) -> String {
+        let pool = &self.database.pool;
+        let size = pool.size() as i64;
+        let idle = pool.num_idle() as i64;
+        let connections = &METRICS.database_connections;
+
+        connections.with_label_values(&["idle"]).set(idle);
+        connections.with_label_values(&["active"]).set(size - idle);
+        connections.with_label_values(&["max"]).set(pool.options().get_max_connections() as i64);
+
+        TextEncoder::new()
+            .encode_to_string(&METRICS.registry.gather())
+            .unwrap_or_else(|e| format!("# failed to encode metrics: {e}\n"))
+    }
+}
@@ -1,7 +1,19 @@
+mod audit_service;
 mod auth_service;
+mod health_service;
+mod metrics_service;
+mod oauth_service;
+mod oidc_service;
+mod rate_limit_service;
 mod token_service;
 mod user_service;
 
+pub use audit_service::AuditService;
 pub use auth_service::AuthService;
+pub use health_service::HealthService;
+pub use metrics_service::{MetricsService, METRICS};
+pub use oauth_service::{ClientCredentials, OAuthService};
+pub use oidc_service::OidcService;
+pub use rate_limit_service::RateLimitService;
 pub use token_service::TokenService;
 pub use user_service::UserService;
@@ -0,0 +1,458 @@
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use sha2::{Digest, Sha256};
+use url::Url;
+
+use crate::configs::Password;
+use crate::entities::{AuthorizationGrant, OAuthClient};
+use crate::errors::{ApiError, OAuthError, UserError};
+use crate::payload::{
+    OAuthAuthorizeDto, OAuthClientCreateDao, OAuthClientCreateDto, OAuthClientCredentialsDto, OAuthConsentDto,
+    OAuthIntrospectionDto, OAuthRedirectDto, OAuthTokenDto, OAuthTokenRequestDto, UserDto,
+};
+use crate::repository::{ClientRepository, GrantRepository, UserRepository};
+use crate::services::TokenService;
+
+const AUTHORIZATION_CODE_TTL: Duration = Duration::from_secs(60);
+
+pub const AUTHORIZATION_CODE_GRANT: &str = "authorization_code";
+pub const CLIENT_CREDENTIALS_GRANT: &str = "client_credentials";
+
+/// Client id and secret presented by a client, from HTTP Basic authentication or the request body.
+pub struct ClientCredentials {
+    pub client_id: String,
+    pub client_secret: Option<String>,
+}
+
+pub struct OAuthService {
+    client_repo: Arc<ClientRepository>,
+    user_repo: Arc<UserRepository>,
+    token_service: Arc<TokenService>,
+    grant_repo: Arc<GrantRepository>,
+    password: Arc<dyn Password>,
+}
+
+impl OAuthService {
+    pub fn new(
+        client_repo: &Arc<ClientRepository>,
+        user_repo: &Arc<UserRepository>,
+        grant_repo: &Arc<GrantRepository>,
+        token_service: &Arc<TokenService>,
+        hasher: &Arc<dyn Password>,
+    ) -> Self {
+        Self {
+            client_repo: Arc::clone(client_repo),
+            user_repo: Arc::clone(user_repo),
+            token_service: Arc::clone(token_service),
+            grant_repo: Arc::clone(grant_repo),
+            password: Arc::clone(hasher),
+        }
+    }
+
+    pub async fn register_client(&self, data: OAuthClientCreateDto) -> Result<OAuthClientCredentialsDto, ApiError> {
+        let OAuthClientCreateDto { name, redirect_uris, scopes, grant_types, confidential } = data;
+
+        for grant_type in grant_types.iter() {
+            if grant_type != AUTHORIZATION_CODE_GRANT && grant_type != CLIENT_CREDENTIALS_GRANT {
+                Err(OAuthError::UnsupportedGrantType(grant_type.clone()))?
+            }
+        }
+
+        if !confidential && grant_types.iter().any(|grant_type| grant_type == CLIENT_CREDENTIALS_GRANT) {
+            Err(OAuthError::InvalidRequest("public clients cannot use the client credentials grant".into()))?
+        }
+
+        if redirect_uris.iter().any(|uri| Url::parse(uri).is_err()) {
+            Err(OAuthError::InvalidRequest("redirect uris must be absolute".into()))?
+        }
+
+        let client_id = TokenService::random_token();
+        let client_secret = confidential.then(TokenService::random_token);
+        let client_secret_hash = match &client_secret {
+            Some(secret) => Some(self.password.hash(secret)?),
+            None => None,
+        };
+
+        let client_data = OAuthClientCreateDao {
+            client_id: client_id.clone(),
+            client_secret: client_secret_hash,
+            name: name.clone(),
+            redirect_uris: redirect_uris.join(" "),
+            scopes: scopes.join(" "),
+            grant_types: grant_types.join(" "),
+        };
+        self.client_repo.add(client_data).await?;
+
+        Ok(OAuthClientCredentialsDto { client_id, client_secret, name, redirect_uris, scopes, grant_types })
+    }
+
+    /// Validates an authorization request and describes it for the consent screen.
+    pub async fn consent(&self, data: &OAuthAuthorizeDto) -> Result<OAuthConsentDto, ApiError> {
+        let (client, redirect_uri, scope) = self.validate_authorization(data).await?;
+
+        Ok(OAuthConsentDto {
+            client_id: client.client_id,
+            client_name: client.name,
+            redirect_uri,
+            scopes: scope.split_whitespace().map(str::to_string).collect(),
+        })
+    }
+
+    /// Records the user's decision and returns where the user agent should be sent next.
+    pub async fn authorize(&self, user: &UserDto, data: OAuthAuthorizeDto, approve: bool) -> Result<OAuthRedirectDto, ApiError> {
+        let (client, redirect_uri, scope) = self.validate_authorization(&data).await?;
+
+        let mut params = Vec::new();
+
+        if approve {
+            let code = TokenService::random_token();
+            let now = Self::now();
+
+            let grant = AuthorizationGrant {
+                code_hash: Self::code_hash(&code),
+                client_id: client.client_id,
+                user_id: user.id,
+                redirect_uri: redirect_uri.clone(),
+                scope,
+                code_challenge: data.code_challenge,
+                created_at: now,
+            };
+            self.grant_repo.add(&grant, now - AUTHORIZATION_CODE_TTL.as_secs() as i64).await?;
+
+            params.push(("code", code));
+        } else {
+            params.push(("error", OAuthError::AccessDenied.code().to_string()));
+        }
+
+        if let Some(state) = data.state {
+            params.push(("state", state));
+        }
+
+        let redirect_uri = Url::parse_with_params(&redirect_uri, &params)
+            .map_err(|e| OAuthError::InvalidRequest(e.to_string()))?;
+
+        Ok(OAuthRedirectDto { redirect_uri: redirect_uri.to_string() })
+    }
+
+    pub async fn exchange(&self, data: OAuthTokenRequestDto, credentials: ClientCredentials) -> Result<OAuthTokenDto, ApiError> {
+        let client = self.authenticate_client(&credentials).await?;
+
+        if !client.allows_grant(&data.grant_type) {
+            Err(OAuthError::UnauthorizedClient)?
+        }
+
+        let (user, scope) = match data.grant_type.as_str() {
+            AUTHORIZATION_CODE_GRANT => {
+                let code = data.code.ok_or(OAuthError::InvalidRequest("missing code".into()))?;
+                let verifier = data.code_verifier.ok_or(OAuthError::InvalidRequest("missing code_verifier".into()))?;
+
+                let grant = self
+                    .grant_repo
+                    .take(&Self::code_hash(&code))
+                    .await?
+                    .filter(|grant| Self::now() - grant.created_at < AUTHORIZATION_CODE_TTL.as_secs() as i64)
+                    .ok_or(OAuthError::InvalidGrant("unknown or expired code".into()))?;
+
+                if grant.client_id != client.client_id || data.redirect_uri.as_deref() != Some(grant.redirect_uri.as_str()) {
+                    Err(OAuthError::InvalidGrant("code was issued to another client or redirect uri".into()))?
+                }
+
+                if URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes())) != grant.code_challenge {
+                    Err(OAuthError::InvalidGrant("code_verifier does not match".into()))?
+                }
+
+                let user = self.user_repo.find(grant.user_id).await.ok_or(UserError::UserNotFound)?;
+
+                (Some(UserDto::from(user)), grant.scope)
+            }
+            CLIENT_CREDENTIALS_GRANT => {
+                if !client.is_confidential() {
+                    Err(OAuthError::UnauthorizedClient)?
+                }
+
+                let scope = data.scope.unwrap_or_else(|| client.scopes.clone());
+
+                if !client.allows_scope(&scope) {
+                    Err(OAuthError::InvalidScope(scope.clone()))?
+                }
+
+                (None, scope)
+            }
+            grant_type => Err(OAuthError::UnsupportedGrantType(grant_type.to_string()))?,
+        };
+
+        let token = self.token_service.generate_scoped_token(user, &client.client_id, &scope)?;
+
+        Ok(OAuthTokenDto {
+            access_token: token.token,
+            token_type: "Bearer".into(),
+            expires_in: token.exp - token.iat,
+            scope,
+        })
+    }
+
+    /// Token introspection as described by RFC 7662, for confidential clients and only their own tokens.
+    pub async fn introspect(&self, token: &str, credentials: ClientCredentials) -> Result<OAuthIntrospectionDto, ApiError> {
+        let client = self.authenticate_client(&credentials).await?;
+
+        if !client.is_confidential() {
+            Err(OAuthError::UnauthorizedClient)?
+        }
+
+        let Ok(token_data) = self.token_service.retrieve_access_token_claims(token) else {
+            return Ok(OAuthIntrospectionDto::default());
+        };
+        let claims = token_data.claims;
+
+        if claims.client_id.as_deref() != Some(client.client_id.as_str()) {
+            return Ok(OAuthIntrospectionDto::default());
+        }
+
+        Ok(OAuthIntrospectionDto {
+            active: true,
+            scope: claims.scope,
+            client_id: claims.client_id,
+            username: Some(claims.username).filter(|username| !username.is_empty()),
+            sub: Some(claims.sub),
+            iat: Some(claims.iat),
+            exp: Some(claims.exp),
+            token_type: Some("Bearer".into()),
+        })
+    }
+
+    fn code_hash(code: &str) -> String {
+        URL_SAFE_NO_PAD.encode(Sha256::digest(code.as_bytes()))
+    }
+
+    fn now() -> i64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs() as i64)
+            .unwrap_or_default()
+    }
+
+    async fn authenticate_client(&self, credentials: &ClientCredentials) -> Result<OAuthClient, OAuthError> {
+        let client = self
+            .client_repo
+            .find_by_client_id(&credentials.client_id)
+            .await
+            .ok_or(OAuthError::InvalidClient)?;
+
+        match (&client.client_secret, &credentials.client_secret) {
+            (Some(secret_hash), Some(secret)) if self.password.verify(secret, secret_hash).unwrap_or(false) => Ok(client),
+            (None, None) => Ok(client),
+            _ => Err(OAuthError::InvalidClient),
+        }
+    }
+
+    async fn validate_authorization(&self, data: &OAuthAuthorizeDto) -> Result<(OAuthClient, String, String), OAuthError> {
+        if data.response_type != "code" {
+            Err(OAuthError::InvalidRequest("response_type must be 'code'".into()))?
+        }
+
+        if data.code_challenge_method.as_deref().unwrap_or("plain") != "S256" {
+            Err(OAuthError::InvalidRequest("code_challenge_method must be 'S256'".into()))?
+        }
+
+        let client = self
+            .client_repo
+            .find_by_client_id(&data.client_id)
+            .await
+            .ok_or(OAuthError::InvalidClient)?;
+
+        if !client.allows_grant(AUTHORIZATION_CODE_GRANT) {
+            Err(OAuthError::UnauthorizedClient)?
+        }
+
+        let redirect_uri = match &data.redirect_uri {
+            Some(redirect_uri) if client.allows_redirect(redirect_uri) => redirect_uri.clone(),
+            Some(_) => Err(OAuthError::InvalidRequest("redirect_uri is not registered".into()))?,
+            None => match client.redirect_uris.split_whitespace().collect::<Vec<_>>()[..] {
+                [redirect_uri] => redirect_uri.to_string(),
+                _ => Err(OAuthError::InvalidRequest("redirect_uri is required".into()))?,
+            },
+        };
+
+        let scope = data.scope.clone().unwrap_or_else(|| client.scopes.clone());
+
+        if !client.allows_scope(&scope) {
+            Err(OAuthError::InvalidScope(scope.clone()))?
+        }
+
+        Ok((client, redirect_uri, scope))
+    }
+}
+
+#[cfg(test)]
+mod oauth_tests {
+    use crate::configs::{Database, MultiHash, PasswordChecker, SchemaManager, Settings};
+    use crate::payload::UserCreateDao;
+    use super::*;
+
+    struct OAuthEnvironment {
+        user_repo: Arc<UserRepository>,
+        oauth_service: OAuthService,
+    }
+
+    impl OAuthEnvironment {
+        async fn new() -> Result<Self, ApiError> {
+            let settings = Arc::new(Settings::new()?);
+            let database = Arc::new(Database::new(&settings, &SchemaManager::default()).await?);
+            let hasher = Arc::new(MultiHash::new()) as Arc<dyn Password>;
+            let policy = Arc::new(PasswordChecker::new(&settings));
+
+            let user_repo = Arc::new(UserRepository::new(&hasher, &policy, &database));
+            let client_repo = Arc::new(ClientRepository::new(&database));
+            let grant_repo = Arc::new(GrantRepository::new(&database));
+            let token_service = Arc::new(TokenService::new(&settings));
+
+            let oauth_service = OAuthService::new(&client_repo, &user_repo, &grant_repo, &token_service, &hasher);
+
+            Ok(Self { user_repo, oauth_service })
+        }
+    }
+
+    fn query_param(url: &str, name: &str) -> Option<String> {
+        Url::parse(url).unwrap().query_pairs().find(|(key, _)| key == name).map(|(_, value)| value.to_string())
+    }
+
+    #[tokio::test]
+    async fn test_authorization_code_with_pkce() -> Result<(), ApiError> {
+        let OAuthEnvironment { user_repo, oauth_service } = OAuthEnvironment::new().await?;
+
+        let user = user_repo
+            .add(UserCreateDao {
+                username: "test_oauth_owner".into(),
+                email: "test_oauth_owner@sieluna.com".into(),
+                password: "test_oauth_password".into(),
+            })
+            .await?;
+
+        let client = oauth_service
+            .register_client(OAuthClientCreateDto {
+                name: "Voice Assistant".into(),
+                redirect_uris: vec!["https://assistant.example/callback".into()],
+                scopes: vec!["devices:read".into(), "devices:write".into()],
+                grant_types: vec![AUTHORIZATION_CODE_GRANT.into()],
+                confidential: false,
+            })
+            .await?;
+
+        let verifier = TokenService::random_token();
+        let request = OAuthAuthorizeDto {
+            response_type: "code".into(),
+            client_id: client.client_id.clone(),
+            redirect_uri: None,
+            scope: Some("devices:read".into()),
+            state: Some("opaque".into()),
+            code_challenge: URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes())),
+            code_challenge_method: Some("S256".into()),
+        };
+
+        let consent = oauth_service.consent(&request).await?;
+
+        assert_eq!(consent.client_name, "Voice Assistant");
+        assert_eq!(consent.scopes, vec!["devices:read"]);
+
+        let redirect = oauth_service.authorize(&user.clone().into(), request, true).await?;
+        let code = query_param(&redirect.redirect_uri, "code").unwrap();
+
+        assert_eq!(query_param(&redirect.redirect_uri, "state").as_deref(), Some("opaque"));
+
+        let exchange = |code: String, code_verifier: String| OAuthTokenRequestDto {
+            grant_type: AUTHORIZATION_CODE_GRANT.into(),
+            code: Some(code),
+            redirect_uri: Some("https://assistant.example/callback".into()),
+            code_verifier: Some(code_verifier),
+            scope: None,
+            client_id: None,
+            client_secret: None,
+        };
+        let credentials = || ClientCredentials {
+            client_id: client.client_id.clone(),
+            client_secret: None,
+        };
+
+        let token = oauth_service.exchange(exchange(code.clone(), verifier.clone()), credentials()).await?;
+
+        assert_eq!(token.scope, "devices:read");
+        assert!(
+            oauth_service.token_service.retrieve_token_claims(&token.access_token).is_err(),
+            "Access tokens should not be accepted as sessions."
+        );
+
+        let replay = oauth_service.exchange(exchange(code, verifier), credentials()).await;
+
+        assert!(replay.is_err(), "Authorization codes should be single use.");
+
+        let introspection = oauth_service.introspect(&token.access_token, credentials()).await;
+
+        assert!(
+            matches!(introspection, Err(ApiError::OAuthError(OAuthError::UnauthorizedClient))),
+            "Public clients should not introspect tokens."
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_client_credentials() -> Result<(), ApiError> {
+        let OAuthEnvironment { oauth_service, .. } = OAuthEnvironment::new().await?;
+
+        let client = oauth_service
+            .register_client(OAuthClientCreateDto {
+                name: "Partner Integration".into(),
+                redirect_uris: vec![],
+                scopes: vec!["telemetry:read".into()],
+                grant_types: vec![CLIENT_CREDENTIALS_GRANT.into()],
+                confidential: true,
+            })
+            .await?;
+
+        let request = |scope: &str| OAuthTokenRequestDto {
+            grant_type: CLIENT_CREDENTIALS_GRANT.into(),
+            code: None,
+            redirect_uri: None,
+            code_verifier: None,
+            scope: Some(scope.into()),
+            client_id: None,
+            client_secret: None,
+        };
+        let credentials = |secret: Option<String>| ClientCredentials {
+            client_id: client.client_id.clone(),
+            client_secret: secret,
+        };
+
+        let token = oauth_service.exchange(request("telemetry:read"), credentials(client.client_secret.clone())).await?;
+
+        assert_eq!(token.token_type, "Bearer");
+
+        let wrong_secret = oauth_service.exchange(request("telemetry:read"), credentials(Some("wrong".into()))).await;
+        let wrong_scope = oauth_service.exchange(request("devices:write"), credentials(client.client_secret.clone())).await;
+
+        assert!(matches!(wrong_secret, Err(ApiError::OAuthError(OAuthError::InvalidClient))));
+        assert!(matches!(wrong_scope, Err(ApiError::OAuthError(OAuthError::InvalidScope(_)))));
+
+        let introspection = oauth_service.introspect(&token.access_token, credentials(client.client_secret.clone())).await?;
+
+        assert!(introspection.active);
+        assert_eq!(introspection.client_id, Some(client.client_id.clone()));
+
+        let other = oauth_service
+            .register_client(OAuthClientCreateDto {
+                name: "Other Integration".into(),
+                redirect_uris: vec![],
+                scopes: vec!["telemetry:read".into()],
+                grant_types: vec![CLIENT_CREDENTIALS_GRANT.into()],
+                confidential: true,
+            })
+            .await?;
+        let other_credentials = ClientCredentials { client_id: other.client_id, client_secret: other.client_secret };
+        let introspection = oauth_service.introspect(&token.access_token, other_credentials).await?;
+
+        assert!(!introspection.active, "Tokens of other clients should be reported inactive.");
+        Ok(())
+    }
+}
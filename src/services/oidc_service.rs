@@ -0,0 +1,563 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use url::Url;
+
+use crate::configs::{OidcProvider, Password, Settings};
+use crate::entities::{User, USER_ROLE};
+use crate::errors::{ApiError, OidcError, UserError};
+use crate::payload::{ExternalIdentityCreateDao, OidcCallbackDto, UserDto, UserImportDao};
+use crate::repository::{IdentityRepository, UserRepository};
+use crate::services::{TokenService, METRICS};
+
+const PENDING_LOGIN_TTL: Duration = Duration::from_secs(600);
+/// Logins can be started anonymously, so past this many the oldest pending one is dropped.
+const MAX_PENDING_LOGINS: usize = 10_000;
+/// Length bounds of usernames, as registration enforces them on `UserCreateDto`.
+const USERNAME_LENGTH: (usize, usize) = (3, 32);
+const FALLBACK_USERNAME: &str = "oidc_user";
+
+/// Fits a provider-supplied name to the registration rules, replacing disallowed characters with `_` and leaving
+/// room for `suffix`, which tells apart names that are already taken.
+fn local_username(raw: &str, suffix: u32) -> String {
+    let (min_length, max_length) = USERNAME_LENGTH;
+    let suffix = if suffix > 1 { format!("_{suffix}") } else { String::new() };
+
+    let mut base: String = raw
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.') { c } else { '_' })
+        .collect();
+
+    if base.trim_matches('_').len() < min_length {
+        base = FALLBACK_USERNAME.to_string();
+    }
+
+    base.truncate(max_length - suffix.len());
+
+    base + &suffix
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ProviderMetadata {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    jwks_uri: Option<String>,
+    #[serde(default)]
+    id_token_signing_alg_values_supported: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct IdTokenClaims {
+    sub: String,
+    email: Option<String>,
+    email_verified: Option<bool>,
+    preferred_username: Option<String>,
+    nonce: Option<String>,
+}
+
+struct PendingLogin {
+    provider: String,
+    nonce: String,
+    code_verifier: String,
+    created: Instant,
+}
+
+pub struct OidcService {
+    settings: Arc<Settings>,
+    user_repo: Arc<UserRepository>,
+    identity_repo: Arc<IdentityRepository>,
+    password: Arc<dyn Password>,
+    client: reqwest::Client,
+    metadata: Mutex<HashMap<String, ProviderMetadata>>,
+    pending: Mutex<HashMap<String, PendingLogin>>,
+}
+
+impl OidcService {
+    pub fn new(
+        settings: &Arc<Settings>,
+        user_repo: &Arc<UserRepository>,
+        identity_repo: &Arc<IdentityRepository>,
+        hasher: &Arc<dyn Password>,
+    ) -> Self {
+        Self {
+            settings: Arc::clone(settings),
+            user_repo: Arc::clone(user_repo),
+            identity_repo: Arc::clone(identity_repo),
+            password: Arc::clone(hasher),
+            client: reqwest::Client::new(),
+            metadata: Mutex::new(HashMap::new()),
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Builds the authorization URL the browser is redirected to, remembering state, nonce and PKCE verifier.
+    pub async fn start(&self, name: &str) -> Result<String, ApiError> {
+        let provider = self.provider(name)?;
+        let metadata = self.metadata(name, provider).await?;
+
+        let state = TokenService::random_token();
+        let nonce = TokenService::random_token();
+        let code_verifier = TokenService::random_token();
+        let code_challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()));
+
+        let url = Url::parse_with_params(
+            &metadata.authorization_endpoint,
+            &[
+                ("response_type", "code"),
+                ("client_id", provider.client_id.as_str()),
+                ("redirect_uri", provider.redirect_uri.as_str()),
+                ("scope", provider.scopes.join(" ").as_str()),
+                ("state", state.as_str()),
+                ("nonce", nonce.as_str()),
+                ("code_challenge", code_challenge.as_str()),
+                ("code_challenge_method", "S256"),
+            ],
+        )
+            .map_err(|e| OidcError::ProviderUnavailable(e.to_string()))?;
+
+        self.remember(state, PendingLogin {
+            provider: name.to_string(),
+            nonce,
+            code_verifier,
+            created: Instant::now(),
+        });
+
+        Ok(url.to_string())
+    }
+
+    fn remember(&self, state: String, login: PendingLogin) {
+        let mut pending = self.pending.lock().unwrap();
+        pending.retain(|_, login| login.created.elapsed() < PENDING_LOGIN_TTL);
+
+        if pending.len() >= MAX_PENDING_LOGINS {
+            let oldest = pending.iter().min_by_key(|(_, login)| login.created).map(|(state, _)| state.clone());
+            if let Some(oldest) = oldest {
+                pending.remove(&oldest);
+            }
+        }
+
+        pending.insert(state, login);
+    }
+
+    /// Exchanges the authorization code, validates the ID token and resolves the local user it belongs to.
+    pub async fn callback(&self, name: &str, data: OidcCallbackDto) -> Result<UserDto, ApiError> {
+        let result = self.complete_login(name, data).await;
+
+        METRICS.record_login("oidc", result.is_ok());
+
+        result
+    }
+
+    async fn complete_login(&self, name: &str, data: OidcCallbackDto) -> Result<UserDto, ApiError> {
+        let OidcCallbackDto { code, state, error, error_description } = data;
+
+        let login = self.pending.lock().unwrap().remove(&state).ok_or(OidcError::InvalidState)?;
+
+        if login.provider != name || login.created.elapsed() >= PENDING_LOGIN_TTL {
+            Err(OidcError::InvalidState)?
+        }
+
+        if let Some(error) = error {
+            let details = error_description.map_or(error.clone(), |description| format!("{error}: {description}"));
+            Err(OidcError::AuthorizationDenied(details))?
+        }
+
+        let code = code.ok_or(OidcError::AuthorizationDenied("missing authorization code".into()))?;
+
+        let provider = self.provider(name)?;
+        let metadata = self.metadata(name, provider).await?;
+
+        let response = self
+            .client
+            .post(&metadata.token_endpoint)
+            .form(&[
+                ("grant_type", "authorization_code"),
+                ("code", code.as_str()),
+                ("redirect_uri", provider.redirect_uri.as_str()),
+                ("client_id", provider.client_id.as_str()),
+                ("client_secret", provider.client_secret.expose()),
+                ("code_verifier", login.code_verifier.as_str()),
+            ])
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| OidcError::ProviderUnavailable(e.to_string()))?
+            .json::<TokenResponse>()
+            .await
+            .map_err(|e| OidcError::ProviderUnavailable(e.to_string()))?;
+
+        let claims = self.validate_id_token(provider, &metadata, &response.id_token).await?;
+
+        if claims.nonce.as_deref() != Some(login.nonce.as_str()) {
+            Err(OidcError::InvalidIdToken("nonce mismatch".into()))?
+        }
+
+        let user = self.link_user(name, provider, claims).await?;
+
+        Ok(user.into())
+    }
+
+    fn provider(&self, name: &str) -> Result<&OidcProvider, OidcError> {
+        self.settings.auth.oidc.get(name).ok_or_else(|| OidcError::UnknownProvider(name.to_string()))
+    }
+
+    async fn metadata(&self, name: &str, provider: &OidcProvider) -> Result<ProviderMetadata, OidcError> {
+        if let Some(metadata) = self.metadata.lock().unwrap().get(name) {
+            return Ok(metadata.clone());
+        }
+
+        let discovery_url = format!("{}/.well-known/openid-configuration", provider.issuer.trim_end_matches('/'));
+
+        let metadata = self
+            .client
+            .get(&discovery_url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| OidcError::ProviderUnavailable(e.to_string()))?
+            .json::<ProviderMetadata>()
+            .await
+            .map_err(|e| OidcError::ProviderUnavailable(e.to_string()))?;
+
+        // OpenID Connect Discovery 4.3: the document must be issued for exactly the configured issuer.
+        if metadata.issuer != provider.issuer {
+            Err(OidcError::ProviderUnavailable(format!(
+                "discovered issuer '{}' does not match the configured '{}'",
+                metadata.issuer, provider.issuer
+            )))?
+        }
+
+        self.metadata.lock().unwrap().insert(name.to_string(), metadata.clone());
+
+        Ok(metadata)
+    }
+
+    async fn validate_id_token(
+        &self,
+        provider: &OidcProvider,
+        metadata: &ProviderMetadata,
+        id_token: &str,
+    ) -> Result<IdTokenClaims, OidcError> {
+        let header = decode_header(id_token).map_err(|e| OidcError::InvalidIdToken(e.to_string()))?;
+        let algorithms = Self::signing_algorithms(provider, metadata);
+
+        if !algorithms.contains(&header.alg) {
+            Err(OidcError::InvalidIdToken(format!("{:?} is not an accepted signing algorithm", header.alg)))?
+        }
+
+        let key = match header.alg {
+            Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512 => {
+                DecodingKey::from_secret(provider.client_secret.expose().as_bytes())
+            }
+            _ => {
+                let jwks_uri = metadata
+                    .jwks_uri
+                    .as_ref()
+                    .ok_or(OidcError::InvalidIdToken("provider does not publish a JWKS".into()))?;
+                let jwks = self
+                    .client
+                    .get(jwks_uri)
+                    .send()
+                    .await
+                    .and_then(|response| response.error_for_status())
+                    .map_err(|e| OidcError::ProviderUnavailable(e.to_string()))?
+                    .json::<JwkSet>()
+                    .await
+                    .map_err(|e| OidcError::ProviderUnavailable(e.to_string()))?;
+                let jwk = match (&header.kid, &jwks.keys[..]) {
+                    (Some(kid), _) => jwks.find(kid),
+                    (None, [key]) => Some(key),
+                    (None, _) => Err(OidcError::InvalidIdToken("kid is required when the JWKS holds several keys".into()))?,
+                }
+                    .ok_or(OidcError::InvalidIdToken("signing key not found".into()))?;
+
+                DecodingKey::from_jwk(jwk).map_err(|e| OidcError::InvalidIdToken(e.to_string()))?
+            }
+        };
+
+        let mut validation = Validation::new(header.alg);
+        validation.algorithms = algorithms;
+        validation.set_audience(&[&provider.client_id]);
+        validation.set_issuer(&[&metadata.issuer]);
+
+        decode::<IdTokenClaims>(id_token, &key, &validation)
+            .map(|token| token.claims)
+            .map_err(|e| OidcError::InvalidIdToken(e.to_string()))
+    }
+
+    /// The configured algorithms, else the ones the provider advertises, else RS256 as OpenID Connect Discovery
+    /// defaults to; the token header never chooses on its own.
+    fn signing_algorithms(provider: &OidcProvider, metadata: &ProviderMetadata) -> Vec<Algorithm> {
+        if !provider.signing_algorithms.is_empty() {
+            return provider.signing_algorithms.clone();
+        }
+
+        let advertised: Vec<_> = metadata
+            .id_token_signing_alg_values_supported
+            .iter()
+            .filter_map(|algorithm| Algorithm::from_str(algorithm).ok())
+            .collect();
+
+        if advertised.is_empty() {
+            vec![Algorithm::RS256]
+        } else {
+            advertised
+        }
+    }
+
+    /// Finds the user linked to the external identity, or creates a new account. An existing account with the same
+    /// verified email is only linked when the provider is trusted with `trust_email`.
+    async fn link_user(&self, name: &str, provider: &OidcProvider, claims: IdTokenClaims) -> Result<User, ApiError> {
+        if let Some(identity) = self.identity_repo.find(name, &claims.sub).await {
+            return self.user_repo.find(identity.user_id).await.ok_or(UserError::UserNotFound.into());
+        }
+
+        let verified_email = claims.email.clone().filter(|_| claims.email_verified == Some(true));
+
+        let existing = match &verified_email {
+            Some(email) => self.user_repo.find_by_email(email).await,
+            None => None,
+        };
+
+        let user = match existing {
+            Some(user) if provider.trust_email => user,
+            Some(_) => Err(OidcError::EmailInUse)?,
+            None => self.create_user(name, &claims, verified_email).await?,
+        };
+
+        let identity_data = ExternalIdentityCreateDao {
+            user_id: user.id,
+            provider: name.to_string(),
+            subject: claims.sub,
+            email: claims.email,
+        };
+        self.identity_repo.add(identity_data).await?;
+
+        tracing::info!("linked {name} identity to user {}", user.id);
+
+        Ok(user)
+    }
+
+    async fn create_user(&self, name: &str, claims: &IdTokenClaims, email: Option<String>) -> Result<User, ApiError> {
+        let email = email.unwrap_or_else(|| format!("{}@{name}.oidc.invalid", claims.sub));
+
+        let base = claims
+            .preferred_username
+            .clone()
+            .or_else(|| email.split('@').next().map(str::to_string))
+            .unwrap_or_else(|| format!("{name}_{}", claims.sub));
+
+        let mut suffix = 1;
+        let mut username = local_username(&base, suffix);
+        while self.user_repo.find_by_username(&username).await.is_some() {
+            suffix += 1;
+            username = local_username(&base, suffix);
+        }
+
+        let user_data = UserImportDao {
+            username,
+            email,
+            password_hash: self.password.hash(&TokenService::random_token())?,
+            role: USER_ROLE.to_string(),
+        };
+
+        self.user_repo.import(user_data).await
+    }
+}
+
+#[cfg(test)]
+mod oidc_tests {
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use ntex::web::{self, test, types, App, HttpResponse};
+    use serde_json::json;
+
+    use crate::configs::{Database, MultiHash, Password, PasswordChecker, SchemaManager};
+    use super::*;
+
+    const CLIENT_ID: &str = "smarinth-test";
+    const CLIENT_SECRET: &str = "smarinth-test-secret";
+
+    #[derive(Clone, Default)]
+    struct MockIdentityProvider {
+        issuer: Arc<Mutex<String>>,
+        nonce: Arc<Mutex<String>>,
+        code_challenge: Arc<Mutex<String>>,
+    }
+
+    async fn discovery(idp: types::State<MockIdentityProvider>) -> HttpResponse {
+        let issuer = idp.issuer.lock().unwrap().clone();
+
+        HttpResponse::Ok().json(&json!({
+            "issuer": issuer,
+            "authorization_endpoint": format!("{issuer}/authorize"),
+            "token_endpoint": format!("{issuer}/token"),
+            "id_token_signing_alg_values_supported": ["HS256"],
+        }))
+    }
+
+    async fn token(
+        form: types::Form<HashMap<String, String>>,
+        idp: types::State<MockIdentityProvider>,
+    ) -> HttpResponse {
+        let verifier = form.get("code_verifier").cloned().unwrap_or_default();
+        let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+
+        if challenge != *idp.code_challenge.lock().unwrap() {
+            return HttpResponse::BadRequest().json(&json!({ "error": "invalid_grant" }));
+        }
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let claims = json!({
+            "iss": idp.issuer.lock().unwrap().clone(),
+            "aud": CLIENT_ID,
+            "sub": "mock-subject",
+            "email": "test_oidc_user@sieluna.com",
+            "email_verified": true,
+            "preferred_username": "test_oidc_user",
+            "nonce": idp.nonce.lock().unwrap().clone(),
+            "iat": now,
+            "exp": now + 300,
+        });
+        let id_token = encode(&Header::default(), &claims, &EncodingKey::from_secret(CLIENT_SECRET.as_bytes())).unwrap();
+
+        HttpResponse::Ok().json(&json!({ "access_token": "mock", "token_type": "Bearer", "id_token": id_token }))
+    }
+
+    fn query_param(url: &str, name: &str) -> String {
+        Url::parse(url).unwrap().query_pairs().find(|(key, _)| key == name).unwrap().1.to_string()
+    }
+
+    #[test]
+    fn test_local_username() {
+        assert_eq!(local_username("Jane Doe", 1), "Jane_Doe");
+        assert_eq!(local_username("jane", 2), "jane_2");
+        assert_eq!(local_username("ü", 1), FALLBACK_USERNAME);
+        assert_eq!(local_username(&"a".repeat(40), 12), format!("{}_12", "a".repeat(29)));
+    }
+
+    async fn login(service: &OidcService, idp: &MockIdentityProvider, name: &str) -> Result<UserDto, ApiError> {
+        let authorize_url = service.start(name).await?;
+
+        assert_eq!(query_param(&authorize_url, "code_challenge_method"), "S256");
+
+        *idp.nonce.lock().unwrap() = query_param(&authorize_url, "nonce");
+        *idp.code_challenge.lock().unwrap() = query_param(&authorize_url, "code_challenge");
+
+        let callback = OidcCallbackDto {
+            code: Some("mock-code".into()),
+            state: query_param(&authorize_url, "state"),
+            error: None,
+            error_description: None,
+        };
+
+        service.callback(name, callback).await
+    }
+
+    #[ntex::test]
+    async fn test_login_with_mock_provider() -> Result<(), ApiError> {
+        let idp = MockIdentityProvider::default();
+        let server_idp = idp.clone();
+        let server = test::server(move || {
+            App::new()
+                .state(server_idp.clone())
+                .route("/.well-known/openid-configuration", web::get().to(discovery))
+                .route("/token", web::post().to(token))
+        })
+            .await;
+        *idp.issuer.lock().unwrap() = server.url("/").trim_end_matches('/').to_string();
+
+        let provider = |trust_email| OidcProvider {
+            issuer: idp.issuer.lock().unwrap().clone(),
+            client_id: CLIENT_ID.into(),
+            client_secret: CLIENT_SECRET.into(),
+            redirect_uri: "http://127.0.0.1:8080/auth/oidc/mock/callback".into(),
+            scopes: vec!["openid".into(), "email".into()],
+            trust_email,
+            signing_algorithms: Vec::new(),
+        };
+        let mut settings = Settings::new()?;
+        settings.auth.oidc.insert("mock".into(), provider(false));
+        settings.auth.oidc.insert("mock_untrusted".into(), provider(false));
+        settings.auth.oidc.insert("mock_trusted".into(), provider(true));
+        settings.auth.oidc.insert("mock_rs256".into(), OidcProvider {
+            signing_algorithms: vec![Algorithm::RS256],
+            ..provider(false)
+        });
+        settings.auth.oidc.insert("mock_wrong_issuer".into(), OidcProvider {
+            issuer: format!("{}/", idp.issuer.lock().unwrap()),
+            ..provider(false)
+        });
+        let settings = Arc::new(settings);
+
+        let database = Arc::new(Database::new(&settings, &SchemaManager::default()).await?);
+        let hasher = Arc::new(MultiHash::new()) as Arc<dyn Password>;
+        let policy = Arc::new(PasswordChecker::new(&settings));
+        let user_repo = Arc::new(UserRepository::new(&hasher, &policy, &database));
+        let identity_repo = Arc::new(IdentityRepository::new(&database));
+        let service = OidcService::new(&settings, &user_repo, &identity_repo, &hasher);
+
+        let user = login(&service, &idp, "mock").await?;
+
+        assert_eq!(user.email, "test_oidc_user@sieluna.com");
+        assert_eq!(login(&service, &idp, "mock").await?.id, user.id, "Second login should reuse the linked identity.");
+
+        let untrusted = login(&service, &idp, "mock_untrusted").await;
+
+        assert!(
+            matches!(untrusted, Err(ApiError::OidcError(OidcError::EmailInUse))),
+            "Untrusted providers should not take over accounts by email."
+        );
+        assert_eq!(login(&service, &idp, "mock_trusted").await?.id, user.id, "Trusted providers should link by email.");
+
+        let unexpected_algorithm = login(&service, &idp, "mock_rs256").await;
+
+        assert!(
+            matches!(unexpected_algorithm, Err(ApiError::OidcError(OidcError::InvalidIdToken(_)))),
+            "The token header should not pick the algorithm."
+        );
+
+        assert!(
+            matches!(service.start("mock_wrong_issuer").await, Err(ApiError::OidcError(OidcError::ProviderUnavailable(_)))),
+            "A discovery document for another issuer should be rejected."
+        );
+
+        for index in 0..=MAX_PENDING_LOGINS {
+            service.remember(format!("test-state-{index}"), PendingLogin {
+                provider: "mock".into(),
+                nonce: String::new(),
+                code_verifier: String::new(),
+                created: Instant::now(),
+            });
+        }
+
+        assert_eq!(service.pending.lock().unwrap().len(), MAX_PENDING_LOGINS, "Pending logins should be bounded.");
+
+        let replayed = OidcCallbackDto {
+            code: Some("mock-code".into()),
+            state: "unknown-state".into(),
+            error: None,
+            error_description: None,
+        };
+
+        assert!(service.callback("mock", replayed).await.is_err(), "Unknown state should be rejected.");
+        Ok(())
+    }
+}
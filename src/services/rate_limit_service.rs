@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::configs::{RateLimitPolicy, RateLimitStore, Reloadable, Settings};
+use crate::entities::RateLimitBucket;
+use crate::errors::{ConfigError, RateLimitError};
+use crate::payload::RateLimitStatusDto;
+use crate::repository::RateLimitRepository;
+
+/// Bound on the in-memory store; once reached, idle buckets are swept and then the least recently used evicted.
+const MAX_MEMORY_BUCKETS: usize = 10_000;
+
+enum BucketStore {
+    Memory(Mutex<HashMap<String, RateLimitBucket>>),
+    Database(Arc<RateLimitRepository>),
+}
+
+pub struct RateLimitService {
+    policies: RwLock<Vec<RateLimitPolicy>>,
+    store: BucketStore,
+    fail_open: bool,
+}
+
+impl RateLimitService {
+    pub fn with_settings(
+        settings: &Arc<Settings>,
+        rate_limit_repo: &Arc<RateLimitRepository>,
+    ) -> Result<Self, ConfigError> {
+        let store = match settings.rate_limit.store {
+            RateLimitStore::Memory => BucketStore::Memory(Mutex::new(HashMap::new())),
+            RateLimitStore::Database => BucketStore::Database(Arc::clone(rate_limit_repo)),
+        };
+
+        Ok(Self {
+            policies: RwLock::new(settings.rate_limit.active_policies()?),
+            store,
+            fail_open: settings.rate_limit.fail_open,
+        })
+    }
+
+    /// The policy with the longest scope matching `path`; among equal scopes the last one configured wins.
+    pub fn policy(&self, path: &str) -> Option<RateLimitPolicy> {
+        self.policies
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .filter(|policy| {
+                let scope = policy.scope.trim_end_matches('/');
+
+                path.strip_prefix(scope).is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+            })
+            .max_by_key(|policy| policy.scope.trim_end_matches('/').len())
+            .cloned()
+    }
+
+    /// Takes one token from the bucket of `subject` under `policy`; `None` when the store failed open and no
+    /// bucket state is known.
+    pub async fn acquire(
+        &self,
+        policy: &RateLimitPolicy,
+        subject: &str,
+    ) -> Result<Option<RateLimitStatusDto>, RateLimitError> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_millis() as i64)
+            .unwrap_or_default();
+        let bucket_key = format!("{}|{subject}", policy.scope);
+
+        let mut allowed = false;
+        let apply = |bucket: Option<RateLimitBucket>| {
+            let mut bucket = bucket.unwrap_or_else(|| RateLimitBucket::full(&bucket_key, policy.capacity, now));
+
+            bucket.refill(policy.capacity, policy.period, now);
+            allowed = bucket.take();
+
+            bucket
+        };
+
+        let bucket = match &self.store {
+            BucketStore::Memory(buckets) => {
+                let mut buckets = buckets.lock().unwrap_or_else(|e| e.into_inner());
+
+                if buckets.len() >= MAX_MEMORY_BUCKETS && !buckets.contains_key(&bucket_key) {
+                    let policies = self.policies.read().unwrap_or_else(|e| e.into_inner());
+                    let idle = policies.iter().map(|policy| policy.period).max().unwrap_or_default() as i64 * 1000;
+                    buckets.retain(|_, bucket| now - bucket.updated_at < idle);
+
+                    if buckets.len() >= MAX_MEMORY_BUCKETS {
+                        let oldest = buckets.iter().min_by_key(|(_, bucket)| bucket.updated_at).map(|(key, _)| key.clone());
+                        if let Some(key) = oldest {
+                            buckets.remove(&key);
+                        }
+                    }
+                }
+
+                let bucket = apply(buckets.remove(&bucket_key));
+                buckets.insert(bucket_key.clone(), bucket.clone());
+
+                bucket
+            }
+            BucketStore::Database(rate_limit_repo) => match rate_limit_repo.update(&bucket_key, apply).await {
+                Ok(bucket) => bucket,
+                Err(e) if self.fail_open => {
+                    tracing::warn!(error = %e, "rate limit store unavailable, allowing request");
+
+                    return Ok(None);
+                }
+                Err(e) => {
+                    tracing::error!(error = %e, "rate limit store unavailable, rejecting request");
+
+                    return Err(RateLimitError::StoreUnavailable(e.to_string()));
+                }
+            },
+        };
+
+        let status = RateLimitStatusDto {
+            limit: policy.capacity,
+            period: policy.period,
+            remaining: bucket.remaining(),
+            reset: bucket.seconds_until(policy.capacity, policy.capacity, policy.period),
+            retry_after: (!allowed).then(|| bucket.seconds_until(1, policy.capacity, policy.period).max(1)),
+        };
+
+        if allowed {
+            Ok(Some(status))
+        } else {
+            Err(RateLimitError::TooManyRequests(status))
+        }
+    }
+}
+
+/// Policies apply live; switching the bucket store needs a restart.
+impl Reloadable for RateLimitService {
+    fn validate(&self, settings: &Settings) -> Result<(), ConfigError> {
+        settings.rate_limit.active_policies().map(|_| ())
+    }
+
+    fn reload(&self, settings: &Settings) -> Result<(), ConfigError> {
+        let policies = settings.rate_limit.active_policies()?;
+
+        *self.policies.write().unwrap_or_else(|e| e.into_inner()) = policies;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod rate_limit_tests {
+    use crate::configs::{Database, RateLimit, RateLimitKey, SchemaManager};
+    use crate::errors::ApiError;
+    use super::*;
+
+    fn policy(scope: &str, capacity: u32) -> RateLimitPolicy {
+        RateLimitPolicy {
+            scope: scope.into(),
+            key: RateLimitKey::Ip,
+            capacity,
+            period: 60,
+        }
+    }
+
+    async fn rate_limit_service(store: RateLimitStore, policies: Vec<RateLimitPolicy>) -> Result<RateLimitService, ApiError> {
+        let mut settings = Settings::new()?;
+        settings.rate_limit = RateLimit { enabled: true, store, fail_open: false, policies };
+        let settings = Arc::new(settings);
+
+        let database = Arc::new(Database::new(&settings, &SchemaManager::default()).await?);
+        let rate_limit_repo = Arc::new(RateLimitRepository::new(&database));
+
+        Ok(RateLimitService::with_settings(&settings, &rate_limit_repo)?)
+    }
+
+    #[ntex::test]
+    async fn test_longest_scope_wins() -> Result<(), ApiError> {
+        let service = rate_limit_service(
+            RateLimitStore::Memory,
+            vec![policy("/", 100), policy("/auth", 10), policy("/auth/", 5)],
+        )
+            .await?;
+
+        assert_eq!(service.policy("/auth/register").map(|policy| policy.capacity), Some(5));
+        assert_eq!(service.policy("/authorize").map(|policy| policy.capacity), Some(100));
+        assert_eq!(service.policy("/api/oauth/authorize").map(|policy| policy.capacity), Some(100));
+        Ok(())
+    }
+
+    #[ntex::test]
+    async fn test_memory_store_is_bounded() -> Result<(), ApiError> {
+        let service = rate_limit_service(RateLimitStore::Memory, vec![policy("/", 1)]).await?;
+        let policy = service.policy("/").unwrap();
+
+        for index in 0..=MAX_MEMORY_BUCKETS {
+            service.acquire(&policy, &format!("ip:10.0.{}.{}", index / 256, index % 256)).await?;
+        }
+
+        let BucketStore::Memory(buckets) = &service.store else { unreachable!() };
+        assert_eq!(buckets.lock().unwrap().len(), MAX_MEMORY_BUCKETS, "Active buckets should not grow past the bound.");
+        Ok(())
+    }
+
+    #[ntex::test]
+    async fn test_database_bucket_is_shared() -> Result<(), ApiError> {
+        let policies = vec![policy("/test_database_bucket", 2)];
+        let first = rate_limit_service(RateLimitStore::Database, policies.clone()).await?;
+        let second = rate_limit_service(RateLimitStore::Database, policies).await?;
+        let policy = first.policy("/test_database_bucket").unwrap();
+
+        let status = first.acquire(&policy, "ip:127.0.0.1").await?;
+        assert_eq!(status.map(|status| status.remaining), Some(1));
+
+        second.acquire(&policy, "ip:127.0.0.1").await?;
+
+        match first.acquire(&policy, "ip:127.0.0.1").await {
+            Err(RateLimitError::TooManyRequests(status)) => {
+                assert_eq!(status.remaining, 0);
+                assert_eq!(status.retry_after, Some(30), "One token is earned every 30 seconds.");
+            }
+            Err(e) => panic!("Unexpected rate limit error: {e}"),
+            Ok(_) => panic!("Both instances should drain the same bucket."),
+        }
+        Ok(())
+    }
+
+    #[ntex::test]
+    async fn test_concurrent_first_requests_share_bucket() -> Result<(), ApiError> {
+        let policies = vec![policy("/test_concurrent_bucket", 5)];
+        let first = rate_limit_service(RateLimitStore::Database, policies.clone()).await?;
+        let second = rate_limit_service(RateLimitStore::Database, policies).await?;
+        let policy = first.policy("/test_concurrent_bucket").unwrap();
+
+        let (a, b) = tokio::join!(first.acquire(&policy, "ip:127.0.0.2"), second.acquire(&policy, "ip:127.0.0.2"));
+
+        assert!(a?.is_some() && b?.is_some(), "Creating the same bucket twice should not fail either request.");
+        Ok(())
+    }
+}
@@ -1,36 +1,57 @@
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, TokenData, Validation};
 use jsonwebtoken::errors::ErrorKind;
 
-use crate::configs::Settings;
-use crate::errors::AuthError;
+use crate::configs::{Reloadable, Settings};
+use crate::errors::{AuthError, ConfigError};
 use crate::payload::{TokenClaimsDto, TokenDto, UserDto};
 
+/// Audience of the session tokens accepted by `JWTAuth`.
+pub const SESSION_AUDIENCE: &str = "smarinth";
+/// Audience of the access tokens issued to OAuth clients, which are never accepted as sessions.
+pub const ACCESS_TOKEN_AUDIENCE: &str = "smarinth:oauth";
+
 #[derive(Clone)]
 pub struct TokenService {
-    expiration: u64,
+    expiration: Arc<AtomicU64>,
     secret: String,
 }
 
 impl TokenService {
     pub fn new(settings: &Arc<Settings>) -> Self {
         Self {
-            expiration: settings.auth.expiration,
-            secret: settings.auth.secret.clone(),
+            expiration: Arc::new(AtomicU64::new(settings.auth.expiration)),
+            secret: settings.auth.secret.expose().to_string(),
         }
     }
 
-    pub fn retrieve_token_claims(
-        &self,
-        token: &str,
-    ) -> Result<TokenData<TokenClaimsDto>, AuthError> {
-        match decode::<TokenClaimsDto>(
-            token,
-            &DecodingKey::from_secret(self.secret.as_ref()),
-            &Validation::default(),
-        ) {
+    /// Claims of a session token; OAuth access tokens are rejected.
+    pub fn retrieve_token_claims(&self, token: &str) -> Result<TokenData<TokenClaimsDto>, AuthError> {
+        let token_data = self.decode_claims(token, SESSION_AUDIENCE)?;
+
+        if token_data.claims.scope.is_some() || token_data.claims.client_id.is_some() {
+            Err(AuthError::InvalidToken(token.to_string()))?
+        }
+
+        Ok(token_data)
+    }
+
+    /// Claims of an OAuth access token; session tokens are rejected.
+    pub fn retrieve_access_token_claims(&self, token: &str) -> Result<TokenData<TokenClaimsDto>, AuthError> {
+        self.decode_claims(token, ACCESS_TOKEN_AUDIENCE)
+    }
+
+    fn decode_claims(&self, token: &str, audience: &str) -> Result<TokenData<TokenClaimsDto>, AuthError> {
+        let mut validation = Validation::default();
+        validation.set_audience(&[audience]);
+
+        match decode::<TokenClaimsDto>(token, &DecodingKey::from_secret(self.secret.as_ref()), &validation) {
             Ok(claims) => Ok(claims),
             Err(err) => match err.kind() {
                 ErrorKind::ExpiredSignature => Err(AuthError::TokenExpired)?,
@@ -40,25 +61,83 @@ impl TokenService {
     }
 
     pub fn generate_token(&self, user: UserDto) -> Result<TokenDto, AuthError> {
-        let iat = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .expect("Time went backwards")
-            .as_secs();
-        let exp = iat + self.expiration;
+        let (iat, exp) = self.lifetime();
 
         let claims = TokenClaimsDto {
             sub: user.id.to_string(),
+            aud: SESSION_AUDIENCE.into(),
             username: user.username,
             email: user.email,
             iat,
             exp,
+            scope: None,
+            client_id: None,
+        };
+
+        self.encode_claims(&claims)
+    }
+
+    /// Issues a token delegated to a client application, on behalf of a user or of the client itself.
+    pub fn generate_scoped_token(&self, user: Option<UserDto>, client_id: &str, scope: &str) -> Result<TokenDto, AuthError> {
+        let (iat, exp) = self.lifetime();
+
+        let claims = match user {
+            Some(user) => TokenClaimsDto {
+                sub: user.id.to_string(),
+                aud: ACCESS_TOKEN_AUDIENCE.into(),
+                username: user.username,
+                email: user.email,
+                iat,
+                exp,
+                scope: Some(scope.to_string()),
+                client_id: Some(client_id.to_string()),
+            },
+            None => TokenClaimsDto {
+                sub: client_id.to_string(),
+                aud: ACCESS_TOKEN_AUDIENCE.into(),
+                username: String::new(),
+                email: String::new(),
+                iat,
+                exp,
+                scope: Some(scope.to_string()),
+                client_id: Some(client_id.to_string()),
+            },
         };
 
+        self.encode_claims(&claims)
+    }
+
+    /// Random URL-safe token with 256 bits of entropy, for secrets, codes and states.
+    pub fn random_token() -> String {
+        let mut bytes = [0u8; 32];
+        OsRng.fill_bytes(&mut bytes);
+
+        URL_SAFE_NO_PAD.encode(bytes)
+    }
+
+    fn lifetime(&self) -> (u64, u64) {
+        let iat = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_secs();
+
+        (iat, iat + self.expiration.load(Ordering::Relaxed))
+    }
+
+    fn encode_claims(&self, claims: &TokenClaimsDto) -> Result<TokenDto, AuthError> {
         let encoding_key = EncodingKey::from_secret(self.secret.as_ref());
 
-        let token = encode(&Header::default(), &claims, &encoding_key)
+        let token = encode(&Header::default(), claims, &encoding_key)
             .map_err(|e| AuthError::TokenCreationError(e.to_string()))?;
 
-        Ok(TokenDto { token, iat, exp })
+        Ok(TokenDto { token, iat: claims.iat, exp: claims.exp })
+    }
+}
+
+impl Reloadable for TokenService {
+    fn reload(&self, settings: &Settings) -> Result<(), ConfigError> {
+        self.expiration.store(settings.auth.expiration, Ordering::Relaxed);
+
+        Ok(())
     }
 }
@@ -1,7 +1,11 @@
 use std::sync::Arc;
 
+use crate::entities::{ADMIN_ROLE, USER_ROLE};
 use crate::errors::{ApiError, UserError};
-use crate::payload::{UserDto, UserIdentity, UserUpdateDao, UserUpdateDto};
+use crate::payload::{
+    UserDto, UserIdentity, UserImportDao, UserImportDto, UserImportRejectionDto, UserImportReportDto, UserUpdateDao,
+    UserUpdateDto,
+};
 use crate::repository::user_repository::UserRepository;
 
 #[derive(Clone)]
@@ -49,4 +53,51 @@ impl UserService {
 
         Ok(user.into())
     }
+
+    pub async fn grant_role(&self, identity: UserIdentity, role: &str) -> Result<UserDto, ApiError> {
+        let id = self.find_user(identity).await?.id;
+
+        self.user_repo.update_role(id, role).await?;
+
+        self.find_user(UserIdentity::Id(id)).await
+    }
+
+    pub async fn delete_user(&self, identity: UserIdentity) -> Result<UserDto, ApiError> {
+        let user = self.find_user(identity).await?;
+
+        self.user_repo.remove(user.id).await?;
+
+        Ok(user)
+    }
+
+    pub async fn import_users(&self, data: Vec<UserImportDto>) -> Result<UserImportReportDto, ApiError> {
+        let mut imported = Vec::new();
+        let mut rejected = Vec::new();
+
+        for UserImportDto { username, email, password_hash, role } in data {
+            let user_exist = self.user_repo.find_by_username(&username).await.is_some();
+            let email_exist = self.user_repo.find_by_email(&email).await.is_some();
+
+            if user_exist || email_exist {
+                let reason = UserError::UserAlreadyExists.to_string();
+                rejected.push(UserImportRejectionDto { username, reason });
+                continue;
+            }
+
+            let role = role.unwrap_or_else(|| USER_ROLE.to_string());
+            if ![ADMIN_ROLE, USER_ROLE].contains(&role.as_str()) {
+                rejected.push(UserImportRejectionDto { username, reason: UserError::InvalidRole(role).to_string() });
+                continue;
+            }
+
+            let user_data = UserImportDao { username: username.clone(), email, password_hash, role };
+
+            match self.user_repo.import(user_data).await {
+                Ok(user) => imported.push(user.into()),
+                Err(err) => rejected.push(UserImportRejectionDto { username, reason: err.to_string() }),
+            }
+        }
+
+        Ok(UserImportReportDto { imported, rejected })
+    }
 }
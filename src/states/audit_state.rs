@@ -0,0 +1,8 @@
+use std::sync::Arc;
+
+use crate::services::AuditService;
+
+#[derive(Clone)]
+pub struct AuditState {
+    pub audit_service: Arc<AuditService>,
+}
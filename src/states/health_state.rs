@@ -0,0 +1,8 @@
+use std::sync::Arc;
+
+use crate::services::HealthService;
+
+#[derive(Clone)]
+pub struct HealthState {
+    pub health_service: Arc<HealthService>,
+}
@@ -0,0 +1,8 @@
+use std::sync::Arc;
+
+use crate::services::MetricsService;
+
+#[derive(Clone)]
+pub struct MetricsState {
+    pub metrics_service: Arc<MetricsService>,
+}
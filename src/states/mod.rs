@@ -1,5 +1,17 @@
+mod audit_state;
 mod auth_state;
+mod health_state;
+mod metrics_state;
+mod oauth_state;
+mod oidc_state;
+mod rate_limit_state;
 mod user_state;
 
+pub use audit_state::AuditState;
 pub use auth_state::AuthState;
+pub use health_state::HealthState;
+pub use metrics_state::MetricsState;
+pub use oauth_state::OAuthState;
+pub use oidc_state::OidcState;
+pub use rate_limit_state::RateLimitState;
 pub use user_state::UserState;
@@ -0,0 +1,8 @@
+use std::sync::Arc;
+
+use crate::services::OAuthService;
+
+#[derive(Clone)]
+pub struct OAuthState {
+    pub oauth_service: Arc<OAuthService>,
+}
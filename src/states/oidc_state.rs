@@ -0,0 +1,9 @@
+use std::sync::Arc;
+
+use crate::services::{OidcService, TokenService};
+
+#[derive(Clone)]
+pub struct OidcState {
+    pub oidc_service: Arc<OidcService>,
+    pub token_service: Arc<TokenService>,
+}
@@ -0,0 +1,9 @@
+use std::sync::Arc;
+
+use crate::services::{RateLimitService, TokenService};
+
+#[derive(Clone)]
+pub struct RateLimitState {
+    pub rate_limit_service: Arc<RateLimitService>,
+    pub token_service: Arc<TokenService>,
+}